@@ -12,6 +12,7 @@ use super::error::Error;
 use actor_info::ActorInfo;
 use args::Args;
 use dialogue_name::DialogueName;
+use location::NodeKey;
 use nodes::*;
 
 use serde::{Deserialize, Serialize};
@@ -26,12 +27,16 @@ pub struct Dialogue {
     pub actor: ActorInfo,
     #[serde(default, skip_serializing_if = "Args::is_empty")]
     pub args: Args,
+    /// Node the runner jumps to when a script error is recovered with
+    /// `Recovery::JumpToErrorNode`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<NodeKey>,
     pub nodes: Nodes,
 }
 
 impl Dialogue {
     pub fn actor_num(&self) -> u8 {
-        *self.actor.num
+        self.actor.count()
     }
 
     pub fn is_message_allowed(&self) -> bool {
@@ -112,6 +117,57 @@ nodes:
         assert!(matches!(result, Err(Error::OwnerOutOfRange { .. })));
     }
 
+    #[test]
+    fn deserialize_listener_out_of_range() {
+        let raw_dialogue = r#"
+actor:
+  roles: [hero, merchant]
+nodes:
+  main:
+  - confirm: Deal?
+    owner: merchant
+    options:
+      message:
+        listeners: [hero, 2]
+"#
+        .trim_start();
+        let result = raw_dialogue.parse::<Dialogue>();
+        assert!(matches!(
+            result,
+            Err(Error::ListenerOutOfRange { listener: 2, .. })
+        ));
+
+        let unknown = raw_dialogue.replace("[hero, 2]", "[villain]");
+        assert!(matches!(
+            unknown.parse::<Dialogue>(),
+            Err(Error::UnknownRole { .. })
+        ));
+        let valid = raw_dialogue.replace("[hero, 2]", "[hero, 1]");
+        assert!(valid.parse::<Dialogue>().is_ok());
+    }
+
+    #[test]
+    fn deserialize_invalid_speed() {
+        let raw_dialogue = r#"
+nodes:
+  main:
+  - message: Hello
+    options:
+      speed: 0
+"#
+        .trim_start();
+        let result = raw_dialogue.parse::<Dialogue>();
+        assert!(matches!(result, Err(Error::InvalidSpeed { .. })));
+
+        let negative = raw_dialogue.replace("speed: 0", "speed: -1.5");
+        assert!(matches!(
+            negative.parse::<Dialogue>(),
+            Err(Error::InvalidSpeed { .. })
+        ));
+        let valid = raw_dialogue.replace("speed: 0", "speed: 0.5");
+        assert!(valid.parse::<Dialogue>().is_ok());
+    }
+
     #[test]
     fn deserialize_actor_zero() {
         let raw_dialogue = r#"
@@ -128,6 +184,69 @@ nodes:
         assert!(matches!(result, Err(Error::MessageNotAllowed { .. })));
     }
 
+    #[test]
+    fn deserialize_on_error_not_found() {
+        let raw_dialogue = r#"
+on_error: oops
+nodes:
+  main: []
+"#
+        .trim_start();
+        let result = raw_dialogue.parse::<Dialogue>();
+        assert!(matches!(result, Err(Error::NodeNotFound { .. })));
+
+        let raw_dialogue = raw_dialogue.replace("main: []", "main: []\n  oops: []");
+        assert!(raw_dialogue.parse::<Dialogue>().is_ok());
+    }
+
+    #[test]
+    fn serde_actor_roles() {
+        let raw_dialogue = r#"
+actor:
+  roles:
+  - hero
+  - merchant
+nodes:
+  main:
+  - message: Welcome!
+    owner: merchant
+  - message: Hi.
+    owner: 0
+"#
+        .trim_start();
+        let deserialized = raw_dialogue.parse::<Dialogue>().unwrap();
+        assert_eq!(deserialized.actor_num(), 2);
+        assert_eq!(deserialized.actor.role_index(&"merchant".into()), Some(1));
+        let serialized = serde_yaml::to_string(&deserialized).unwrap();
+        assert_eq!(raw_dialogue, serialized);
+
+        let unknown = raw_dialogue.replace("owner: merchant", "owner: villain");
+        assert!(matches!(
+            unknown.parse::<Dialogue>(),
+            Err(Error::UnknownRole { ref role, .. }) if &**role == "villain"
+        ));
+        let count = raw_dialogue.replace("actor:\n", "actor:\n  num: 3\n");
+        assert!(matches!(
+            count.parse::<Dialogue>(),
+            Err(Error::RoleCount { num: 3, roles: 2 })
+        ));
+        let duplicate = raw_dialogue.replace("- merchant", "- hero");
+        assert!(matches!(
+            duplicate.parse::<Dialogue>(),
+            Err(Error::DuplicateRole(_))
+        ));
+    }
+
+    #[test]
+    fn deserialize_invalid_template() {
+        let raw_dialogue = "nodes:\n  main:\n  - message: ok\n  - goto: ${missing";
+        let result = raw_dialogue.parse::<Dialogue>();
+        assert!(matches!(
+            result,
+            Err(Error::Template { ref location, .. }) if location.to_string() == "main:1"
+        ));
+    }
+
     #[test]
     fn deserialize() {
         let raw = r#"
@@ -3,24 +3,67 @@ use std::ops::Deref;
 
 #[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
 pub struct ActorInfo {
+    #[serde(default, skip_serializing_if = "ActorNum::is_default")]
     pub num: ActorNum,
+    /// Names of the actors by index, so that owners can refer to them by role. `num` defaults
+    /// to the number of roles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<ActorRole>,
 }
 
 impl ActorInfo {
+    pub fn count(&self) -> u8 {
+        match self.roles.len() {
+            len if len > 0 && self.num.is_default() => len as u8,
+            _ => *self.num,
+        }
+    }
+
+    pub fn role_index(&self, role: &ActorRole) -> Option<u8> {
+        self.roles
+            .iter()
+            .position(|r| r == role)
+            .map(|index| index as u8)
+    }
+
     pub(crate) fn is_owner_in_range(&self, owner: u8) -> bool {
         owner <= self.max_owner()
     }
 
     pub(crate) fn max_owner(&self) -> u8 {
-        *self.num - 1
+        self.count() - 1
     }
 
     pub(crate) fn is_actor_num_not_zero(&self) -> bool {
-        *self.num > 0
+        self.count() > 0
     }
 
     pub(crate) fn is_skip_serializing(&self) -> bool {
-        self.num.is_default()
+        self.num.is_default() && self.roles.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ActorRole(String);
+
+impl From<&str> for ActorRole {
+    fn from(s: &str) -> Self {
+        ActorRole(s.to_owned())
+    }
+}
+
+impl Deref for ActorRole {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ActorRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
@@ -18,6 +18,64 @@ pub struct Line {
     pub r#type: LineType,
 }
 
+/// A piece of a line the runner evaluates in the script engine.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Expression<'a> {
+    /// A script evaluated as a whole, like an `if:` condition or an `eval:`.
+    Script(&'a str),
+    /// A text of which only the holes of its [`Template`] are evaluated.
+    Template(&'a str),
+}
+
+impl Line {
+    pub fn expressions(&self) -> Vec<Expression<'_>> {
+        let mut scripts: Vec<&str> = self.r#if.iter().map(|r#if| r#if.as_ref()).collect();
+        let mut texts: Vec<&Texts> = Vec::new();
+        let mut templates: Vec<&str> = Vec::new();
+        let message = match &self.r#type {
+            LineType::Message(message) => Some(message),
+            LineType::Confirm(confirm) => Some(&confirm.message),
+            LineType::Choice(choice) => choice.message(),
+            _ => None,
+        };
+        templates.extend(
+            (message
+                .and_then(|message| message.options.as_ref())
+                .into_iter())
+            .flat_map(MessageOptions::templates),
+        );
+        match &self.r#type {
+            LineType::Message(message) => texts.push(&message.texts),
+            LineType::Confirm(confirm) => {
+                texts.push(&confirm.message.texts);
+                if let Some(response) = confirm.options.as_ref().and_then(|o| o.response.as_ref()) {
+                    texts.extend([&response.yes, &response.no]);
+                }
+            }
+            LineType::Choice(choice) => {
+                texts.extend(choice.texts.values());
+                texts.extend(choice.message().map(|message| &message.texts));
+            }
+            LineType::Eval(eval) => scripts.push(&eval.source),
+            LineType::Goto(goto) => templates.push(&goto.pre_evaluation_value),
+            LineType::Call(call) => templates.push(&call.pre_evaluation_node_key),
+            LineType::Return(r#return) => scripts.push(&r#return.pre_evaluation_value),
+            LineType::Exit(exit) => match &exit.value {
+                ExitValue::PreEvaluation(source) => scripts.push(source),
+                ExitValue::ExitCode(_) => (),
+            },
+        }
+
+        let texts = texts
+            .into_iter()
+            .flat_map(Texts::iter)
+            .map(|text| text.as_str());
+        (scripts.into_iter().map(Expression::Script))
+            .chain(templates.into_iter().chain(texts).map(Expression::Template))
+            .collect()
+    }
+}
+
 impl<'de> Deserialize<'de> for Line {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -1,4 +1,5 @@
 use super::texts::Texts;
+use crate::dialogue::actor_info::ActorRole;
 
 use serde::{Deserialize, Serialize, ser::SerializeStruct};
 use std::collections::BTreeSet;
@@ -39,7 +40,7 @@ impl Serialize for Message {
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Owner {
-    pub(crate) id: u8,
+    pub(crate) actor: ActorRef,
     #[serde(skip)]
     default: bool,
 }
@@ -53,21 +54,41 @@ impl Owner {
 impl Default for Owner {
     fn default() -> Self {
         Self {
-            id: 0,
+            actor: ActorRef::Index(0),
             default: true,
         }
     }
 }
 
 impl Deref for Owner {
-    type Target = u8;
+    type Target = ActorRef;
 
     fn deref(&self) -> &Self::Target {
-        &self.id
+        &self.actor
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// An actor referred to by its index, or by one of the roles of [`ActorInfo`].
+///
+/// [`ActorInfo`]: crate::ActorInfo
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ActorRef {
+    Index(u8),
+    Role(ActorRole),
+}
+
+impl std::fmt::Display for ActorRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActorRef::Index(index) => write!(f, "{index}"),
+            ActorRef::Role(role) => write!(f, "{role}"),
+        }
+    }
+}
+
+/// Options of a message, of which `emotion` and `font.color` are templates.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct MessageOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub emotion: Option<String>,
@@ -79,9 +100,25 @@ pub struct MessageOptions {
     pub listeners: Option<Listeners>,
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+impl MessageOptions {
+    pub fn templates(&self) -> impl Iterator<Item = &str> {
+        let color = self.font.as_ref().and_then(|font| font.color.as_deref());
+        self.emotion.as_deref().into_iter().chain(color)
+    }
+}
+
+/// Actors the message is addressed to, by index or by role like its owner.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 #[serde(transparent)]
-pub struct Listeners(BTreeSet<u8>);
+pub struct Listeners(BTreeSet<ActorRef>);
+
+impl Deref for Listeners {
+    type Target = BTreeSet<ActorRef>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -107,7 +144,7 @@ impl Default for TypingSpeedFactor {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct FontProperties {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub weight: Option<u16>,
@@ -1,7 +1,9 @@
 mod lang_texts;
+mod template;
 mod text;
 
 pub use lang_texts::LangTexts;
+pub use template::{Segment, Template, TemplateError};
 pub use text::Text;
 
 use language_tags::LanguageTag;
@@ -21,6 +23,17 @@ impl Texts {
             Texts::Multilingual(lang_texts) => lang_texts.get(lang),
         }
     }
+
+    /// The text of every language.
+    pub fn iter(&self) -> impl Iterator<Item = &Text> {
+        let (monolingual, multilingual) = match self {
+            Texts::Monolingual(text) => (Some(text), None),
+            Texts::Multilingual(lang_texts) => (None, Some(lang_texts.values())),
+        };
+        monolingual
+            .into_iter()
+            .chain(multilingual.into_iter().flatten())
+    }
 }
 
 impl Default for Texts {
@@ -0,0 +1,242 @@
+/// A text split into literal parts and the expression holes the runner evaluates.
+///
+/// Holes are written `${expr}` or `${{ expr }}`; the latter ends at the first `}}` outside of a
+/// string, so it may contain unbalanced braces. `\$` stands for a literal `$`, and the other
+/// escapes are those of JavaScript template literals, like `\n`, `\\` or `\u{1F600}`, as when
+/// texts were evaluated as such, so a backslash before any other character drops out. Backticks
+/// are passed through as is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Template(Vec<Segment>);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment {
+    Literal(String),
+    Hole(String),
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Clone)]
+pub enum TemplateError {
+    #[error("Unterminated expression starting at byte {0}")]
+    Unterminated(usize),
+    #[error("Empty expression at byte {0}")]
+    EmptyHole(usize),
+    #[error("Invalid escape sequence at byte {0}")]
+    InvalidEscape(usize),
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut rest = text;
+
+        while let Some(c) = rest.chars().next() {
+            let position = text.len() - rest.len();
+            if let Some(escape) = rest.strip_prefix('\\') {
+                let (unescaped, len) =
+                    Self::unescape(escape).ok_or(TemplateError::InvalidEscape(position))?;
+                literal.extend(unescaped);
+                rest = &escape[len..];
+            } else if rest.starts_with("${") {
+                let double = rest.starts_with("${{");
+                let body = &rest[if double { 3 } else { 2 }..];
+                let len =
+                    Self::hole_len(body, double).ok_or(TemplateError::Unterminated(position))?;
+                let expr = body[..len].trim();
+                if expr.is_empty() {
+                    return Err(TemplateError::EmptyHole(position));
+                }
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Hole(expr.to_string()));
+                rest = &body[len + if double { 2 } else { 1 }..];
+            } else {
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template(segments))
+    }
+
+    /// The character the escape sequence at the start of `escape` stands for, none for a line
+    /// continuation, and the length of the sequence after the backslash.
+    fn unescape(escape: &str) -> Option<(Option<char>, usize)> {
+        let hex = |digits: &str| u32::from_str_radix(digits, 16).ok();
+        let c = escape.chars().next()?;
+        let (code, len) = match c {
+            'n' => ('\n' as u32, 1),
+            't' => ('\t' as u32, 1),
+            'r' => ('\r' as u32, 1),
+            'b' => (0x08, 1),
+            'f' => (0x0c, 1),
+            'v' => (0x0b, 1),
+            '0' if !escape[1..].starts_with(|c: char| c.is_ascii_digit()) => (0, 1),
+            '1'..='9' | '0' => return None,
+            'x' => (hex(escape.get(1..3)?)?, 3),
+            'u' if escape[1..].starts_with('{') => {
+                let end = escape.find('}')?;
+                (hex(&escape[2..end]).filter(|_| end > 2)?, end + 1)
+            }
+            'u' => {
+                let code = hex(escape.get(1..5)?)?;
+                // A surrogate pair is written as two escapes, e.g. `\uD83D\uDE00`.
+                match escape[5..]
+                    .strip_prefix("\\u")
+                    .and_then(|low| hex(low.get(..4)?))
+                {
+                    Some(low @ 0xDC00..=0xDFFF) if (0xD800..=0xDBFF).contains(&code) => {
+                        (0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00), 11)
+                    }
+                    _ => (code, 5),
+                }
+            }
+            '\n' => return Some((None, 1)),
+            '\r' => return Some((None, if escape[1..].starts_with('\n') { 2 } else { 1 })),
+            c => (c as u32, c.len_utf8()),
+        };
+        Some((Some(char::from_u32(code)?), len))
+    }
+
+    /// Length of the expression at the start of `body`, up to the closing brace(s).
+    fn hole_len(body: &str, double: bool) -> Option<usize> {
+        let mut depth = 0usize;
+        let mut quote = None;
+        let mut chars = body.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match (quote, c) {
+                (Some(_), '\\') => {
+                    chars.next();
+                }
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => (),
+                (None, '\'' | '"' | '`') => quote = Some(c),
+                (None, '{') => depth += 1,
+                (None, '}') if depth > 0 => depth -= 1,
+                (None, '}') if !double || body[i + 1..].starts_with('}') => return Some(i),
+                _ => (),
+            }
+        }
+        None
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    pub fn holes(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|segment| match segment {
+            Segment::Hole(expr) => Some(expr.as_str()),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.holes().next().is_none()
+    }
+
+    /// Joins the literal parts with the values `eval` gives for the holes.
+    pub fn render<E>(&self, mut eval: impl FnMut(&str) -> Result<String, E>) -> Result<String, E> {
+        self.0
+            .iter()
+            .try_fold(String::new(), |mut rendered, segment| {
+                match segment {
+                    Segment::Literal(literal) => rendered.push_str(literal),
+                    Segment::Hole(expr) => rendered.push_str(&eval(expr)?),
+                }
+                Ok(rendered)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(text: &str) -> Vec<Segment> {
+        Template::parse(text).unwrap().0
+    }
+
+    #[test]
+    fn parse_literals_and_holes() {
+        use Segment::{Hole, Literal};
+
+        assert_eq!(segments("plain `text`"), [Literal("plain `text`".into())]);
+        assert_eq!(
+            segments("Hi ${name}, ${{ count + 1 }}!"),
+            [
+                Literal("Hi ".into()),
+                Hole("name".into()),
+                Literal(", ".into()),
+                Hole("count + 1".into()),
+                Literal("!".into()),
+            ]
+        );
+        assert_eq!(
+            segments(r"\${not} a \\ \d"),
+            [Literal(r"${not} a \ d".into())]
+        );
+        assert_eq!(segments("${ {a: '}'}.a }"), [Hole("{a: '}'}.a".into())]);
+        assert_eq!(
+            segments("${{ x ? '}}' : 1 }}"),
+            [Hole("x ? '}}' : 1".into())]
+        );
+    }
+
+    #[test]
+    fn parse_escapes() {
+        use Segment::{Hole, Literal};
+
+        assert_eq!(
+            segments(r"a\nb\t${x}\x41\u00e9\u{1F600}\uD83D\uDE00\0"),
+            [
+                Literal("a\nb\t".into()),
+                Hole("x".into()),
+                Literal("A\u{e9}\u{1F600}\u{1F600}\0".into()),
+            ]
+        );
+        assert_eq!(segments("one \\\ntwo"), [Literal("one two".into())]);
+        assert_eq!(segments(r"\`\'\q"), [Literal(r"`'q".into())]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Template::parse("a ${b"),
+            Err(TemplateError::Unterminated(2))
+        );
+        assert_eq!(
+            Template::parse("${{ b }"),
+            Err(TemplateError::Unterminated(0))
+        );
+        assert_eq!(Template::parse("a${ }"), Err(TemplateError::EmptyHole(1)));
+        assert_eq!(
+            Template::parse(r"a\x4"),
+            Err(TemplateError::InvalidEscape(1))
+        );
+        assert_eq!(
+            Template::parse(r"\u{}"),
+            Err(TemplateError::InvalidEscape(0))
+        );
+        assert_eq!(
+            Template::parse(r"\u{110000}"),
+            Err(TemplateError::InvalidEscape(0))
+        );
+        assert_eq!(
+            Template::parse(r"\01"),
+            Err(TemplateError::InvalidEscape(0))
+        );
+        assert_eq!(Template::parse("a\\"), Err(TemplateError::InvalidEscape(1)));
+    }
+
+    #[test]
+    fn render() {
+        let template = Template::parse("${a} and ${b}").unwrap();
+        let rendered = template.render(|expr| Ok::<_, ()>(expr.to_uppercase()));
+        assert_eq!(rendered, Ok("A and B".to_string()));
+    }
+}
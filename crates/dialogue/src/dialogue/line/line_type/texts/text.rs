@@ -1,3 +1,5 @@
+use super::template::{Template, TemplateError};
+
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::ops::Deref;
@@ -6,6 +8,12 @@ use std::ops::Deref;
 #[serde(transparent)]
 pub struct Text(String);
 
+impl Text {
+    pub fn template(&self) -> Result<Template, TemplateError> {
+        Template::parse(&self.0)
+    }
+}
+
 impl From<String> for Text {
     fn from(s: String) -> Self {
         Text(s)
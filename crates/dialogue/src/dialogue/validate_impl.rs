@@ -1,45 +1,119 @@
 use super::super::error::Error;
 use super::Dialogue;
-use super::line::{LineType, Owner};
+use super::line::{ActorRef, Expression, LineType, Message, Template};
 use super::location::Location;
 
 impl Dialogue {
     pub(super) fn validate(&self) -> Result<(), Error> {
+        self.validate_roles()?;
         self.validate_owners()?;
+        self.validate_on_error()?;
+        self.validate_templates()?;
         Ok(())
     }
 
-    fn validate_owners(&self) -> Result<(), Error> {
+    fn validate_roles(&self) -> Result<(), Error> {
+        let roles = &self.actor.roles;
+        if !roles.is_empty() && self.actor.count() as usize != roles.len() {
+            return Err(Error::RoleCount {
+                num: self.actor.count(),
+                roles: roles.len(),
+            });
+        }
+        match (roles.iter().enumerate()).find(|(index, role)| roles[..*index].contains(role)) {
+            Some((_, role)) => Err(Error::DuplicateRole(role.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_templates(&self) -> Result<(), Error> {
         for (node_key, node) in self.nodes.iter() {
             for (line_idx, line) in node.iter().enumerate() {
-                let validate_owner = |owner: &Owner| {
-                    let location = Location {
-                        node_key: node_key.clone(),
-                        line_position: line_idx.into(),
+                for expression in line.expressions() {
+                    let Expression::Template(text) = expression else {
+                        continue;
                     };
+                    Template::parse(text).map_err(|source| Error::Template {
+                        location: Location {
+                            node_key: node_key.clone(),
+                            line_position: line_idx.into(),
+                        },
+                        source,
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_on_error(&self) -> Result<(), Error> {
+        match &self.on_error {
+            Some(node_key) if !self.nodes.contains_key(node_key) => Err(Error::NodeNotFound {
+                referenced_node: node_key.to_string(),
+                location: "on_error".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_owners(&self) -> Result<(), Error> {
+        for (node_key, node) in self.nodes.iter() {
+            for (line_idx, line) in node.iter().enumerate() {
+                let location = || Location {
+                    node_key: node_key.clone(),
+                    line_position: line_idx.into(),
+                };
+                let validate_actor = |actor: &ActorRef, listener: bool| match actor {
+                    ActorRef::Index(index) if !self.actor.is_owner_in_range(*index) => {
+                        Err(match listener {
+                            false => Error::OwnerOutOfRange {
+                                owner: *index,
+                                max_owner: self.actor.max_owner(),
+                                location: location(),
+                            },
+                            true => Error::ListenerOutOfRange {
+                                listener: *index,
+                                max_owner: self.actor.max_owner(),
+                                location: location(),
+                            },
+                        })
+                    }
+                    ActorRef::Role(role) if self.actor.role_index(role).is_none() => {
+                        Err(Error::UnknownRole {
+                            role: role.clone(),
+                            location: location(),
+                        })
+                    }
+                    _ => Ok(()),
+                };
+                let validate_message = |message: &Message| {
                     if !self.is_message_allowed() {
-                        return Err(Error::MessageNotAllowed { location });
-                    } else if !self.actor.is_owner_in_range(**owner) {
-                        return Err(Error::OwnerOutOfRange {
-                            owner: **owner,
-                            max_owner: self.actor.max_owner(),
-                            location,
+                        return Err(Error::MessageNotAllowed {
+                            location: location(),
+                        });
+                    }
+                    validate_actor(&message.owner, false)?;
+                    let speed = (message.options.as_ref()).and_then(|options| options.speed);
+                    if let Some(speed) = speed.filter(|speed| !speed.is_finite() || **speed <= 0.0)
+                    {
+                        return Err(Error::InvalidSpeed {
+                            speed: *speed,
+                            location: location(),
                         });
                     }
+                    let listeners =
+                        (message.options.as_ref()).and_then(|options| options.listeners.as_ref());
+                    for listener in listeners.into_iter().flat_map(|listeners| listeners.iter()) {
+                        validate_actor(listener, true)?;
+                    }
                     Ok(())
                 };
 
                 match &line.r#type {
-                    LineType::Message(message) => {
-                        validate_owner(&message.owner)?;
-                    }
+                    LineType::Message(message) => validate_message(message)?,
+                    LineType::Confirm(confirm) => validate_message(&confirm.message)?,
                     LineType::Choice(choice) => {
-                        choice
-                            .options
-                            .as_ref()
-                            .and_then(|opts| opts.message.as_ref())
-                            .map(|message| validate_owner(&message.owner))
-                            .transpose()?;
+                        choice.message().map(validate_message).transpose()?;
                     }
                     _ => {}
                 }
@@ -1,3 +1,5 @@
+use super::dialogue::actor_info::ActorRole;
+use super::dialogue::line::TemplateError;
 use super::dialogue::location::Location;
 
 #[derive(thiserror::Error, Debug)]
@@ -10,8 +12,27 @@ pub enum Error {
         max_owner: u8,
         location: Location,
     },
+    #[error("Listener {listener} exceeds maximum {max_owner} at {location}")]
+    ListenerOutOfRange {
+        listener: u8,
+        max_owner: u8,
+        location: Location,
+    },
+    #[error("Actor {role} at {location} is not one of actor.roles")]
+    UnknownRole { role: ActorRole, location: Location },
+    #[error("actor.num is {num}, but {roles} actor.roles are declared")]
+    RoleCount { num: u8, roles: usize },
+    #[error("Role {0} is declared more than once in actor.roles")]
+    DuplicateRole(ActorRole),
+    #[error("Typing speed {speed} at {location} should be a positive number")]
+    InvalidSpeed { speed: f32, location: Location },
     #[error("actor.num is 0, so no message is allowed at {location}")]
     MessageNotAllowed { location: Location },
+    #[error("Invalid template at {location}: {source}")]
+    Template {
+        location: Location,
+        source: TemplateError,
+    },
     #[error("Referenced node '{referenced_node}' not found at {location}")]
     NodeNotFound {
         referenced_node: String,
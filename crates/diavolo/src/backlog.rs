@@ -0,0 +1,408 @@
+use super::boa_ctx::HostCall;
+use super::data::Data;
+use super::dialogue_ctx::ViewActor;
+use super::emotions::Emotions;
+use super::runner::ScriptSnapshot;
+use super::state_machine::call_stack::CallStack;
+use super::view::View;
+use super::visiting_states::VisitingStates;
+
+use dialogue::{ChoiceKey, Text};
+
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+#[derive(Debug, Default)]
+pub struct Backlog {
+    entries: Vec<BacklogEntry>,
+    /// Checkpoints of the last `rollback_limit` entries, as each one holds the visiting states
+    /// of the whole dialogue so far.
+    checkpoints: VecDeque<Checkpoint>,
+    rollback_limit: usize,
+}
+
+impl Deref for Backlog {
+    type Target = Vec<BacklogEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl Backlog {
+    pub(crate) fn new(rollback_limit: usize) -> Self {
+        Self {
+            rollback_limit,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push(&mut self, entry: BacklogEntry, checkpoint: Checkpoint) {
+        self.entries.push(entry);
+        self.checkpoints.push_back(checkpoint);
+        if self.checkpoints.len() > self.rollback_limit {
+            self.checkpoints.pop_front();
+        }
+    }
+
+    /// The number of entries [`Backlog::rewind`] can drop at most.
+    pub(crate) fn rewindable(&self) -> usize {
+        self.checkpoints.len().saturating_sub(1)
+    }
+
+    pub(crate) fn record_selected(&mut self, choice_key: Option<ChoiceKey>) {
+        if let Some(BacklogEntry::Choice(_, selected)) = self.entries.last_mut() {
+            *selected = choice_key;
+        }
+    }
+
+    pub(crate) fn record_confirmed(&mut self, approved: Option<bool>) {
+        if let Some(BacklogEntry::Confirm(_, confirmed)) = self.entries.last_mut() {
+            *confirmed = approved;
+        }
+    }
+
+    /// Drops the last `n` entries along with the current one and returns the checkpoint
+    /// taken when the runner reached the line of the oldest dropped entry.
+    pub(crate) fn rewind(&mut self, n: usize) -> Option<Checkpoint> {
+        let target = self.checkpoints.len().checked_sub(n.checked_add(1)?)?;
+        self.entries.truncate(self.entries.len() - (n + 1));
+        self.checkpoints.drain(target..).next()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BacklogEntry {
+    Message(BacklogMessage),
+    Confirm(BacklogMessage, Option<bool>),
+    Choice(Option<BacklogMessage>, Option<ChoiceKey>),
+}
+
+impl BacklogEntry {
+    pub(crate) fn from_view(view: &View) -> Option<Self> {
+        match view {
+            View::Message(message_view) => Some(BacklogEntry::Message(BacklogMessage::new(
+                message_view.view_actor(),
+                message_view.text(),
+            ))),
+            View::Confirm(confirm_view) => Some(BacklogEntry::Confirm(
+                BacklogMessage::new(confirm_view.view_actor(), confirm_view.text()),
+                None,
+            )),
+            View::Choice(choice_view) => Some(BacklogEntry::Choice(
+                choice_view.message_view().as_ref().map(|message_view| {
+                    BacklogMessage::new(message_view.view_actor(), message_view.text())
+                }),
+                None,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> Option<&BacklogMessage> {
+        match self {
+            BacklogEntry::Message(message) | BacklogEntry::Confirm(message, _) => Some(message),
+            BacklogEntry::Choice(message, _) => message.as_ref(),
+        }
+    }
+
+    pub fn selected(&self) -> Option<&ChoiceKey> {
+        match self {
+            BacklogEntry::Choice(_, selected) => selected.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn confirmed(&self) -> Option<bool> {
+        match self {
+            BacklogEntry::Confirm(_, confirmed) => *confirmed,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BacklogMessage {
+    view_actor: ViewActor<'static>,
+    text: Text,
+}
+
+impl BacklogMessage {
+    fn new(view_actor: &ViewActor, text: &Text) -> Self {
+        Self {
+            view_actor: view_actor.clone().into_owned(),
+            text: text.clone(),
+        }
+    }
+
+    pub fn view_actor(&self) -> &ViewActor<'static> {
+        &self.view_actor
+    }
+
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    call_stack: CallStack,
+    visiting_states: VisitingStates,
+    exit_code: Option<u8>,
+    error_node_depth: Option<usize>,
+    emotions: Emotions,
+    script: ScriptSnapshot,
+    /// The host calls the line made, which it gets the results of when it runs again.
+    calls: Vec<HostCall>,
+}
+
+impl Checkpoint {
+    pub(crate) fn capture(data: &Data, script: ScriptSnapshot) -> Self {
+        Self {
+            call_stack: data.state_machine.call_stack.clone(),
+            visiting_states: data.visiting_states.clone(),
+            exit_code: data.exit_code,
+            error_node_depth: data.error_node_depth,
+            emotions: data.emotions.clone(),
+            script,
+            calls: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record_calls(&mut self, calls: Vec<HostCall>) {
+        self.calls = calls;
+    }
+
+    /// What scripts left at the checkpoint, which the script context is restored to along
+    /// with the data.
+    pub(crate) fn script(&self) -> &ScriptSnapshot {
+        &self.script
+    }
+
+    /// Restores the data and returns the host calls to replay.
+    pub(crate) fn restore(self, data: &mut Data) -> Vec<HostCall> {
+        data.state_machine.call_stack = self.call_stack;
+        data.visiting_states = self.visiting_states;
+        data.exit_code = self.exit_code;
+        data.error_node_depth = self.error_node_depth;
+        data.emotions = self.emotions;
+        self.calls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store};
+    use dialogue::ChoiceKey;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_rollback() {
+        let dialogue: Dialogue = r#"
+args:
+  flag: mut boolean
+nodes:
+  main:
+  - message: first
+  - id: q1
+    choice:
+      foo: Foo
+      bar: Bar
+  - eval: flag = true
+  - message: ${lines.q1.selected}
+"#
+        .parse()
+        .unwrap();
+
+        let engine = Engine::default();
+        let dialogue_ctx = DialogueCtx::builder()
+            .args(serde_json::json!({ "flag": false }))
+            .build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        assert!(
+            runner
+                .dispatch(Action::Select(ChoiceKey::new("foo")))
+                .unwrap()
+        );
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("foo"));
+        assert_eq!(runner.backlog().len(), 3);
+        assert_eq!(runner.backlog()[1].selected(), Some(&ChoiceKey::new("foo")));
+        runner.eval_for_assert(r#"assert_eq(flag, true, "flag should be set");"#);
+
+        assert!(!runner.dispatch(Action::Rollback(3)).unwrap());
+        assert!(runner.dispatch(Action::Rollback(1)).unwrap());
+        runner.update_view().unwrap();
+        assert!(runner.view().is_choice());
+        assert_eq!(runner.backlog().len(), 2);
+        assert_eq!(runner.backlog()[1].selected(), None);
+        runner.eval_for_assert(
+            r#"
+            assert_eq(flag, false, "flag should be restored");
+            assert_eq(lines.q1.selected, undefined, "selection should be restored");
+            assert_eq(lines.q1.visited_count, 1, "visited count should be restored");
+        "#,
+        );
+
+        assert!(
+            runner
+                .dispatch(Action::Select(ChoiceKey::new("bar")))
+                .unwrap()
+        );
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("bar"));
+        assert_eq!(
+            runner
+                .backlog()
+                .last()
+                .and_then(|entry| entry.message())
+                .map(|m| m.text().as_str()),
+            Some("bar")
+        );
+    }
+
+    #[test]
+    fn test_rollback_args_and_limit() {
+        use crate::Config;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dialogue: Dialogue = r#"
+args:
+  state: mut number
+nodes:
+  main:
+  - eval: "state = { count: 1 }"
+  - message: first
+  - eval: state.count += 1
+  - message: second
+  - eval: state.count += 1
+  - message: third
+"#
+        .parse()
+        .unwrap();
+
+        let config = Config {
+            rollback_limit: 2,
+            ..Default::default()
+        };
+        let engine = Engine::builder().config(config).build();
+        let dialogue_ctx = DialogueCtx::builder()
+            .args(serde_json::json!({ "state": 0 }))
+            .build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        for _ in 0..2 {
+            runner.dispatch(Action::Skip).unwrap();
+            runner.update_view().unwrap();
+            assert!(runner.dispatch(Action::Advance).unwrap());
+        }
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("third"));
+        assert_eq!(runner.backlog().len(), 3);
+
+        assert!(!runner.dispatch(Action::Rollback(2)).unwrap());
+        assert!(runner.dispatch(Action::Rollback(1)).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("second"));
+        runner.eval_for_assert(
+            r#"assert_eq(state.count, 2, "in-place mutations should be rolled back");"#,
+        );
+        assert!(!runner.dispatch(Action::Rollback(1)).unwrap());
+
+        let visits = Arc::new(AtomicUsize::new(0));
+        let engine = Engine::builder()
+            .function("visit", {
+                let visits = visits.clone();
+                move || {
+                    visits.fetch_add(1, Ordering::Relaxed);
+                    Ok::<_, String>(())
+                }
+            })
+            .build();
+        let dialogue: Dialogue =
+            "nodes:\n  main:\n  - message: a\n  - eval: visit()\n  - message: b"
+                .parse()
+                .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        assert_eq!(visits.load(Ordering::Relaxed), 1);
+        assert!(!runner.dispatch(Action::Rollback(0)).unwrap());
+        assert_eq!(
+            visits.load(Ordering::Relaxed),
+            1,
+            "no line should run again"
+        );
+        assert_eq!(runner.backlog().len(), 2);
+    }
+
+    #[test]
+    fn test_rollback_scripts() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let engine = Engine::builder()
+            .function("greet", {
+                let calls = calls.clone();
+                move || {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    Ok::<_, String>("hi")
+                }
+            })
+            .build();
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: n = null
+  - message: first
+  - eval: n = (n ?? 0) + 1; greet()
+  - message: ${n} ${greet()}
+  - message: third
+"#
+        .parse()
+        .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        for _ in 0..2 {
+            runner.dispatch(Action::Skip).unwrap();
+            assert!(runner.dispatch(Action::Advance).unwrap());
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        assert!(runner.dispatch(Action::Rollback(1)).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("1 hi"),
+            "the eval line before the entry should not run again"
+        );
+        runner.eval_for_assert(r#"assert_eq(n, 1, "n should be rolled back");"#);
+        assert_eq!(
+            calls.load(Ordering::Relaxed),
+            2,
+            "the host should not be called again"
+        );
+
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        assert!(runner.dispatch(Action::Rollback(1)).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("1 hi"));
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
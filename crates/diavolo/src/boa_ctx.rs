@@ -1,16 +1,145 @@
+use super::clock::Clock;
 use super::data::Data;
-use dialogue::{ChoiceTexts, LangTexts, LineIf, Text, Texts};
+use super::dialogue_ctx::DialogueArgs;
+use super::engine::config::ScriptLimits;
+use super::error::{EvalError, LimitExceeded};
+use super::host::{Callback, Host, HostFunction, HostFuture, HostItem};
+use dialogue::{
+    ChoiceTexts, Expression, LangTexts, LineIf, Location, MessageOptions, Nodes, Template, Text,
+    Texts,
+};
 
-use boa_engine::object::IntegrityLevel;
-use boa_engine::property::PropertyDescriptor;
-use boa_engine::{Context, JsObject, JsResult, JsValue, NativeFunction, Source, js_string};
+use boa_engine::ast::declaration::LexicalDeclaration;
+use boa_engine::ast::operations::{self, LexicallyScopedDeclaration};
+use boa_engine::ast::scope::Scope;
+use boa_engine::builtins::promise::PromiseState;
+use boa_engine::interner::Interner;
+use boa_engine::object::builtins::{JsArray, JsPromise};
+use boa_engine::object::{IntegrityLevel, ObjectInitializer};
+use boa_engine::parser::Parser;
+use boa_engine::property::{Attribute, PropertyDescriptor, PropertyKey};
+use boa_engine::{
+    Context, JsError, JsNativeError, JsObject, JsResult, JsValue, NativeFunction, Script, Source,
+    js_string,
+};
 
-use std::ops::{Deref, DerefMut, Sub};
+use indexmap::IndexMap;
+use language_tags::LanguageTag;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+use std::time::Instant;
 
+/// The script context of a runner, living on its script thread as long as the runner does.
+/// Scripts are parsed once, all of them when the dialogue is loaded.
 #[derive(Debug, Default)]
 pub struct BoaCtx {
     context: Context,
+    host_calls: Rc<RefCell<HostCalls>>,
+    raw_marker: Option<String>,
+    recovered: Vec<EvalError>,
+    /// Parsed scripts by the location of their line and their source.
+    scripts: HashMap<Location, HashMap<String, Script>>,
+    location: Option<Location>,
+    limits: ScriptLimits,
+    clock: Option<Arc<dyn Clock>>,
+    /// When the scripts of the current line started running, for the time budget.
+    line_started: Option<Instant>,
+    /// The state of the `Math.random` generator.
+    random: Rc<Cell<u64>>,
+    /// The globals defined before [`BoaCtx::compile`], by the engine rather than by scripts.
+    builtins: HashSet<PropertyKey>,
+    /// The top-level `let`, `const` and `class` declarations found by [`BoaCtx::compile`],
+    /// with whether each is constant.
+    declarations: Vec<(String, bool)>,
+    /// Holds the values of the mutable args.
+    args: Option<JsObject>,
+    /// The snapshots taken by [`BoaCtx::snapshot`] and not forgotten yet, by id.
+    snapshots: HashMap<u64, Snapshot>,
+    next_snapshot: u64,
+    /// How many times a script was parsed.
+    #[cfg(test)]
+    parsed: usize,
+}
+
+/// What scripts left at some point, which [`BoaCtx::restore`] returns to.
+#[derive(Debug)]
+struct Snapshot {
+    /// The globals scripts defined, with their values.
+    globals: IndexMap<PropertyKey, JsValue>,
+    /// The values of the top-level declarations evaluated so far.
+    lexicals: JsObject,
+    /// The own properties of the objects reachable from the globals, the declarations and
+    /// the args.
+    objects: Vec<(JsObject, IndexMap<PropertyKey, JsValue>)>,
+    random: u64,
+}
+
+/// A host function call made by the line being evaluated.
+///
+/// A line waiting for async calls runs again once all of them completed. Its calls then get
+/// the results of the first run, in the same order, so that the host sees each of them once.
+pub(crate) struct HostCall {
+    name: String,
+    outcome: HostOutcome,
+}
+
+enum HostOutcome {
+    /// The value of a sync call, or the error either kind of call threw.
+    Returned(Result<Value, String>),
+    Waiting(HostFuture),
+    /// The result of the future of an async call.
+    Settled(Result<Value, String>),
+}
+
+impl std::fmt::Debug for HostCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostCall")
+            .field("name", &self.name)
+            .field("waiting", &self.is_waiting())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HostCall {
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.outcome, HostOutcome::Waiting(_))
+    }
+
+    /// Polls the future of a waiting call once.
+    pub fn poll(&mut self, cx: &mut std::task::Context<'_>) {
+        if let HostOutcome::Waiting(future) = &mut self.outcome
+            && let Poll::Ready(result) = future.as_mut().poll(cx)
+        {
+            tracing::debug!("Async host function {} completed", self.name);
+            self.outcome = HostOutcome::Settled(result);
+        }
+    }
+}
+
+/// The host calls of the line being evaluated, and how many of them it made again so far.
+#[derive(Default)]
+struct HostCalls {
+    calls: Vec<HostCall>,
+    replayed: usize,
+}
+
+impl std::fmt::Debug for HostCalls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(&self.calls).finish()
+    }
+}
+
+/// Result of evaluating a script that may have produced a promise.
+#[derive(Debug)]
+pub enum Evaluated<T = JsValue> {
+    Ready(T),
+    /// The script waits for async host calls.
+    Pending,
 }
 
 impl Deref for BoaCtx {
@@ -28,26 +157,366 @@ impl DerefMut for BoaCtx {
 }
 
 impl BoaCtx {
-    pub fn eval_if(&mut self, line_if: &LineIf) -> boa_engine::JsResult<bool> {
-        let result = self.context.eval(Source::from_bytes(line_if.as_bytes()))?;
-        Ok(result
-            .as_boolean()
-            .expect("line_if should evaluate to a boolean"))
+    /// Boa's "clock cycles" between two checks of the time budget.
+    const INSTRUCTIONS_PER_CHECK: u32 = 256;
+
+    /// Applies `limits`, measuring the time budget with `clock`.
+    pub fn set_limits(&mut self, limits: &ScriptLimits, clock: Arc<dyn Clock>) {
+        let runtime_limits = self.context.runtime_limits_mut();
+        runtime_limits.set_loop_iteration_limit(limits.loop_iterations);
+        runtime_limits.set_recursion_limit(limits.recursion);
+        runtime_limits.set_stack_size_limit(limits.stack_size);
+        self.limits = limits.clone();
+        self.clock = Some(clock);
     }
 
-    pub fn eval_str(&mut self, value: impl AsRef<str>) -> boa_engine::JsResult<JsValue> {
-        self.context
-            .eval(Source::from_bytes(value.as_ref().as_bytes()))
+    /// Makes the scripts evaluated from now on those of the line at `location`, starting its
+    /// time budget. Its host calls get the results of `calls` first, in order.
+    pub fn start_line(&mut self, location: &Location, calls: Vec<HostCall>) {
+        self.location = Some(location.clone());
+        self.line_started = self.clock.as_ref().map(|clock| clock.now());
+        *self.host_calls.borrow_mut() = HostCalls { calls, replayed: 0 };
+    }
+
+    /// The host calls the current line made so far.
+    pub fn take_host_calls(&mut self) -> Vec<HostCall> {
+        std::mem::take(&mut self.host_calls.borrow_mut().calls)
+    }
+
+    /// Parses every script and template of `nodes`, so that syntax errors surface before the
+    /// first line runs rather than when their line is visited, and finds the top-level
+    /// declarations snapshots keep.
+    pub fn compile(&mut self, nodes: &Nodes) -> Result<(), (Location, EvalError)> {
+        let global = self.context.global_object();
+        let builtins = global.own_property_keys(&mut self.context);
+        self.builtins = builtins.unwrap_or_default().into_iter().collect();
+
+        let mut interner = Interner::default();
+        let mut declarations = Vec::new();
+        for (node_key, node) in nodes.iter() {
+            for (position, line) in node.iter().enumerate() {
+                let location = Location {
+                    node_key: node_key.clone(),
+                    line_position: position.into(),
+                };
+                for expression in line.expressions() {
+                    self.compile_expression(&location, expression)
+                        .map_err(|e| (location.clone(), e))?;
+                    if let Expression::Script(source) = expression {
+                        Self::collect_declarations(source, &mut interner, &mut declarations);
+                    }
+                }
+            }
+        }
+        self.declarations = declarations;
+        Ok(())
+    }
+
+    /// Adds the names `source` declares with `let`, `const` or `class` at its top level, and
+    /// whether they are constant.
+    fn collect_declarations(
+        source: &str,
+        interner: &mut Interner,
+        declarations: &mut Vec<(String, bool)>,
+    ) {
+        let Ok(script) =
+            Parser::new(Source::from_bytes(source)).parse_script(&Scope::new_global(), interner)
+        else {
+            return;
+        };
+        for declaration in operations::lexically_scoped_declarations(&script) {
+            let constant = matches!(
+                declaration,
+                LexicallyScopedDeclaration::LexicalDeclaration(LexicalDeclaration::Const(_))
+            );
+            for name in declaration.bound_names() {
+                let name = interner.resolve_expect(name).to_string();
+                if !declarations.iter().any(|(declared, _)| *declared == name) {
+                    declarations.push((name, constant));
+                }
+            }
+        }
+    }
+
+    fn compile_expression(
+        &mut self,
+        location: &Location,
+        expression: Expression,
+    ) -> Result<(), EvalError> {
+        match expression {
+            Expression::Script(source) => {
+                self.parse(Some(location), source)
+                    .map_err(EvalError::js(source))?;
+            }
+            Expression::Template(text) => {
+                let template = Template::parse(text).map_err(EvalError::template(text))?;
+                for hole in template.holes() {
+                    self.parse(Some(location), &Self::hole_source(hole))
+                        .map_err(EvalError::js(text))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parenthesized, so that holes are parsed as expressions as in a template literal.
+    fn hole_source(hole: &str) -> String {
+        format!("({hole})")
+    }
+
+    fn parse(&mut self, location: Option<&Location>, source: &str) -> JsResult<Script> {
+        let script = Script::parse(Source::from_bytes(source), None, &mut self.context)?;
+        #[cfg(test)]
+        {
+            self.parsed += 1;
+        }
+        if let Some(location) = location {
+            self.scripts
+                .entry(location.clone())
+                .or_default()
+                .insert(source.to_string(), script.clone());
+        }
+        Ok(script)
+    }
+
+    /// Evaluates the script compiled for `source` at the current location, parsing it first if
+    /// it was not.
+    fn run(&mut self, source: &str) -> Result<JsValue, EvalError> {
+        let location = self.location.clone();
+        let cached = location
+            .as_ref()
+            .and_then(|location| self.scripts.get(location))
+            .and_then(|scripts| scripts.get(source))
+            .cloned();
+        let script = match cached {
+            Some(script) => script,
+            None => self
+                .parse(location.as_ref(), source)
+                .map_err(EvalError::js(source))?,
+        };
+
+        let (Some(budget), Some(clock), Some(started)) = (
+            self.limits.time_budget,
+            self.clock.clone(),
+            self.line_started,
+        ) else {
+            return script
+                .evaluate(&mut self.context)
+                .map_err(EvalError::js(source));
+        };
+        // Boa cannot be interrupted, but its async evaluation yields every few instructions,
+        // which is where the budget is checked. A script out of time is abandoned where it
+        // stands: later scripts run as usual, but the frames it entered stay on boa's stack.
+        let mut evaluation = std::pin::pin!(
+            script.evaluate_async_with_budget(&mut self.context, Self::INSTRUCTIONS_PER_CHECK)
+        );
+        let mut cx = std::task::Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(result) = evaluation.as_mut().poll(&mut cx) {
+                return result.map_err(EvalError::js(source));
+            }
+            let elapsed = clock.elapsed(started);
+            if elapsed > budget {
+                let limit = LimitExceeded::TimeBudget { budget, elapsed };
+                return Err(EvalError::limit(source, limit));
+            }
+        }
+    }
+
+    pub fn eval_if(&mut self, line_if: &LineIf) -> Result<Evaluated, EvalError> {
+        self.eval_script(line_if)
+    }
+
+    /// Evaluates `source` and settles the resulting promise if it already can be. The script
+    /// is pending while any async host call it made is, even one it did not wait for.
+    pub fn eval_script(&mut self, source: &str) -> Result<Evaluated, EvalError> {
+        let value = self.run(source)?;
+        let evaluated = self.settle(value).map_err(EvalError::js(source))?;
+        let waiting = (self.host_calls.borrow().calls.iter()).any(HostCall::is_waiting);
+        Ok(if waiting {
+            Evaluated::Pending
+        } else {
+            evaluated
+        })
+    }
+
+    /// Unwraps `value` if it is a promise that has already settled after running the job
+    /// queue, so that scripts may return promises wherever a value is expected.
+    fn settle(&mut self, value: JsValue) -> JsResult<Evaluated> {
+        self.context.run_jobs()?;
+
+        let Some(promise) = value
+            .as_object()
+            .and_then(|object| JsPromise::from_object(object.clone()).ok())
+        else {
+            return Ok(Evaluated::Ready(value));
+        };
+
+        match promise.state() {
+            PromiseState::Pending => Ok(Evaluated::Pending),
+            PromiseState::Fulfilled(value) => Ok(Evaluated::Ready(value)),
+            PromiseState::Rejected(reason) => Err(JsError::from_opaque(reason)),
+        }
+    }
+
+    /// Takes a snapshot of what scripts left so far, which stays in the context until
+    /// [`BoaCtx::forget`], and returns its id.
+    ///
+    /// Objects are kept as they are, with their own properties copied, so that restoring the
+    /// snapshot keeps two globals referring to the same object. What lives in internal slots,
+    /// like the entries of a `Map`, is not copied.
+    pub fn snapshot(&mut self) -> JsResult<u64> {
+        let global = self.context.global_object();
+        let mut globals = IndexMap::new();
+        for key in global.own_property_keys(&mut self.context)? {
+            if !self.builtins.contains(&key) {
+                let value = global.get(key.clone(), &mut self.context)?;
+                globals.insert(key, value);
+            }
+        }
+        let lexicals = self.read_lexicals()?;
+        let mut roots: Vec<JsValue> = globals.values().cloned().collect();
+        for key in lexicals.own_property_keys(&mut self.context)? {
+            roots.push(lexicals.get(key, &mut self.context)?);
+        }
+        roots.extend(self.args.clone().map(JsValue::from));
+
+        let snapshot = Snapshot {
+            globals,
+            lexicals,
+            objects: Self::copy_reachable(roots, &mut self.context)?,
+            random: self.random.get(),
+        };
+        let id = self.next_snapshot;
+        self.next_snapshot += 1;
+        self.snapshots.insert(id, snapshot);
+        Ok(id)
+    }
+
+    /// Returns to what scripts left when the snapshot `id` was taken. Top-level declarations
+    /// evaluated since stay declared, and constants keep their value.
+    pub fn restore(&mut self, id: u64) -> JsResult<()> {
+        let snapshot = (self.snapshots.get(&id)).expect("snapshots should outlive their handle");
+        let context = &mut self.context;
+        let global = context.global_object();
+        for key in global.own_property_keys(context)? {
+            if !self.builtins.contains(&key) && !snapshot.globals.contains_key(&key) {
+                Self::remove(&global, key, context)?;
+            }
+        }
+        for (key, value) in &snapshot.globals {
+            global.set(key.clone(), value.clone(), false, context)?;
+        }
+        for (object, properties) in &snapshot.objects {
+            for key in object.own_property_keys(context)? {
+                if !properties.contains_key(&key) {
+                    Self::remove(object, key, context)?;
+                }
+            }
+            for (key, value) in properties {
+                object.set(key.clone(), value.clone(), false, context)?;
+            }
+        }
+        let (lexicals, random) = (snapshot.lexicals.clone(), snapshot.random);
+        self.write_lexicals(lexicals)?;
+        self.random.set(random);
+        Ok(())
+    }
+
+    pub fn forget(&mut self, id: u64) {
+        self.snapshots.remove(&id);
+    }
+
+    /// The values of the top-level declarations evaluated so far, by name.
+    fn read_lexicals(&mut self) -> JsResult<JsObject> {
+        let reads: String = (self.declarations.iter())
+            .map(|(name, _)| format!("try {{ $values.{name} = {name} }} catch {{}}\n"))
+            .collect();
+        let source = format!(
+            "(() => {{ const $values = {{ __proto__: null }};\n{reads}return $values }})()"
+        );
+        let values = self.context.eval(Source::from_bytes(&source))?;
+        Ok(values.as_object().expect("the values should be an object"))
+    }
+
+    /// Sets the top-level declarations that are not constant to `values`.
+    fn write_lexicals(&mut self, values: JsObject) -> JsResult<()> {
+        let writes: String = (self.declarations.iter())
+            .filter(|(_, constant)| !constant)
+            .map(|(name, _)| {
+                format!("if (\"{name}\" in $values) try {{ {name} = $values.{name} }} catch {{}}\n")
+            })
+            .collect();
+        let source = format!("($values) => {{\n{writes}}}");
+        let write = self.context.eval(Source::from_bytes(&source))?;
+        let write = write
+            .as_callable()
+            .expect("the writer should be a function");
+        write.call(&JsValue::undefined(), &[values.into()], &mut self.context)?;
+        Ok(())
+    }
+
+    /// The own properties of every object reachable from `roots` through own properties,
+    /// leaving out functions.
+    fn copy_reachable(
+        roots: Vec<JsValue>,
+        context: &mut Context,
+    ) -> JsResult<Vec<(JsObject, IndexMap<PropertyKey, JsValue>)>> {
+        let mut seen = HashSet::new();
+        let mut objects = Vec::new();
+        let mut stack = roots;
+        while let Some(value) = stack.pop() {
+            let Some(object) = value.as_object() else {
+                continue;
+            };
+            if object.is_callable() || !seen.insert(object.clone()) {
+                continue;
+            }
+            let mut properties = IndexMap::new();
+            for key in object.own_property_keys(context)? {
+                let value = object.get(key.clone(), context)?;
+                stack.push(value.clone());
+                properties.insert(key, value);
+            }
+            objects.push((object, properties));
+        }
+        Ok(objects)
+    }
+
+    /// Deletes `key` from `object`, or sets it to `undefined` if it cannot be deleted, like a
+    /// global declared with `var`.
+    fn remove(object: &JsObject, key: PropertyKey, context: &mut Context) -> JsResult<()> {
+        if object
+            .delete_property_or_throw(key.clone(), context)
+            .is_err()
+        {
+            object.set(key, JsValue::undefined(), false, context)?;
+        }
+        Ok(())
+    }
+
+    pub fn eval_str(&mut self, value: impl AsRef<str>) -> Result<JsValue, EvalError> {
+        self.run(value.as_ref())
+    }
+
+    /// Makes [`BoaCtx::eval_texts`] render failing templates raw, prefixed with `marker`.
+    pub fn set_raw_marker(&mut self, marker: Option<String>) {
+        self.raw_marker = marker;
+    }
+
+    /// The errors of the templates rendered raw since the last call.
+    pub fn take_recovered(&mut self) -> Vec<EvalError> {
+        std::mem::take(&mut self.recovered)
     }
 
-    pub fn eval_texts(&mut self, texts: &Texts) -> JsResult<Texts> {
+    pub fn eval_texts(&mut self, texts: &Texts) -> Result<Texts, EvalError> {
         match texts {
-            Texts::Monolingual(text) => Ok(Texts::Monolingual(self.eval_text(text)?)),
+            Texts::Monolingual(text) => Ok(Texts::Monolingual(self.eval_display_text(text)?)),
             Texts::Multilingual(lang_texts) => {
                 let evaluated_lang_texts = lang_texts
                     .iter()
                     .map(|(language, text)| {
-                        Ok::<_, boa_engine::JsError>((language.clone(), self.eval_text(text)?))
+                        Ok::<_, EvalError>((language.clone(), self.eval_display_text(text)?))
                     })
                     .collect::<Result<LangTexts, _>>()?;
                 Ok(Texts::Multilingual(evaluated_lang_texts))
@@ -55,7 +524,22 @@ impl BoaCtx {
         }
     }
 
-    pub fn eval_choice_texts(&mut self, texts: &ChoiceTexts) -> JsResult<ChoiceTexts> {
+    /// Evaluates the templates of `options`, which are absent from the result when not given.
+    pub fn eval_message_options(
+        &mut self,
+        options: Option<&MessageOptions>,
+    ) -> Result<MessageOptions, EvalError> {
+        let mut options = options.cloned().unwrap_or_default();
+        if let Some(emotion) = &mut options.emotion {
+            *emotion = self.eval_text(&emotion.as_str().into())?.to_string();
+        }
+        if let Some(color) = options.font.as_mut().and_then(|font| font.color.as_mut()) {
+            *color = self.eval_text(&color.as_str().into())?.to_string();
+        }
+        Ok(options)
+    }
+
+    pub fn eval_choice_texts(&mut self, texts: &ChoiceTexts) -> Result<ChoiceTexts, EvalError> {
         texts
             .iter()
             .map(|(key, lang_texts)| {
@@ -65,16 +549,51 @@ impl BoaCtx {
             .collect()
     }
 
-    pub fn eval_text(&mut self, text: &Text) -> JsResult<Text> {
+    fn eval_display_text(&mut self, text: &Text) -> Result<Text, EvalError> {
+        match (self.eval_text(text), &self.raw_marker) {
+            (Err(error), Some(marker)) if !error.is_runtime_limit() => {
+                let raw = format!("{marker}{text}").into();
+                self.recovered.push(error);
+                Ok(raw)
+            }
+            (result, _) => result,
+        }
+    }
+
+    pub fn eval_text(&mut self, text: &Text) -> Result<Text, EvalError> {
         tracing::debug!("Evaluating text: {}", text);
-        let result = self
-            .context
-            .eval(Source::from_bytes(format!("`{text}`").as_bytes()))?;
-        Ok(result
-            .as_string()
-            .expect("message should evaluate to a string")
-            .to_std_string_escaped()
-            .into())
+        let template = text
+            .template()
+            .map_err(EvalError::template(text.as_str()))?;
+        let js_error = || EvalError::js(text.as_str());
+        template
+            .render(|hole| {
+                let made = self.host_calls.borrow().calls.len();
+                let value = (self.run(&Self::hole_source(hole)))
+                    .map_err(|error| error.in_script(text.as_str()))?;
+                let waiting =
+                    (self.host_calls.borrow().calls[made..].iter()).any(HostCall::is_waiting);
+                if waiting || value.is_promise() {
+                    let mut host_calls = self.host_calls.borrow_mut();
+                    host_calls.calls.truncate(made);
+                    host_calls.replayed = made;
+                    let msg = "text templates cannot wait for promises, await them in an eval line";
+                    return Err(js_error()(JsNativeError::typ().with_message(msg).into()));
+                }
+                let value = value.to_string(&mut self.context).map_err(js_error())?;
+                Ok(value.to_std_string_escaped())
+            })
+            .map(Text::from)
+    }
+
+    #[cfg(test)]
+    pub fn compiled_scripts(&self) -> usize {
+        self.scripts.values().map(HashMap::len).sum()
+    }
+
+    #[cfg(test)]
+    pub fn parsed_scripts(&self) -> usize {
+        self.parsed
     }
 
     #[cfg(test)]
@@ -108,8 +627,12 @@ impl BoaCtx {
             .expect("Boa script should run without errors");
     }
 
-    pub fn define_properties(&mut self, data: Arc<Mutex<Data>>) -> JsResult<()> {
-        let lines_getter = Self::lines_getter(data.clone());
+    pub fn define_properties(
+        &mut self,
+        data: Arc<Mutex<Data>>,
+        clock: Arc<dyn Clock>,
+    ) -> JsResult<()> {
+        let lines_getter = Self::lines_getter(data.clone(), clock);
         let self_getter = Self::line_getter(data.clone(), lines_getter.clone(), 0);
         let prev_getter = Self::line_getter(data.clone(), lines_getter.clone(), -1);
         let next_getter = Self::line_getter(data.clone(), lines_getter.clone(), 1);
@@ -122,6 +645,306 @@ impl BoaCtx {
         Ok(())
     }
 
+    pub fn define_host(&mut self, host: &Host) -> JsResult<()> {
+        for (name, item) in host.iter() {
+            let value: JsValue = match item {
+                HostItem::Function(function) => self
+                    .host_function(name, function.clone())
+                    .to_js_function(self.context.realm())
+                    .into(),
+                HostItem::Object(object) => {
+                    let mut properties = Vec::new();
+                    for (key, value) in &object.properties {
+                        properties.push((key, JsValue::from_json(value, &mut self.context)?));
+                    }
+
+                    let mut initializer = ObjectInitializer::new(&mut self.context);
+                    for (key, function) in &object.functions {
+                        initializer.function(
+                            Self::host_function_with(
+                                self.host_calls.clone(),
+                                &format!("{name}.{key}"),
+                                function.clone(),
+                            ),
+                            js_string!(key.as_str()),
+                            function.arity(),
+                        );
+                    }
+                    for (key, value) in properties {
+                        initializer.property(
+                            js_string!(key.as_str()),
+                            value,
+                            Attribute::READONLY | Attribute::ENUMERABLE,
+                        );
+                    }
+                    initializer.build().into()
+                }
+            };
+
+            self.context.register_global_property(
+                js_string!(name.as_str()),
+                value,
+                Attribute::READONLY | Attribute::ENUMERABLE,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers `args`, whose mutable values snapshots copy.
+    pub fn define_args(&mut self, args: &DialogueArgs) -> JsResult<()> {
+        self.args = Some(args.register_in_boa_context(&mut self.context)?);
+        Ok(())
+    }
+
+    fn host_function(&self, name: &str, function: HostFunction) -> NativeFunction {
+        Self::host_function_with(self.host_calls.clone(), name, function)
+    }
+
+    /// Calls `function`, unless the line is running again and made this call before, in which
+    /// case the call gets the result it got then.
+    fn host_function_with(
+        host_calls: Rc<RefCell<HostCalls>>,
+        name: &str,
+        function: HostFunction,
+    ) -> NativeFunction {
+        let name = name.to_string();
+        unsafe {
+            NativeFunction::from_closure(
+                move |_this: &JsValue,
+                      args: &[JsValue],
+                      context: &mut Context|
+                      -> JsResult<JsValue> {
+                    let error =
+                        |message| JsNativeError::error().with_message(format!("{name}: {message}"));
+
+                    let mut calls = host_calls.borrow_mut();
+                    let index = calls.replayed;
+                    calls.replayed += 1;
+                    if let Some(call) = calls.calls.get(index) {
+                        if call.name != name {
+                            let message = format!("called where {} was before", call.name);
+                            return Err(error(message).into());
+                        }
+                        tracing::debug!("Replaying host function {name}");
+                        return match &call.outcome {
+                            HostOutcome::Returned(result) => {
+                                let value = result.clone().map_err(error)?;
+                                JsValue::from_json(&value, context)
+                            }
+                            HostOutcome::Settled(Ok(value)) => {
+                                let value = JsValue::from_json(value, context)?;
+                                Ok(JsPromise::resolve(value, context).into())
+                            }
+                            HostOutcome::Settled(Err(message)) => {
+                                let error = error(message.clone());
+                                Ok(JsPromise::reject(error, context).into())
+                            }
+                            HostOutcome::Waiting(_) => Ok(JsPromise::new_pending(context).0.into()),
+                        };
+                    }
+                    drop(calls);
+
+                    tracing::debug!("Calling host function {name}");
+                    let args = args
+                        .iter()
+                        .map(|arg| Ok(arg.to_json(context)?.unwrap_or_default()))
+                        .collect::<JsResult<Vec<_>>>()?;
+                    let (outcome, value) = match function.callback() {
+                        Callback::Sync(callback) => {
+                            let result = callback(args);
+                            let value = match &result {
+                                Ok(value) => JsValue::from_json(value, context),
+                                Err(message) => Err(error(message.clone()).into()),
+                            };
+                            (HostOutcome::Returned(result), value)
+                        }
+                        Callback::Async(callback) => match callback(args) {
+                            Ok(future) => {
+                                let promise = JsPromise::new_pending(context).0;
+                                (HostOutcome::Waiting(future), Ok(promise.into()))
+                            }
+                            Err(message) => {
+                                let value = Err(error(message.clone()).into());
+                                (HostOutcome::Returned(Err(message)), value)
+                            }
+                        },
+                    };
+                    host_calls.borrow_mut().calls.push(HostCall {
+                        name: name.clone(),
+                        outcome,
+                    });
+                    value
+                },
+            )
+        }
+    }
+
+    /// Replaces `Math.random` with a xorshift generator started from `seed`, so that scripts
+    /// are reproducible.
+    pub fn define_random(&mut self, seed: u64) -> JsResult<()> {
+        self.random.set(seed.max(1));
+        let state = self.random.clone();
+        let random = unsafe {
+            NativeFunction::from_closure(
+                move |_this: &JsValue,
+                      _args: &[JsValue],
+                      _context: &mut Context|
+                      -> JsResult<JsValue> {
+                    let mut x = state.get();
+                    x ^= x << 13;
+                    x ^= x >> 7;
+                    x ^= x << 17;
+                    state.set(x);
+                    Ok(JsValue::from((x >> 11) as f64 / (1u64 << 53) as f64))
+                },
+            )
+        };
+
+        let math = self
+            .context
+            .global_object()
+            .get(js_string!("Math"), &mut self.context)?;
+        let random = random.to_js_function(self.context.realm());
+        math.as_object().expect("Math should be an object").set(
+            js_string!("random"),
+            random,
+            true,
+            &mut self.context,
+        )?;
+
+        Ok(())
+    }
+
+    /// Defines the read-only `actors` of the dialogue ctx, named in the current language, and
+    /// `engine` holding the settings scripts may depend on. `language` is the one of the
+    /// config, used until the runner switches to another.
+    pub fn define_dialogue_ctx(
+        &mut self,
+        data: Arc<Mutex<Data>>,
+        language: &LanguageTag,
+    ) -> JsResult<()> {
+        let actors_getter = Self::actors_getter(data.clone(), language.clone());
+        Self::define_property(&mut self.context, "actors", actors_getter)?;
+
+        let language = language.clone();
+        let language_getter = unsafe {
+            NativeFunction::from_closure(move |_this, _args, _context| {
+                let data = data.lock().unwrap();
+                let language = data.language.as_ref().unwrap_or(&language);
+                Ok(js_string!(language.as_str()).into())
+            })
+        };
+        let language_getter = language_getter.to_js_function(self.context.realm());
+        let engine = ObjectInitializer::new(&mut self.context)
+            .accessor(
+                js_string!("language"),
+                Some(language_getter),
+                None,
+                Attribute::ENUMERABLE,
+            )
+            .build();
+        engine.set_integrity_level(IntegrityLevel::Frozen, &mut self.context)?;
+        self.context.register_global_property(
+            js_string!("engine"),
+            engine,
+            Attribute::READONLY | Attribute::ENUMERABLE,
+        )
+    }
+
+    fn actors_getter(data: Arc<Mutex<Data>>, language: LanguageTag) -> NativeFunction {
+        unsafe {
+            NativeFunction::from_closure(
+                move |_this: &JsValue,
+                      _args: &[JsValue],
+                      context: &mut Context|
+                      -> JsResult<JsValue> {
+                    tracing::debug!("Accessing 'actors' property from BoaCtx");
+                    let actors = {
+                        let data = data.lock().unwrap();
+                        let current = data.language.as_ref().unwrap_or(&language);
+                        let actors = data.dialogue_ctx.actors();
+                        actors.to_script_json(current, &language)
+                    };
+                    let actors = (actors.as_array().into_iter().flatten())
+                        .enumerate()
+                        .map(|(index, actor)| {
+                            let actor = JsValue::from_json(actor, context)?;
+                            if let Some(object) = actor.as_object() {
+                                Self::define_actor_state(&object, &data, index as u8, context)?;
+                                object.set_integrity_level(IntegrityLevel::Frozen, context)?;
+                            }
+                            Ok(actor)
+                        })
+                        .collect::<JsResult<Vec<_>>>()?;
+                    let actors = JsArray::from_iter(actors, context);
+                    actors.set_integrity_level(IntegrityLevel::Frozen, context)?;
+                    Ok(actors.into())
+                },
+            )
+        }
+    }
+
+    /// Defines the current `emotion` of the actor at `index`, which scripts may set or reset with
+    /// `null`, and the read-only `portrait` matching it.
+    fn define_actor_state(
+        object: &JsObject,
+        data: &Arc<Mutex<Data>>,
+        index: u8,
+        context: &mut Context,
+    ) -> JsResult<()> {
+        let emotion_data = data.clone();
+        let emotion_getter = unsafe {
+            NativeFunction::from_closure(move |_this, _args, _context| {
+                let data = emotion_data.lock().unwrap();
+                Ok((data.emotions.get(index))
+                    .map_or(JsValue::null(), |emotion| js_string!(emotion).into()))
+            })
+        };
+        let emotion_data = data.clone();
+        let emotion_setter = unsafe {
+            NativeFunction::from_closure(move |_this, args, context| {
+                let emotion = match args.first() {
+                    None => None,
+                    Some(value) if value.is_null_or_undefined() => None,
+                    Some(value) => Some(value.to_string(context)?.to_std_string_escaped()),
+                };
+                emotion_data.lock().unwrap().emotions.set(index, emotion);
+                Ok(JsValue::undefined())
+            })
+        };
+        let portrait_data = data.clone();
+        let portrait_getter = unsafe {
+            NativeFunction::from_closure(move |_this, _args, _context| {
+                let data = portrait_data.lock().unwrap();
+                let actor = data.dialogue_ctx.actors().get(index as usize);
+                let portrait = actor.and_then(|actor| actor.portrait(data.emotions.get(index)));
+                Ok(portrait.map_or(JsValue::null(), |portrait| js_string!(portrait).into()))
+            })
+        };
+
+        object.define_property_or_throw(
+            js_string!("emotion"),
+            PropertyDescriptor::builder()
+                .get(emotion_getter.to_js_function(context.realm()))
+                .set(emotion_setter.to_js_function(context.realm()))
+                .enumerable(true)
+                .configurable(false)
+                .build(),
+            context,
+        )?;
+        object.define_property_or_throw(
+            js_string!("portrait"),
+            PropertyDescriptor::builder()
+                .get(portrait_getter.to_js_function(context.realm()))
+                .enumerable(true)
+                .configurable(false)
+                .build(),
+            context,
+        )?;
+        Ok(())
+    }
+
     fn define_property(
         context: &mut Context,
         name: &str,
@@ -168,7 +991,7 @@ impl BoaCtx {
         }
     }
 
-    fn lines_getter(data: Arc<Mutex<Data>>) -> NativeFunction {
+    fn lines_getter(data: Arc<Mutex<Data>>, clock: Arc<dyn Clock>) -> NativeFunction {
         unsafe {
             NativeFunction::from_closure(
                 move |_this: &JsValue,
@@ -177,7 +1000,9 @@ impl BoaCtx {
                       -> JsResult<JsValue> {
                     tracing::debug!("Accessing 'lines' property from BoaCtx");
                     let data = data.lock().unwrap();
-                    let lines = data.lines_or_panic();
+                    let lines = data.lines().ok_or_else(|| {
+                        JsNativeError::error().with_message("No lines for the current node")
+                    })?;
                     let lines_obj = JsObject::with_object_proto(context.intrinsics());
 
                     for (index, (line_id, visiting_counting)) in lines.iter().enumerate() {
@@ -219,6 +1044,7 @@ impl BoaCtx {
 
                                     let key = js_string!("selected_at");
                                     let value = Self::create_date_from_instant(
+                                        clock.as_ref(),
                                         &selected.selected_at,
                                         context,
                                     )?;
@@ -231,7 +1057,7 @@ impl BoaCtx {
                             VisitingCounting::Call(state) => {
                                 if let Some(returned_value) = state.returned_value() {
                                     let key = js_string!("returned");
-                                    let value = returned_value.clone();
+                                    let value = returned_value.to_js(context)?;
                                     line_obj.set(key, value, true, context)?;
                                 }
                             }
@@ -266,16 +1092,11 @@ impl BoaCtx {
     }
 
     fn create_date_from_instant(
+        clock: &dyn Clock,
         instant: &std::time::Instant,
         context: &mut Context,
     ) -> JsResult<JsObject> {
-        use std::time::{SystemTime, UNIX_EPOCH};
-
-        let timestamp_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .sub(instant.elapsed())
-            .as_millis();
+        let timestamp_ms = clock.unix_time(*instant).as_millis();
 
         let date_ctor = context
             .global_object()
@@ -286,3 +1107,383 @@ impl BoaCtx {
         date_ctor.construct(&[JsValue::from(timestamp_ms as f64)], None, context)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BoaCtx;
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store};
+    use dialogue::Location;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_script_limits() {
+        use crate::{Config, LimitExceeded, RunnerError, ScriptLimits};
+
+        let config = Config {
+            limits: ScriptLimits {
+                loop_iterations: 1000,
+                recursion: 64,
+                call_stack_depth: 16,
+                lines_per_advance: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = Engine::builder().config(config).build();
+
+        let run = |source: &str| {
+            let dialogue: Dialogue = source.parse().unwrap();
+            let store = &Store::new(&engine, Data::default());
+            match Runner::instantiate(store, Arc::new(dialogue))
+                .err()
+                .unwrap()
+            {
+                RunnerError::Limit { limit, .. } => Some(limit),
+                _ => None,
+            }
+        };
+
+        assert!(matches!(
+            run("nodes:\n  main:\n  - eval: while (true) {}"),
+            Some(LimitExceeded::Runtime(_))
+        ));
+        assert!(matches!(
+            run("nodes:\n  main:\n  - eval: (function f() { f() })()"),
+            Some(LimitExceeded::Runtime(_))
+        ));
+        assert_eq!(
+            run("nodes:\n  main:\n  - call: sub\n  sub:\n  - call: sub"),
+            Some(LimitExceeded::CallStackDepth(16))
+        );
+        assert_eq!(
+            run("nodes:\n  main:\n  - goto: 0"),
+            Some(LimitExceeded::Lines(100))
+        );
+        assert_eq!(run("nodes:\n  main:\n  - eval: notDefined()"), None);
+    }
+
+    #[test]
+    fn test_time_budget_and_stack_size() {
+        use crate::{Config, LimitExceeded, ManualClock, RunnerError, ScriptLimits};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let clock = Arc::new(ManualClock::default());
+        let config = Config {
+            limits: ScriptLimits {
+                loop_iterations: u64::MAX,
+                stack_size: 64,
+                time_budget: Some(Duration::from_secs(1)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .function("tick", {
+                let clock = clock.clone();
+                move || {
+                    clock.advance(Duration::from_millis(100));
+                    Ok::<_, String>(())
+                }
+            })
+            .build();
+
+        let run = |source: &str| {
+            let dialogue: Dialogue = source.parse().unwrap();
+            let store = &Store::new(&engine, Data::default());
+            Runner::instantiate(store, Arc::new(dialogue)).err()
+        };
+
+        let error = run("nodes:\n  main:\n  - eval: for (;;) tick()").unwrap();
+        assert!(
+            matches!(
+                error,
+                RunnerError::Limit { limit: LimitExceeded::TimeBudget { budget, elapsed }, .. }
+                    if budget == Duration::from_secs(1) && elapsed > budget
+            ),
+            "{error}"
+        );
+
+        let nine_ticks = "  - eval: for (let i = 0; i < 9; i++) tick()\n";
+        let error = run(&format!(
+            "nodes:\n  main:\n{nine_ticks}{nine_ticks}  - message: a"
+        ));
+        assert_eq!(error, None, "each line should get a budget of its own");
+
+        let source = "(function f(n, ...rest) { return n && f(n - 1, ...rest) })(32, 1, 2, 3)";
+        let error = run(&format!("nodes:\n  main:\n  - eval: '{source}'")).unwrap();
+        assert!(
+            matches!(
+                error,
+                RunnerError::Limit {
+                    limit: LimitExceeded::Runtime(_),
+                    ..
+                }
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_scripts_after_time_budget() {
+        use crate::{Config, LimitExceeded, ManualClock, RunnerError, ScriptLimits};
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        let clock = Arc::new(ManualClock::default());
+        let config = Config {
+            limits: ScriptLimits {
+                time_budget: Some(Duration::from_secs(1)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let slow = Arc::new(AtomicBool::new(true));
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .function("tick", {
+                let clock = clock.clone();
+                move || {
+                    clock.advance(Duration::from_millis(100));
+                    Ok::<_, String>(())
+                }
+            })
+            .function("slow", {
+                let slow = slow.clone();
+                move || Ok::<_, String>(slow.swap(false, Ordering::Relaxed))
+            })
+            .build();
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: count = 1
+  - message: first
+  - message: second
+  - eval: if (slow()) for (;;) tick()
+  - eval: count += 1
+  - message: ${count}
+"#
+        .parse()
+        .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+
+        let error = runner.dispatch(Action::Advance).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                RunnerError::Limit {
+                    limit: LimitExceeded::TimeBudget { .. },
+                    ..
+                }
+            ),
+            "{error}"
+        );
+
+        assert!(runner.dispatch(Action::Rollback(1)).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("2"));
+    }
+
+    #[test]
+    fn test_compiled_scripts() {
+        use crate::RunnerError;
+
+        let engine = Engine::default();
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: count = 0
+  - message: plain `text`
+  - eval: count += 1
+  - if: count < 3
+    goto: 1
+  - message: ${count}
+"#
+        .parse()
+        .unwrap();
+        let mut ctx = BoaCtx::default();
+        ctx.compile(&dialogue.nodes).unwrap();
+        assert_eq!(ctx.compiled_scripts(), 4);
+        let location = |position: usize| Location {
+            node_key: "main".into(),
+            line_position: position.into(),
+        };
+        ctx.start_line(&location(0), Vec::new());
+        ctx.eval_str("count = 0").unwrap();
+        ctx.start_line(&location(2), Vec::new());
+        for _ in 0..3 {
+            ctx.eval_str("count += 1").unwrap();
+        }
+        assert_eq!(ctx.compiled_scripts(), 4);
+
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        let parsed = runner.parsed_scripts();
+        let mut messages = Vec::new();
+        while !runner.is_terminated() {
+            runner.dispatch(Action::Skip).unwrap();
+            runner.update_view().unwrap();
+            messages.extend(runner.view().message().map(str::to_string));
+            runner.dispatch(Action::Advance).unwrap();
+        }
+        assert_eq!(
+            messages,
+            ["plain `text`", "plain `text`", "plain `text`", "3"]
+        );
+        assert_eq!(
+            runner.parsed_scripts(),
+            parsed,
+            "later advances should run the scripts parsed when the dialogue was loaded"
+        );
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - exit: 0\n  - message: ${1 +}"
+            .parse()
+            .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        assert!(matches!(
+            Runner::instantiate(store, Arc::new(dialogue)),
+            Err(RunnerError::Script { ref location, ref script, .. })
+                if location.to_string() == "main:1" && script == "${1 +}"
+        ));
+    }
+
+    #[test]
+    fn test_text_templates() {
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: name = "Dia"
+  - message: 'It`s ${name}, \${name} \\ ${{ {n: 1}.n }}${"}"}\n\u00e9'
+"#
+        .parse()
+        .unwrap();
+        let engine = Engine::default();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("It`s Dia, ${name} \\ 1}\n\u{e9}")
+        );
+    }
+
+    #[test]
+    fn test_script_globals() {
+        use crate::{CheckIssue, Config, RunnerError};
+
+        let dialogue: Dialogue = r#"
+actor:
+  num: 2
+args:
+  lines: string
+  count: mut number
+nodes:
+  main:
+  - eval: args.count += 1
+  - message: ${actors[1].name} (${actors[1].mood}) ${args.lines} ${args.count} ${engine.language}
+"#
+        .parse()
+        .unwrap();
+
+        let engine = Engine::default();
+        let dialogue_ctx = || {
+            DialogueCtx::builder()
+                .actors(serde_json::json!([{ "name": "Alice", "mood": "calm" }]))
+                .unwrap()
+                .system_actor(true)
+                .args(serde_json::json!({ "lines": "a", "count": 1 }))
+                .build()
+        };
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx()));
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("Alice (calm) a 2 en"));
+        runner.eval_for_assert(
+            r#"
+            assert_eq(actors[0].system, true, "the system actor should be flagged");
+            actors[1].name = "Bob";
+            assert_eq(actors[1].name, "Alice", "actors should be read-only");
+            args.lines = "b";
+            assert_eq(args.lines, "a", "immutable args should be read-only");
+            assert_eq(typeof lines, "object", "args should not shadow lines");
+        "#,
+        );
+
+        let dialogue: Dialogue = "actor:\n  num: 2\nargs:\n  lines: string\n  count: mut number\n\
+            nodes:\n  main:\n  - eval: args.lines = args.cuont"
+            .parse()
+            .unwrap();
+        let config = Config {
+            check_scripts: true,
+            ..Default::default()
+        };
+        let engine = Engine::builder().config(config).build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx()));
+        assert!(matches!(
+            Runner::instantiate(store, Arc::new(dialogue)),
+            Err(RunnerError::Check { issue: CheckIssue::UnknownArg(ref name), .. })
+                if name == "cuont"
+        ));
+    }
+
+    #[test]
+    fn test_script_state() {
+        let dialogue: Dialogue = r#"
+args:
+  count: mut number
+nodes:
+  main:
+  - eval: |
+      var visits = 1;
+      let party = ["Alice"];
+      const limit = 3;
+      function greet(name) { return `hi ${name} (${limit})` }
+      stats = { gold: 10, double() { return this.gold * 2 } };
+      args.count += 1;
+  - message: ${Math.random() < 1}
+  - eval: visits += 1; party.push("Bob"); stats.gold += 1
+  - message: ${greet(party.join("+"))} ${visits} ${stats.double()} ${args.count}
+"#
+        .parse()
+        .unwrap();
+        let engine = Engine::default();
+        let dialogue_ctx = DialogueCtx::builder()
+            .args(serde_json::json!({ "count": 1 }))
+            .build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("hi Alice+Bob (3) 2 22 2"),
+            "each advance should see what the previous ones left"
+        );
+        runner.eval_for_assert(
+            r#"
+            assert_eq(typeof limit, "number", "declarations should be kept");
+            let assigned = true;
+            try { limit = 4 } catch { assigned = false }
+            assert_eq(assigned, false, "constants should stay constant");
+            assert_eq(args.count, 2, "mutable args should be kept");
+        "#,
+        );
+    }
+}
@@ -0,0 +1,533 @@
+use super::error::RunnerError;
+use dialogue::{Dialogue, Expression, Line, LineType, Location, Template};
+
+use boa_engine::ast::expression::access::{
+    PropertyAccess, PropertyAccessField, SimplePropertyAccess,
+};
+use boa_engine::ast::expression::operator::assign::AssignTarget;
+use boa_engine::ast::expression::operator::update::UpdateTarget;
+use boa_engine::ast::expression::operator::{Assign, Update};
+use boa_engine::ast::function::{
+    AsyncFunctionExpression, AsyncGeneratorExpression, ClassExpression, FormalParameterList,
+    FunctionBody, FunctionExpression, GeneratorExpression,
+};
+use boa_engine::ast::scope::Scope;
+use boa_engine::ast::statement::iteration::{
+    ForInLoop, ForLoop, ForLoopInitializer, ForOfLoop, IterableLoopInitializer,
+};
+use boa_engine::ast::statement::{Block, Catch, Switch};
+use boa_engine::ast::visitor::{VisitWith, Visitor};
+use boa_engine::ast::{self, operations};
+use boa_engine::interner::{Interner, Sym};
+use boa_engine::parser::Parser;
+use boa_engine::{Context, Source};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::ops::ControlFlow;
+
+/// A mistake in a script found by [`check`] before the dialogue runs.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum CheckIssue {
+    #[error("`{0}` is neither an arg, a global nor a host function, and no script assigns it")]
+    UnknownIdentifier(String),
+    #[error("No arg is named `{0}`")]
+    UnknownArg(String),
+    #[error("No line of this node has the id `{0}`")]
+    UnknownLineId(String),
+    #[error("`{0}` is not in the state of that line")]
+    UnknownLineField(String),
+    #[error("Arg `{0}` is not `mut` and cannot be assigned")]
+    ImmutableArg(String),
+}
+
+/// Resolves the free identifiers of every script of `dialogue` against its args, the globals of
+/// `context`, the globals the scripts declare or assign and the names declared in the script
+/// itself, its `args.<name>` accesses against its args, its `lines.<id>` accesses against the
+/// line ids of their node and the fields read from those lines, `self`, `prev` and `next`
+/// against the state of the line they refer to.
+///
+/// Scripts which do not parse are skipped, as compiling them reports the error.
+pub(crate) fn check(dialogue: &Dialogue, context: &mut Context) -> Result<(), RunnerError> {
+    let mut interner = Interner::default();
+    let mut scripts = Vec::new();
+    for (node_key, node) in dialogue.nodes.iter() {
+        for (position, line) in node.iter().enumerate() {
+            let location = Location {
+                node_key: node_key.clone(),
+                line_position: position.into(),
+            };
+            for expression in line.expressions() {
+                let (script, sources) = match expression {
+                    Expression::Script(source) => (source, vec![source.to_string()]),
+                    Expression::Template(text) => match Template::parse(text) {
+                        Ok(template) => {
+                            (text, template.holes().map(|h| format!("({h})")).collect())
+                        }
+                        Err(_) => continue,
+                    },
+                };
+                let mut names = Names::default();
+                for source in sources {
+                    let parsed = Parser::new(Source::from_bytes(&source))
+                        .parse_script(&Scope::new_global(), &mut interner);
+                    if let Ok(parsed) = parsed {
+                        names
+                            .globals
+                            .extend(operations::lexically_declared_names(&parsed));
+                        names
+                            .globals
+                            .extend(operations::var_declared_names(&parsed));
+                        let _ = names.visit_script(&parsed);
+                    }
+                }
+                scripts.push((location.clone(), script, names));
+            }
+        }
+    }
+
+    let resolve = |sym: Sym| interner.resolve_expect(sym).to_string();
+    let mut known: HashSet<String> = (dialogue.args.keys())
+        .map(|name| name.to_string())
+        .chain(["arguments".to_string()])
+        .collect();
+    let globals = context.global_object().own_property_keys(context);
+    known.extend(globals.unwrap_or_default().iter().map(ToString::to_string));
+    // Top-level declarations and assignments to undeclared names outlive their script, unlike
+    // parameters and declarations nested in functions or blocks.
+    known.extend((scripts.iter()).flat_map(|(_, _, names)| {
+        (names.globals.iter())
+            .chain(&names.assigned)
+            .map(|&sym| resolve(sym))
+    }));
+
+    for (location, script, names) in &scripts {
+        let issue = |issue| RunnerError::Check {
+            location: location.clone(),
+            issue,
+            script: script.to_string(),
+        };
+        if let Some(name) = (names.referenced.iter())
+            .map(|&sym| resolve(sym))
+            .find(|name| !known.contains(name))
+        {
+            return Err(issue(CheckIssue::UnknownIdentifier(name)));
+        }
+
+        let fields = |accesses: &[(Sym, Sym)], object: &str| {
+            (accesses.iter())
+                .filter(|&&(target, _)| resolve(target) == object)
+                .map(|&(_, field)| resolve(field))
+                .collect::<Vec<_>>()
+        };
+        let is_arg = |name: &str| dialogue.args.contains_key(&name.into());
+        if let Some(name) = (fields(&names.accesses, "args").into_iter()).find(|name| !is_arg(name))
+        {
+            return Err(issue(CheckIssue::UnknownArg(name)));
+        }
+
+        let node = &dialogue.nodes[&location.node_key];
+        let line_by_id = |id: &str| node.iter().find(|line| line.id.as_deref() == Some(id));
+        if let Some(id) = (fields(&names.accesses, "lines").into_iter())
+            .find(|id| id.parse::<usize>().is_err() && line_by_id(id).is_none())
+        {
+            return Err(issue(CheckIssue::UnknownLineId(id)));
+        }
+
+        let position = *location.line_position as isize;
+        let relative = [("self", 0), ("prev", -1), ("next", 1)].map(|(name, offset)| {
+            let line = usize::try_from(position + offset)
+                .ok()
+                .and_then(|i| node.get(i));
+            (name.to_string(), line)
+        });
+        let line_fields = (names.line_fields.iter())
+            .filter(|&&(lines, _, _)| resolve(lines) == "lines")
+            .map(|&(_, id, field)| {
+                let id = resolve(id);
+                (line_by_id(&id), format!("lines.{id}"), resolve(field))
+            })
+            .chain(relative.into_iter().flat_map(|(name, line)| {
+                fields(&names.accesses, &name)
+                    .into_iter()
+                    .map(move |field| (line, name.clone(), field))
+            }));
+        for (line, path, field) in line_fields {
+            if let Some(line) = line
+                && !has_state_field(line, &field)
+            {
+                return Err(issue(CheckIssue::UnknownLineField(format!(
+                    "{path}.{field}"
+                ))));
+            }
+        }
+
+        let assigned = (names.assigned.iter().map(|&sym| resolve(sym)))
+            .chain(fields(&names.assigned_fields, "args"));
+        if let Some(name) = assigned.into_iter().find(|name| {
+            (dialogue.args.get(&name.as_str().into())).is_some_and(|arg| !arg.is_mutable())
+        }) {
+            return Err(issue(CheckIssue::ImmutableArg(name)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether scripts can read `field` from the state of `line`, as built by the `lines` global.
+fn has_state_field(line: &Line, field: &str) -> bool {
+    let type_fields: &[&str] = match line.r#type {
+        LineType::Confirm(_) => &["approved", "rejected"],
+        LineType::Choice(_) => &["selected", "selected_at"],
+        LineType::Call(_) => &["returned"],
+        _ => &[],
+    };
+    ["id", "visited", "visited_count", "visited_count_next"]
+        .iter()
+        .chain(type_fields)
+        .any(|&name| name == field)
+}
+
+#[derive(Default)]
+struct Names {
+    /// Free identifiers, which no scope of the script declares.
+    referenced: Vec<Sym>,
+    /// The names the script declares at its top level, which become globals.
+    globals: Vec<Sym>,
+    /// Free identifiers the script assigns or updates.
+    assigned: Vec<Sym>,
+    /// `object.field` accesses as the symbols of `object` and `field`, for a free `object`.
+    accesses: Vec<(Sym, Sym)>,
+    assigned_fields: Vec<(Sym, Sym)>,
+    /// `object.id.field` accesses as the symbols of `object`, `id` and `field`.
+    line_fields: Vec<(Sym, Sym, Sym)>,
+    /// The names declared by each scope enclosing the node being visited, innermost last.
+    scopes: Vec<Vec<Sym>>,
+    /// Set between the parameters of a function and its body, which share a scope.
+    parameters: bool,
+}
+
+impl<'ast> Visitor<'ast> for Names {
+    type BreakTy = Infallible;
+
+    fn visit_formal_parameter_list(
+        &mut self,
+        node: &'ast FormalParameterList,
+    ) -> ControlFlow<Self::BreakTy> {
+        // Left open for the body, which always comes next.
+        self.scopes.push(operations::bound_names(node));
+        node.visit_with(self)?;
+        self.parameters = true;
+        ControlFlow::Continue(())
+    }
+
+    fn visit_function_body(&mut self, node: &'ast FunctionBody) -> ControlFlow<Self::BreakTy> {
+        let declared = (operations::var_declared_names(node).into_iter())
+            .chain(operations::lexically_declared_names(node));
+        if !std::mem::take(&mut self.parameters) {
+            // A class static block has no parameters.
+            self.scopes.push(Vec::new());
+        }
+        self.scopes.last_mut().unwrap().extend(declared);
+        let flow = node.visit_with(self);
+        self.scopes.pop();
+        flow
+    }
+
+    fn visit_function_expression(
+        &mut self,
+        node: &'ast FunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.scoped(node.name().map(|name| name.sym()), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_generator_expression(
+        &mut self,
+        node: &'ast GeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.scoped(node.name().map(|name| name.sym()), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_async_function_expression(
+        &mut self,
+        node: &'ast AsyncFunctionExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.scoped(node.name().map(|name| name.sym()), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_async_generator_expression(
+        &mut self,
+        node: &'ast AsyncGeneratorExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.scoped(node.name().map(|name| name.sym()), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_class_expression(
+        &mut self,
+        node: &'ast ClassExpression,
+    ) -> ControlFlow<Self::BreakTy> {
+        self.scoped(node.name().map(|name| name.sym()), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_block(&mut self, node: &'ast Block) -> ControlFlow<Self::BreakTy> {
+        let declared = operations::lexically_declared_names(node.statement_list());
+        self.scoped(declared, |names| node.visit_with(names))
+    }
+
+    fn visit_switch(&mut self, node: &'ast Switch) -> ControlFlow<Self::BreakTy> {
+        let declared = (node.cases().iter())
+            .flat_map(|case| operations::lexically_declared_names(case.body()));
+        self.scoped(declared, |names| node.visit_with(names))
+    }
+
+    fn visit_for_loop(&mut self, node: &'ast ForLoop) -> ControlFlow<Self::BreakTy> {
+        let declared = match node.init() {
+            Some(ForLoopInitializer::Lexical(init)) => operations::bound_names(init.declaration()),
+            _ => Vec::new(),
+        };
+        self.scoped(declared, |names| node.visit_with(names))
+    }
+
+    fn visit_for_in_loop(&mut self, node: &'ast ForInLoop) -> ControlFlow<Self::BreakTy> {
+        let declared = Self::iterable_bound_names(node.initializer());
+        self.scoped(declared, |names| node.visit_with(names))
+    }
+
+    fn visit_for_of_loop(&mut self, node: &'ast ForOfLoop) -> ControlFlow<Self::BreakTy> {
+        let declared = Self::iterable_bound_names(node.initializer());
+        self.scoped(declared, |names| node.visit_with(names))
+    }
+
+    fn visit_catch(&mut self, node: &'ast Catch) -> ControlFlow<Self::BreakTy> {
+        let declared = node.parameter().map(operations::bound_names);
+        self.scoped(declared.into_iter().flatten(), |names| {
+            node.visit_with(names)
+        })
+    }
+
+    fn visit_expression(&mut self, node: &'ast ast::Expression) -> ControlFlow<Self::BreakTy> {
+        if let ast::Expression::Identifier(identifier) = node
+            && self.is_free(identifier.sym())
+        {
+            self.referenced.push(identifier.sym());
+        }
+        node.visit_with(self)
+    }
+
+    fn visit_assign(&mut self, node: &'ast Assign) -> ControlFlow<Self::BreakTy> {
+        match node.lhs() {
+            AssignTarget::Identifier(identifier) => self.assign(identifier.sym()),
+            AssignTarget::Access(PropertyAccess::Simple(access)) => self.assign_field(access),
+            _ => (),
+        }
+        node.visit_with(self)
+    }
+
+    fn visit_update(&mut self, node: &'ast Update) -> ControlFlow<Self::BreakTy> {
+        match node.target() {
+            UpdateTarget::Identifier(identifier) => self.assign(identifier.sym()),
+            UpdateTarget::PropertyAccess(PropertyAccess::Simple(access)) => {
+                self.assign_field(access)
+            }
+            _ => (),
+        }
+        node.visit_with(self)
+    }
+
+    fn visit_simple_property_access(
+        &mut self,
+        node: &'ast SimplePropertyAccess,
+    ) -> ControlFlow<Self::BreakTy> {
+        if let Some(access) = self.field_access(node) {
+            self.accesses.push(access);
+        }
+        if let (ast::Expression::PropertyAccess(PropertyAccess::Simple(target)), field) =
+            (node.target(), node.field())
+            && let (Some((object, id)), PropertyAccessField::Const(field)) =
+                (self.field_access(target), field)
+        {
+            self.line_fields.push((object, id, field.sym()));
+        }
+        node.visit_with(self)
+    }
+}
+
+impl Names {
+    /// Visits with `declared` in scope.
+    fn scoped(
+        &mut self,
+        declared: impl IntoIterator<Item = Sym>,
+        visit: impl FnOnce(&mut Self) -> ControlFlow<Infallible>,
+    ) -> ControlFlow<Infallible> {
+        self.scopes.push(declared.into_iter().collect());
+        let flow = visit(self);
+        self.scopes.pop();
+        flow
+    }
+
+    fn is_free(&self, sym: Sym) -> bool {
+        !self.scopes.iter().any(|scope| scope.contains(&sym))
+    }
+
+    fn assign(&mut self, sym: Sym) {
+        if self.is_free(sym) {
+            self.assigned.push(sym);
+        }
+    }
+
+    fn assign_field(&mut self, access: &SimplePropertyAccess) {
+        if let Some(access) = self.field_access(access) {
+            self.assigned_fields.push(access);
+        }
+    }
+
+    /// `object.field` as the symbols of `object` and `field`, if `object` is free.
+    fn field_access(&self, access: &SimplePropertyAccess) -> Option<(Sym, Sym)> {
+        match (access.target(), access.field()) {
+            (ast::Expression::Identifier(target), PropertyAccessField::Const(field))
+                if self.is_free(target.sym()) =>
+            {
+                Some((target.sym(), field.sym()))
+            }
+            _ => None,
+        }
+    }
+
+    fn iterable_bound_names(initializer: &IterableLoopInitializer) -> Vec<Sym> {
+        match initializer {
+            IterableLoopInitializer::Let(binding) | IterableLoopInitializer::Const(binding) => {
+                operations::bound_names(binding)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, Data, DialogueCtx, Engine, Runner, Store};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_check_scripts() {
+        let config = Config {
+            check_scripts: true,
+            ..Default::default()
+        };
+        let engine = Engine::builder()
+            .config(config)
+            .function("greet", |name: String| {
+                Ok::<_, String>(format!("Hi, {name}"))
+            })
+            .build();
+        let check = |source: &str| {
+            let args = "args:\n  name: string\n  count: mut number\n";
+            let dialogue: Dialogue = format!("{args}{source}").parse().unwrap();
+            let dialogue_ctx = DialogueCtx::builder()
+                .args(serde_json::json!({ "name": "a", "count": 0 }))
+                .build();
+            let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+            match Runner::instantiate(store, Arc::new(dialogue)) {
+                Ok(_) => None,
+                Err(RunnerError::Check {
+                    location, issue, ..
+                }) => Some((location.to_string(), issue)),
+                Err(error) => panic!("unexpected error: {error}"),
+            }
+        };
+
+        let valid = r#"
+nodes:
+  main:
+  - id: q1
+    choice:
+      foo: Foo
+  - eval: count += 1; greet(name); seen = [1].map(function f(x) { return Math.abs(x) })
+  - if: lines.q1.selected == 'foo' && seen
+    message: ${name} ${lines[0].visited_count} ${{ self.visited_count }}
+"#;
+        assert_eq!(check(valid), None);
+
+        assert_eq!(
+            check("nodes:\n  main:\n  - message: hi\n  - message: ${nmae}"),
+            Some((
+                "main:1".to_string(),
+                CheckIssue::UnknownIdentifier("nmae".to_string())
+            ))
+        );
+        assert_eq!(
+            check("nodes:\n  main:\n  - id: q1\n    message: hi\n  sub:\n  - eval: lines.q1"),
+            Some((
+                "sub:0".to_string(),
+                CheckIssue::UnknownLineId("q1".to_string())
+            ))
+        );
+        assert_eq!(
+            check("nodes:\n  main:\n  - eval: name = 'b'"),
+            Some((
+                "main:0".to_string(),
+                CheckIssue::ImmutableArg("name".to_string())
+            ))
+        );
+
+        let shadowed = r#"
+nodes:
+  main:
+  - eval: "{ let name = 1; name = 2 } [1].forEach(name => { name += 1 })"
+  - eval: "for (const name of [1]) { try {} catch (count) { count++ } } (function name() {})"
+  - eval: "function f(args) { args.name = 1; args.other++ } let args2 = { name: 1 }"
+  - eval: "if (true) { let lines = {}; lines.q2 }"
+"#;
+        assert_eq!(check(shadowed), None);
+        for source in [
+            "name++",
+            "args.name++",
+            "args.name += 'b'",
+            "(() => { name = 1 })()",
+        ] {
+            assert_eq!(
+                check(&format!("nodes:\n  main:\n  - eval: \"{source}\"")),
+                Some((
+                    "main:0".to_string(),
+                    CheckIssue::ImmutableArg("name".to_string())
+                )),
+                "{source}"
+            );
+        }
+
+        let unknown_field = |path: &str| CheckIssue::UnknownLineField(path.to_string());
+        let source = "nodes:\n  main:\n  - id: q1\n    choice:\n      foo: Foo\n  - eval: ";
+        assert_eq!(
+            check(&format!("{source}lines.q1.selectd")),
+            Some(("main:1".to_string(), unknown_field("lines.q1.selectd")))
+        );
+        assert_eq!(
+            check(&format!("{source}prev.approved")),
+            Some(("main:1".to_string(), unknown_field("prev.approved")))
+        );
+        assert_eq!(
+            check(&format!("{source}self.returned")),
+            Some(("main:1".to_string(), unknown_field("self.returned")))
+        );
+
+        let scoped = "nodes:\n  main:\n  - eval: '[1].map(x => x); let y = 1'\n  - eval: y + x";
+        assert_eq!(
+            check(scoped),
+            Some((
+                "main:1".to_string(),
+                CheckIssue::UnknownIdentifier("x".to_string())
+            ))
+        );
+    }
+}
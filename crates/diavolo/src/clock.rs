@@ -0,0 +1,163 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of time for everything the runner derives from elapsed durations: typing progress,
+/// choice timeouts, fast-forward bookkeeping and the `*_at` timestamps exposed to scripts.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Converts `instant` to the wall-clock time since the Unix epoch.
+    fn unix_time(&self, instant: Instant) -> Duration;
+
+    fn elapsed(&self, since: Instant) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_time(&self, instant: Instant) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(instant.elapsed())
+    }
+}
+
+/// A clock that only moves when told to, for tests and replays.
+#[derive(Debug)]
+pub struct ManualClock {
+    origin: Instant,
+    unix_origin: Duration,
+    elapsed: Mutex<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::with_unix_time(Duration::ZERO)
+    }
+}
+
+impl ManualClock {
+    /// Creates a clock whose origin corresponds to `unix_time` on the wall clock.
+    pub fn with_unix_time(unix_time: Duration) -> Self {
+        Self {
+            origin: Instant::now(),
+            unix_origin: unix_time,
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    pub fn set_elapsed(&self, elapsed: Duration) {
+        *self.elapsed.lock().unwrap() = elapsed;
+    }
+
+    pub fn elapsed_since_origin(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed_since_origin()
+    }
+
+    fn unix_time(&self, instant: Instant) -> Duration {
+        self.unix_origin + instant.saturating_duration_since(self.origin)
+    }
+}
+
+/// Shared clock handle that compares by identity, so it can live inside views.
+#[derive(Debug, Clone)]
+pub(crate) struct ClockHandle(Arc<dyn Clock>);
+
+impl ClockHandle {
+    pub(crate) fn new(clock: Arc<dyn Clock>) -> Self {
+        Self(clock)
+    }
+}
+
+impl PartialEq for ClockHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl std::ops::Deref for ClockHandle {
+    type Target = dyn Clock;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, Engine, Runner, Store};
+    use dialogue::ChoiceKey;
+
+    #[test]
+    fn test_manual_clock() {
+        use crate::ManualClock;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - message: abcdef
+  - id: q
+    choice:
+      foo: foo
+      bar: bar
+    options:
+      timeout: 10.0
+  - message: done
+"#
+        .parse()
+        .unwrap();
+
+        let clock = Arc::new(ManualClock::with_unix_time(Duration::from_secs(1000)));
+        let engine = Engine::builder().clock(clock.clone()).build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().as_message().unwrap().visible_str(), "");
+
+        clock.advance(Duration::from_millis(450));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().as_message().unwrap().visible_str(), "abcd");
+
+        clock.advance(Duration::from_secs(1));
+        runner.update_view().unwrap();
+        assert!(runner.view().as_message().unwrap().is_completed());
+        assert!(runner.dispatch(Action::Advance).unwrap());
+
+        clock.advance(Duration::from_secs(5));
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        assert_eq!(choice.remaining_time(), Duration::from_secs(5));
+        assert!(!choice.is_expired());
+
+        assert!(
+            runner
+                .dispatch(Action::Select(ChoiceKey::new("bar")))
+                .unwrap()
+        );
+        runner.eval_for_assert(
+            r#"assert_eq(lines.q.selected_at.getTime(), 1006450, "selected_at should follow the clock");"#,
+        );
+    }
+}
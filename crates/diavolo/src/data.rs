@@ -1,18 +1,34 @@
-use super::dialogue_ctx::DialogueCtx;
+use super::dialogue_ctx::{DialogueArgs, DialogueCtx};
+use super::emotions::Emotions;
+use super::engine::config::Config;
+use super::error::RunnerError;
+use super::line_state::*;
 use super::runner::EvaluatedLine;
+use super::script_value::ScriptValue;
 use super::state_machine::StateMachine;
 use super::view::View;
-use super::line_state::*;
 use super::visiting_states::{Lines, VisitingStates};
 
-use dialogue::{NodeKey, Nodes};
+use dialogue::{Location, NodeKey, Nodes};
+use language_tags::LanguageTag;
+
+use std::time::Instant;
 
 #[derive(Debug, Default)]
 pub struct Data {
     pub dialogue_ctx: DialogueCtx,
+    pub(crate) args: Option<DialogueArgs>,
     pub(crate) state_machine: StateMachine,
     pub(crate) visiting_states: VisitingStates,
+    pub(crate) emotions: Emotions,
+    /// Language set by [`Action::SetLanguage`](super::Action::SetLanguage), overriding the one
+    /// of the engine config.
+    pub(crate) language: Option<LanguageTag>,
     pub(crate) exit_code: Option<u8>,
+    pub(crate) pending: bool,
+    /// Call stack depth of the `on_error:` node while it runs, which returns into the line
+    /// that failed rather than a call line.
+    pub(crate) error_node_depth: Option<usize>,
 }
 
 impl Data {
@@ -23,101 +39,147 @@ impl Data {
         }
     }
 
-    pub(crate) fn goto(&mut self, nodes: &Nodes) {
-        let line_id_or_index = self
-            .visiting_state_or_panic::<GotoState>()
-            .line_id_or_index
-            .as_str();
-
-        let line_position = line_id_or_index
-            .parse::<usize>()
-            .map(|idx| idx.into())
-            .unwrap_or_else(|_| {
-                let node: &dialogue::Node = nodes
-                    .get(self.state_machine.current_node_key())
-                    .expect("Current node should exist");
-                node.iter()
-                    .enumerate()
-                    .find(|(_, line)| {
-                        line.id
-                            .as_ref()
-                            .map(|id| **id == *line_id_or_index)
-                            .unwrap_or_default()
-                    })
-                    .map(|(idx, _)| idx)
-                    .expect(format!("Line ID {:?} does not exist", line_id_or_index).as_str())
-                    .into()
-            });
-
-        self.state_machine.goto(line_position);
-    }
-
-    pub(crate) fn call(&mut self, nodes: &Nodes) {
-        let node_key = self
+    /// The language texts are shown in, which is the one of `config` unless overridden.
+    pub(crate) fn language<'a>(&'a self, config: &'a Config) -> &'a LanguageTag {
+        self.language.as_ref().unwrap_or(&config.language)
+    }
+
+    pub(crate) fn goto(&mut self, nodes: &Nodes) -> Result<(), RunnerError> {
+        let location = self.state_machine.location();
+        let line_id_or_index = self.current_state::<GotoState>()?.line_id_or_index.as_str();
+        let node = nodes
+            .get(&location.node_key)
+            .ok_or_else(|| RunnerError::internal(location, "Current node does not exist"))?;
+
+        let line_position = match line_id_or_index.parse::<usize>() {
+            Ok(idx) => Some(idx).filter(|idx| *idx < node.len()),
+            Err(_) => node.iter().position(|line| {
+                line.id
+                    .as_ref()
+                    .map(|id| **id == *line_id_or_index)
+                    .unwrap_or_default()
+            }),
+        }
+        .ok_or_else(|| RunnerError::LineNotFound {
+            location: location.clone(),
+            line: line_id_or_index.to_string(),
+        })?;
+
+        self.state_machine.goto(line_position.into());
+        Ok(())
+    }
+
+    pub(crate) fn call(&mut self, nodes: &Nodes, max_depth: usize) -> Result<(), RunnerError> {
+        let node_key = match self.state_machine.call_stack.is_empty() {
+            true => NodeKey::main(),
+            false => self.current_state::<CallState>()?.node_key.clone(),
+        };
+        self.call_node(node_key, nodes, max_depth)
+    }
+
+    pub(crate) fn call_node(
+        &mut self,
+        node_key: NodeKey,
+        nodes: &Nodes,
+        max_depth: usize,
+    ) -> Result<(), RunnerError> {
+        let location = self
             .state_machine
             .call_stack
-            .is_empty()
-            .then_some(NodeKey::main())
-            .unwrap_or_else(|| {
-                self.visiting_state_or_panic::<CallState>()
-                    .node_key
-                    .clone()
-            });
+            .last()
+            .cloned()
+            .unwrap_or_else(|| Location::uninitialized(NodeKey::main()));
         let node = nodes
             .get(&node_key)
-            .expect(format!("Node {:?} does not exist", node_key).as_str());
-        self.state_machine.call(node_key.clone());
+            .ok_or_else(|| RunnerError::NodeNotFound {
+                location: location.clone(),
+                node_key: node_key.clone(),
+            })?;
+        self.state_machine
+            .call(node_key.clone(), max_depth)
+            .map_err(|limit| limit.at(&location))?;
         self.visiting_states.ensure_node(node_key, node);
+        self.emotions.enter_node();
+        Ok(())
     }
 
-    pub(crate) fn ret(&mut self) {
+    pub(crate) fn ret(&mut self, now: Instant) -> Result<ScriptValue, RunnerError> {
         let value = self
             .visiting_state::<ReturnState>()
             .map(|r| &r.value)
             .cloned()
             .unwrap_or_default();
+        let depth = self.state_machine.call_stack.len();
         self.state_machine.call_stack.pop();
-        if !self.state_machine.call_stack.is_empty() {
-            self.visiting_state_mut_or_panic::<CallState>()
-                .ret(value);
+        self.emotions.leave_node();
+        if self.error_node_depth.take_if(|d| *d == depth).is_some()
+            || self.state_machine.call_stack.is_empty()
+        {
+            return Ok(value);
         }
+
+        self.current_state_mut::<CallState>()?
+            .ret(value.clone(), now);
+        Ok(value)
     }
 
     pub(crate) fn exit(&mut self, code: u8) {
         self.exit_code = Some(code);
         self.state_machine.call_stack.clear();
+        self.error_node_depth = None;
+        self.emotions.clear();
     }
 
-    pub(crate) fn visit_line(&mut self, evaluated_line: EvaluatedLine) {
+    pub(crate) fn visit_line(
+        &mut self,
+        evaluated_line: EvaluatedLine,
+        now: Instant,
+    ) -> Result<(), RunnerError> {
         self.visiting_states.visit_line(
             self.state_machine.location(),
             self.state_machine.is_fast_forward(),
             evaluated_line,
+            now,
         )
     }
 
-    pub(crate) fn lines_or_panic(&self) -> &Lines {
+    pub(crate) fn lines(&self) -> Option<&Lines> {
         self.visiting_states
             .lines(self.state_machine.current_node_key())
-            .expect("Lines should exist for the current node key")
     }
 
-    pub(crate) fn complete_message_or_panic(&mut self, view: &View) {
+    pub(crate) fn complete_message(
+        &mut self,
+        view: &View,
+        now: Instant,
+    ) -> Result<(), RunnerError> {
         use super::line_state::{ChoiceState, MessageState};
         match view {
-            View::Message(_) => {
-                self.visiting_state_mut_or_panic::<MessageState>()
-                    .complete();
-            }
-            View::Confirm(_) => {
-                self.visiting_state_mut_or_panic::<ConfirmState>()
-                    .complete();
-            }
-            View::Choice(_) => {
-                self.visiting_state_mut_or_panic::<ChoiceState>()
-                    .complete_message_or_panic();
+            View::Message(_) => self.current_state_mut::<MessageState>()?.complete(now),
+            View::Confirm(_) => self.current_state_mut::<ConfirmState>()?.complete(now),
+            View::Choice(_) => self
+                .current_state_mut::<ChoiceState>()?
+                .complete_message(now)
+                .map_err(|e| RunnerError::internal(self.state_machine.location(), e.to_string()))?,
+            _ => {
+                let message = "No message to complete in the current view";
+                return Err(RunnerError::internal(self.state_machine.location(), message));
             }
-            _ => panic!("No message to complete in the current view"),
+        }
+        Ok(())
+    }
+
+    /// The state of the current message, confirm or choice prompt.
+    pub(crate) fn message_state_mut(&mut self) -> Option<&mut MessageState> {
+        if self.visiting_state::<MessageState>().is_some() {
+            self.visiting_state_mut::<MessageState>()
+        } else if self.visiting_state::<ConfirmState>().is_some() {
+            self.visiting_state_mut::<ConfirmState>()
+                .map(|state| &mut state.message_state)
+        } else {
+            self.visiting_state_mut::<ChoiceState>()?
+                .message_state
+                .as_mut()
         }
     }
 
@@ -126,8 +188,10 @@ impl Data {
             .visiting_state::<T>(self.state_machine.location())
     }
 
-    pub(crate) fn visiting_state_or_panic<T: LineState>(&self) -> &T {
-        self.visiting_state::<T>().unwrap()
+    /// The visiting state of the current line, which the runner expects to exist.
+    pub(crate) fn current_state<T: LineState>(&self) -> Result<&T, RunnerError> {
+        self.visiting_state::<T>()
+            .ok_or_else(|| Self::missing_state::<T>(self.state_machine.location()))
     }
 
     pub(crate) fn visiting_state_mut<T: LineState>(&mut self) -> Option<&mut T> {
@@ -135,7 +199,14 @@ impl Data {
             .visiting_state_mut::<T>(self.state_machine.location())
     }
 
-    pub(crate) fn visiting_state_mut_or_panic<T: LineState>(&mut self) -> &mut T {
-        self.visiting_state_mut::<T>().unwrap()
+    pub(crate) fn current_state_mut<T: LineState>(&mut self) -> Result<&mut T, RunnerError> {
+        let location = self.state_machine.location().clone();
+        self.visiting_state_mut::<T>()
+            .ok_or_else(|| Self::missing_state::<T>(&location))
+    }
+
+    fn missing_state<T>(location: &Location) -> RunnerError {
+        let name = std::any::type_name::<T>().rsplit("::").next().unwrap_or_default();
+        RunnerError::internal(location, format!("No {name} for the current line"))
     }
 }
@@ -8,6 +8,9 @@ pub use args::*;
 pub struct DialogueCtx {
     actors: Actors,
     args: Option<Args>,
+    /// The actor definitions the actors were read from, as given, for recordings.
+    actors_json: Option<serde_json::Value>,
+    system_actor: bool,
 }
 
 impl DialogueCtx {
@@ -19,23 +22,38 @@ impl DialogueCtx {
         &self.actors
     }
 
+    pub(crate) fn bind_roles(
+        &mut self,
+        roles: &[dialogue::ActorRole],
+    ) -> Result<(), crate::RunnerError> {
+        self.actors.bind_roles(roles)
+    }
+
     pub fn actors_count(&self) -> u8 {
         self.actors.len() as u8
     }
 
     pub fn actor(&self, owner: &dialogue::Owner) -> Option<&Actor> {
-        self.actors.get(**owner as usize)
+        self.actors.get_by_ref(owner)
     }
 
     pub fn args(&self) -> &Option<Args> {
         &self.args
     }
 
+    pub(crate) fn actors_json(&self) -> Option<&serde_json::Value> {
+        self.actors_json.as_ref()
+    }
+
+    pub(crate) fn has_system_actor(&self) -> bool {
+        self.system_actor
+    }
+
     pub fn parsed_args(
         &mut self,
         dialogue_args: &dialogue::Args,
     ) -> Result<Option<DialogueArgs>, Box<dyn std::error::Error>> {
-        match self.args.take() {
+        match self.args.as_ref() {
             Some(args) => Ok(Some(args.try_to_parsed(dialogue_args)?)),
             None if !dialogue_args.is_empty() => Err("No args provided".into()),
             None => Ok(None),
@@ -46,13 +64,15 @@ impl DialogueCtx {
 #[derive(Debug, Default)]
 pub struct DialogueCtxBuilder {
     actors: Option<Actors>,
+    actors_json: Option<serde_json::Value>,
     args: Option<Args>,
     system_actor: bool,
 }
 
 impl DialogueCtxBuilder {
     pub fn actors(mut self, actors: serde_json::Value) -> Result<Self, Box<dyn std::error::Error>> {
-        self.actors.replace(actors.try_into()?);
+        self.actors.replace(actors.clone().try_into()?);
+        self.actors_json.replace(actors);
         Ok(self)
     }
 
@@ -61,6 +81,8 @@ impl DialogueCtxBuilder {
         self
     }
 
+    /// Puts the system actor first. Actors bound by role are left as is, as a role can be bound
+    /// to `{ "system": true }` instead.
     pub fn system_actor(mut self, condition: bool) -> Self {
         self.system_actor = condition;
         self
@@ -69,12 +91,74 @@ impl DialogueCtxBuilder {
     pub fn build(self) -> DialogueCtx {
         let mut ctx = DialogueCtx::default();
         if let Some(mut actors) = self.actors {
-            if self.system_actor {
+            if self.system_actor && !actors.is_bound_by_role() {
                 actors.insert(0, Actor::system());
             }
             ctx.actors = actors;
         }
         ctx.args = self.args;
+        ctx.actors_json = self.actors_json;
+        ctx.system_actor = self.system_actor;
         ctx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_actor_roles() {
+        use crate::RunnerError;
+
+        let dialogue: Arc<Dialogue> = r#"
+actor:
+  roles: [hero, merchant]
+nodes:
+  main:
+  - owner: merchant
+    message: Welcome, ${actors[0].name}!
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let engine = Engine::default();
+        let instantiate = |actors: serde_json::Value| {
+            let dialogue_ctx = DialogueCtx::builder()
+                .actors(actors)
+                .unwrap()
+                .system_actor(true)
+                .build();
+            let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+            Runner::instantiate(store, dialogue.clone())
+        };
+
+        let actors =
+            serde_json::json!({ "merchant": { "name": "Bob" }, "hero": { "name": "Alice" } });
+        let mut runner = instantiate(actors.clone()).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().actor().unwrap().name(), "Bob");
+        assert_eq!(runner.view().message(), Some("Welcome, Alice!"));
+        let data = runner.store().data.lock().unwrap();
+        assert_eq!(data.dialogue_ctx.actors().to_json(), actors);
+        drop(data);
+
+        assert!(matches!(
+            instantiate(serde_json::json!({ "hero": { "name": "Alice" } })),
+            Err(RunnerError::UnboundRole { ref role }) if role == "merchant"
+        ));
+        let actors = serde_json::json!({
+            "hero": { "name": "Alice" },
+            "merchant": { "name": "Bob" },
+            "villain": { "name": "Eve" }
+        });
+        assert!(matches!(
+            instantiate(actors),
+            Err(RunnerError::UnknownRole { ref role }) if role == "villain"
+        ));
+    }
+}
@@ -6,17 +6,95 @@ pub struct Actors(Vec<Actor>);
 impl TryFrom<serde_json::Value> for Actors {
     type Error = Box<dyn std::error::Error>;
 
+    /// Reads an array of actor definitions, or an object of them by the role they play.
     fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
-        let actors = value
-            .as_array()
-            .ok_or("Actors should be a JSON array of actor definitions")?
-            .into_iter()
-            .map(|v| v.try_into())
-            .collect::<Result<_, _>>()?;
+        let actors = match value {
+            serde_json::Value::Array(actors) => actors
+                .iter()
+                .map(|v| v.try_into())
+                .collect::<Result<_, _>>()?,
+            serde_json::Value::Object(roles) => roles
+                .iter()
+                .map(|(role, v)| {
+                    let mut actor = Actor::try_from(v)?;
+                    actor.role = Some(role.as_str().into());
+                    Ok(actor)
+                })
+                .collect::<Result<_, Self::Error>>()?,
+            _ => return Err("Actors should be a JSON array or object of actor definitions".into()),
+        };
         Ok(Actors(actors))
     }
 }
 
+impl Actors {
+    /// Inverse of the `TryFrom<serde_json::Value>` conversion, system actors included.
+    pub fn to_json(&self) -> serde_json::Value {
+        if self.is_bound_by_role() {
+            let roles = self.iter().filter_map(|actor| {
+                let role = actor.role.as_ref()?;
+                Some((role.to_string(), actor.to_json()))
+            });
+            return roles.collect::<serde_json::Map<_, _>>().into();
+        }
+        self.iter().map(Actor::to_json).collect()
+    }
+
+    /// The actors as scripts see them, named in `lang`, see [`ActorName::get`].
+    pub fn to_script_json(&self, lang: &LanguageTag, default: &LanguageTag) -> serde_json::Value {
+        self.iter()
+            .map(|actor| actor.to_script_json(lang, default))
+            .collect()
+    }
+
+    pub fn is_bound_by_role(&self) -> bool {
+        self.iter().any(|actor| actor.role.is_some())
+    }
+
+    pub fn get_by_ref(&self, actor: &ActorRef) -> Option<&Actor> {
+        self.get(self.index_of(actor)? as usize)
+    }
+
+    pub fn index_of(&self, actor: &ActorRef) -> Option<u8> {
+        match actor {
+            ActorRef::Index(index) => Some(*index).filter(|index| (*index as usize) < self.len()),
+            ActorRef::Role(role) => (self.iter())
+                .position(|actor| actor.role.as_ref() == Some(role))
+                .map(|index| index as u8),
+        }
+    }
+
+    /// Orders actors bound by role as `roles` declares them, so that indexes follow the roles.
+    pub(crate) fn bind_roles(&mut self, roles: &[ActorRole]) -> Result<(), RunnerError> {
+        if !self.is_bound_by_role() {
+            return Ok(());
+        }
+        if let Some(actor) = self
+            .iter()
+            .find(|actor| (actor.role.as_ref()).is_none_or(|role| !roles.contains(role)))
+        {
+            return Err(RunnerError::UnknownRole {
+                role: actor
+                    .role
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            });
+        }
+        let mut actors = std::mem::take(&mut self.0);
+        for role in roles {
+            let index = (actors
+                .iter()
+                .position(|actor| actor.role.as_ref() == Some(role)))
+            .ok_or_else(|| RunnerError::UnboundRole {
+                role: role.to_string(),
+            })?;
+            self.0.push(actors.remove(index));
+        }
+        Ok(())
+    }
+}
+
 impl FromIterator<Actor> for Actors {
     fn from_iter<I: IntoIterator<Item = Actor>>(iter: I) -> Self {
         Self(iter.into_iter().collect())
@@ -46,7 +124,19 @@ impl DerefMut for Actors {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Actor {
     pub(super) system: bool,
+    /// Role the actor is bound to, when the actors are given by role.
+    pub(super) role: Option<ActorRole>,
     pub(super) name: ActorName,
+    /// Portrait shown while the actor has no emotion, or one it has no portrait for.
+    pub(super) portrait: Option<String>,
+    /// Portraits by emotion.
+    pub(super) emotions: BTreeMap<String, String>,
+    pub(super) color: Option<String>,
+    /// Multiplies the typing speed of the messages of the actor.
+    pub(super) typing_speed: TypingSpeedFactor,
+    pub(super) voice: Option<String>,
+    /// Fields of the actor definition other than the ones above, for scripts and views to read.
+    pub(super) props: serde_json::Map<String, serde_json::Value>,
 }
 
 impl TryFrom<&serde_json::Value> for Actor {
@@ -55,40 +145,208 @@ impl TryFrom<&serde_json::Value> for Actor {
     fn try_from(value: &serde_json::Value) -> Result<Self, Self::Error> {
         let map = value.as_object().ok_or("Actor should be a JSON object")?;
 
-        let name = map
-            .get("name")
-            .and_then(|v| v.as_str())
-            .ok_or("Actor must have a 'name' field of type string")?;
+        if map.get("system").and_then(|v| v.as_bool()) == Some(true) {
+            return Ok(Actor::system());
+        }
+
+        let name = match map.get("name") {
+            Some(serde_json::Value::String(name)) => ActorName::from(name.as_str()),
+            Some(serde_json::Value::Object(names)) => ActorName::Multilingual(
+                names
+                    .iter()
+                    .map(|(lang, name)| {
+                        let name = name.as_str().ok_or("Actor name should be a string")?;
+                        Ok((lang.parse()?, name.to_string()))
+                    })
+                    .collect::<Result<_, Self::Error>>()?,
+            ),
+            _ => return Err("Actor must have a 'name' field of type string or object".into()),
+        };
+
+        let string = |key: &str| match map.get(key) {
+            None => Ok(None),
+            Some(serde_json::Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(format!("Actor '{key}' should be a string")),
+        };
+
+        let emotions = match map.get("emotions") {
+            None => BTreeMap::new(),
+            Some(serde_json::Value::Object(emotions)) => emotions
+                .iter()
+                .map(|(emotion, portrait)| {
+                    let portrait = portrait
+                        .as_str()
+                        .ok_or("Actor portraits should be strings")?;
+                    Ok((emotion.clone(), portrait.to_string()))
+                })
+                .collect::<Result<_, Self::Error>>()?,
+            Some(_) => return Err("Actor 'emotions' should be an object of portraits".into()),
+        };
+
+        let typing_speed = match map.get("typing_speed") {
+            None => TypingSpeedFactor::default(),
+            Some(speed) => speed
+                .as_f64()
+                .filter(|speed| speed.is_finite() && *speed > 0.0)
+                .map(|speed| TypingSpeedFactor::from(speed as f32))
+                .ok_or("Actor 'typing_speed' should be a positive number")?,
+        };
+
+        let props = map
+            .iter()
+            .filter(|(key, _)| !Self::FIELDS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
 
         Ok(Actor {
             system: false,
-            name: ActorName::from(name),
+            role: None,
+            name,
+            portrait: string("portrait")?,
+            emotions,
+            color: string("color")?,
+            typing_speed,
+            voice: string("voice")?,
+            props,
         })
     }
 }
 
 impl Actor {
+    const FIELDS: [&str; 7] = [
+        "system",
+        "name",
+        "portrait",
+        "emotions",
+        "color",
+        "typing_speed",
+        "voice",
+    ];
+
     pub fn system() -> Self {
         Self {
             system: true,
+            role: None,
             name: ActorName::system(),
+            portrait: None,
+            emotions: BTreeMap::new(),
+            color: None,
+            typing_speed: TypingSpeedFactor::default(),
+            voice: None,
+            props: serde_json::Map::new(),
         }
     }
 
-    pub fn view_actor(&self, lang: &LanguageTag) -> ViewActor {
-        let name = self
-            .name
-            .get(lang)
-            .expect("actor name for the specified language should exist");
-        ViewActor {
-            system: self.system,
-            name: std::borrow::Cow::Borrowed(name),
+    pub fn role(&self) -> Option<&ActorRole> {
+        self.role.as_ref()
+    }
+
+    /// The portrait for `emotion`, falling back to the default portrait.
+    pub fn portrait(&self, emotion: Option<&str>) -> Option<&str> {
+        emotion
+            .and_then(|emotion| self.emotions.get(emotion))
+            .or(self.portrait.as_ref())
+            .map(String::as_str)
+    }
+
+    pub fn emotions(&self) -> &BTreeMap<String, String> {
+        &self.emotions
+    }
+
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+
+    pub fn typing_speed(&self) -> TypingSpeedFactor {
+        self.typing_speed
+    }
+
+    pub fn voice(&self) -> Option<&str> {
+        self.voice.as_deref()
+    }
+
+    pub fn props(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.props
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let name = match &self.name {
+            _ if self.system => return serde_json::json!({ "system": true }),
+            ActorName::Monolingual(name) => serde_json::Value::from(name.as_str()),
+            ActorName::Multilingual(lang_names) => lang_names
+                .iter()
+                .map(|(lang, name)| (lang.to_string(), serde_json::Value::from(name.as_str())))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        };
+        let mut json = self.props.clone();
+        json.insert("name".to_string(), name);
+        json.extend(self.definition_json());
+        json.into()
+    }
+
+    /// The actor as scripts see it, named in `lang`, `null` standing for unset fields. The
+    /// current `emotion` and `portrait` are left to the script context, as they change.
+    pub fn to_script_json(&self, lang: &LanguageTag, default: &LanguageTag) -> serde_json::Value {
+        let mut json = self.props.clone();
+        let name = self.name.get(lang, default).cloned();
+        json.insert("name".to_string(), name.into());
+        json.insert("system".to_string(), self.system.into());
+        json.insert("role".to_string(), self.role.as_deref().into());
+        json.insert("emotions".to_string(), serde_json::json!(self.emotions));
+        json.insert("color".to_string(), self.color.clone().into());
+        json.insert("typing_speed".to_string(), (*self.typing_speed).into());
+        json.insert("voice".to_string(), self.voice.clone().into());
+        json.into()
+    }
+
+    /// The fields of the definition which are set to other than their defaults.
+    fn definition_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut json = serde_json::Map::new();
+        if let Some(portrait) = &self.portrait {
+            json.insert("portrait".to_string(), portrait.as_str().into());
+        }
+        if !self.emotions.is_empty() {
+            json.insert("emotions".to_string(), serde_json::json!(self.emotions));
+        }
+        if let Some(color) = &self.color {
+            json.insert("color".to_string(), color.as_str().into());
+        }
+        if self.typing_speed != TypingSpeedFactor::default() {
+            json.insert("typing_speed".to_string(), (*self.typing_speed).into());
+        }
+        if let Some(voice) = &self.voice {
+            json.insert("voice".to_string(), voice.as_str().into());
         }
+        json
+    }
+
+    /// The actor as shown in `lang`, or `None` if it has no name in that language, see
+    /// [`ActorName::get`].
+    pub fn view_actor(
+        &self,
+        lang: &LanguageTag,
+        default: &LanguageTag,
+        emotion: Option<&str>,
+    ) -> Option<ViewActor<'_>> {
+        let name = self.name.get(lang, default)?;
+        Some(ViewActor {
+            system: self.system,
+            name: Cow::Borrowed(name),
+            portrait: self.portrait(emotion).map(Cow::Borrowed),
+            color: self.color().map(Cow::Borrowed),
+            typing_speed: self.typing_speed,
+            voice: self.voice().map(Cow::Borrowed),
+            props: Cow::Borrowed(&self.props),
+        })
     }
 }
 
+use super::super::error::RunnerError;
+use dialogue::{ActorRef, ActorRole, TypingSpeedFactor};
 use language_tags::LanguageTag;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(PartialEq, Clone)]
 pub enum ActorName {
@@ -112,10 +370,17 @@ impl std::fmt::Debug for ActorName {
 }
 
 impl ActorName {
-    pub fn get(&self, lang: &LanguageTag) -> Option<&String> {
+    /// The name in `lang`, or else in its primary language, e.g. `en` for `en-US`, or else in
+    /// `default`, the language of the config.
+    pub fn get(&self, lang: &LanguageTag, default: &LanguageTag) -> Option<&String> {
         match self {
             ActorName::Monolingual(name) => Some(name),
-            ActorName::Multilingual(lang_names) => lang_names.get(lang),
+            ActorName::Multilingual(lang_names) => (lang_names.get(lang))
+                .or_else(|| {
+                    let primary = LanguageTag::parse(lang.primary_language()).ok()?;
+                    lang_names.get(&primary)
+                })
+                .or_else(|| lang_names.get(default)),
         }
     }
 }
@@ -148,7 +413,12 @@ impl From<&str> for ActorName {
 #[derive(Debug, PartialEq, Clone)]
 pub struct ViewActor<'a> {
     system: bool,
-    name: std::borrow::Cow<'a, str>,
+    name: Cow<'a, str>,
+    portrait: Option<Cow<'a, str>>,
+    color: Option<Cow<'a, str>>,
+    typing_speed: TypingSpeedFactor,
+    voice: Option<Cow<'a, str>>,
+    props: Cow<'a, serde_json::Map<String, serde_json::Value>>,
 }
 
 impl ViewActor<'_> {
@@ -160,10 +430,160 @@ impl ViewActor<'_> {
         &self.name
     }
 
+    /// The portrait for the current emotion of the actor.
+    pub fn portrait(&self) -> Option<&str> {
+        self.portrait.as_deref()
+    }
+
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+
+    pub fn typing_speed(&self) -> TypingSpeedFactor {
+        self.typing_speed
+    }
+
+    pub fn voice(&self) -> Option<&str> {
+        self.voice.as_deref()
+    }
+
+    pub fn props(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.props
+    }
+
     pub fn into_owned(self) -> ViewActor<'static> {
         ViewActor {
             system: self.system,
-            name: std::borrow::Cow::Owned(self.name.into_owned()),
+            name: Cow::Owned(self.name.into_owned()),
+            portrait: self
+                .portrait
+                .map(|portrait| Cow::Owned(portrait.into_owned())),
+            color: self.color.map(|color| Cow::Owned(color.into_owned())),
+            typing_speed: self.typing_speed,
+            voice: self.voice.map(|voice| Cow::Owned(voice.into_owned())),
+            props: Cow::Owned(self.props.into_owned()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store};
+
+    #[test]
+    fn test_rich_actors() {
+        use crate::{Config, ManualClock, RunnerError};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Arc<Dialogue> = r#"
+actor:
+  num: 2
+nodes:
+  main:
+  - owner: 1
+    message: abcdefghij
+    options:
+      emotion: happy
+  - owner: 1
+    message: ${actors[1].name} ${actors[1].voice} ${actors[1].emotions.happy} ${actors[1].level}
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let actors = serde_json::json!([
+            { "system": true },
+            {
+                "name": { "en": "Alice", "ja": "アリス" },
+                "portrait": "alice.png",
+                "emotions": { "happy": "alice_happy.png" },
+                "color": "#ff8800",
+                "typing_speed": 2.0,
+                "voice": "alice_v1",
+                "level": 3
+            }
+        ]);
+        let dialogue_ctx = DialogueCtx::builder()
+            .actors(actors.clone())
+            .unwrap()
+            .build();
+        assert_eq!(dialogue_ctx.actors().to_json(), actors);
+
+        let clock = Arc::new(ManualClock::with_unix_time(Duration::from_secs(1000)));
+        let engine = Engine::builder().clock(clock.clone()).build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+
+        clock.advance(Duration::from_millis(450));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abcdefghi"));
+        let actor = runner.view().actor().unwrap();
+        assert_eq!(actor.name(), "Alice");
+        assert_eq!(actor.portrait(), Some("alice_happy.png"));
+        assert_eq!(actor.color(), Some("#ff8800"));
+        assert_eq!(*actor.typing_speed(), 2.0);
+        assert_eq!(actor.voice(), Some("alice_v1"));
+        assert_eq!(actor.props()["level"], 3);
+
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("Alice alice_v1 alice_happy.png 3")
+        );
+        assert_eq!(
+            runner.view().actor().unwrap().portrait(),
+            Some("alice_happy.png")
+        );
+
+        let engine = |language: &str| {
+            let config = Config {
+                language: language.parse().unwrap(),
+                ..Default::default()
+            };
+            Engine::builder().config(config).build()
+        };
+        let dialogue_ctx = || {
+            DialogueCtx::builder()
+                .actors(actors.clone())
+                .unwrap()
+                .build()
+        };
+        let store = &Store::new(&engine("fr"), Data::with_ctx(dialogue_ctx()));
+        assert!(matches!(
+            Runner::instantiate(store, dialogue.clone()),
+            Err(RunnerError::MissingActorName { actor: 1, ref language, .. })
+                if language.as_str() == "fr"
+        ));
+
+        let store = &Store::new(&engine("en-US"), Data::with_ctx(dialogue_ctx()));
+        let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().actor().unwrap().name(),
+            "Alice",
+            "a regional language should fall back to its primary language"
+        );
+        runner.eval_for_assert(r#"assert_eq(actors[1].name, "Alice", "scripts too");"#);
+
+        let store = &Store::new(&engine("ja"), Data::with_ctx(dialogue_ctx()));
+        let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        assert!(
+            runner
+                .dispatch(Action::SetLanguage("de-CH".parse().unwrap()))
+                .unwrap()
+        );
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().actor().unwrap().name(),
+            "アリス",
+            "another language should fall back to the one of the config"
+        );
+
+        let invalid = serde_json::json!([{ "name": "Bob", "typing_speed": 0 }]);
+        assert!(DialogueCtx::builder().actors(invalid).is_err());
+    }
+}
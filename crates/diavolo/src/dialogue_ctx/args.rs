@@ -1,10 +1,12 @@
+use super::super::script_value::ScriptValue;
+
 use boa_engine::{
-    Context, JsResult, JsValue, NativeFunction, js_string,
+    Context, JsObject, JsResult, JsValue, NativeFunction, js_string,
+    object::IntegrityLevel,
     property::{Attribute, PropertyDescriptor},
 };
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
 
 #[derive(Debug)]
 pub struct Args(serde_json::Value);
@@ -40,14 +42,13 @@ impl Args {
         map.into_iter().for_each(|(key, value)| {
             use dialogue::ArgType;
             let arg_type = dialogue_args.get(&key.as_str().into()).unwrap();
-            let typed: JsValue = match arg_type.type_of() {
-                ArgType::Number => value.as_f64().unwrap().into(),
-                ArgType::Integer => value.as_i64().unwrap().into(),
-                ArgType::Bool => value.as_bool().unwrap().into(),
-                ArgType::String => js_string!(value.as_str().unwrap()).into(),
+            let typed = match arg_type.type_of() {
+                ArgType::Number | ArgType::Integer => ScriptValue::Number(value.as_f64().unwrap()),
+                ArgType::Bool => ScriptValue::Bool(value.as_bool().unwrap()),
+                ArgType::String => ScriptValue::String(value.as_str().unwrap().to_string()),
             };
             let arg = if arg_type.is_mutable() {
-                ArgVariant::Mutable(MutableVar::new(typed))
+                ArgVariant::Mutable(typed)
             } else {
                 ArgVariant::Immutable(typed)
             };
@@ -58,7 +59,7 @@ impl Args {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct DialogueArgs(HashMap<String, ArgVariant>);
 
 impl Deref for DialogueArgs {
@@ -71,66 +72,86 @@ impl Deref for DialogueArgs {
 
 #[derive(Debug)]
 pub enum ArgVariant {
-    Immutable(JsValue),
-    Mutable(MutableVar),
+    Immutable(ScriptValue),
+    Mutable(ScriptValue),
 }
 
 impl DialogueArgs {
-    pub fn register_in_boa_context(&self, context: &mut Context) -> JsResult<()> {
-        self.iter()
-            .map(|(key, value)| match value {
-                ArgVariant::Immutable(val) => context.register_global_property(
-                    js_string!(key.to_owned()),
-                    val.clone(),
-                    Attribute::ENUMERABLE,
-                ),
-                ArgVariant::Mutable(mutable_var) => mutable_var.register_as_property(context, key),
-            })
-            .collect()
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct MutableVar {
-    value: Arc<Mutex<JsValue>>,
-}
-
-impl MutableVar {
-    pub fn new(value: JsValue) -> Self {
-        Self {
-            value: Arc::new(Mutex::new(value)),
+    /// Registers every arg under the `args` namespace, and as a global unless a global of the
+    /// same name, like `lines`, already exists. Mutable args are accessors to the properties of
+    /// the returned object, which holds their values while the context lives.
+    pub(crate) fn register_in_boa_context(&self, context: &mut Context) -> JsResult<JsObject> {
+        let namespace = JsObject::with_object_proto(context.intrinsics());
+        let cells = JsObject::with_null_proto();
+        for (key, value) in self.iter() {
+            let global = key != "args"
+                && !(context.global_object())
+                    .has_own_property(js_string!(key.as_str()), context)?;
+            match value {
+                ArgVariant::Immutable(value) => {
+                    let value = value.to_js(context)?;
+                    if global {
+                        context.register_global_property(
+                            js_string!(key.to_owned()),
+                            value.clone(),
+                            Attribute::ENUMERABLE,
+                        )?;
+                    }
+                    namespace.define_property_or_throw(
+                        js_string!(key.as_str()),
+                        PropertyDescriptor::builder()
+                            .value(value)
+                            .writable(false)
+                            .enumerable(true)
+                            .configurable(false)
+                            .build(),
+                        context,
+                    )?;
+                }
+                ArgVariant::Mutable(value) => {
+                    let value = value.to_js(context)?;
+                    cells.create_data_property_or_throw(
+                        js_string!(key.as_str()),
+                        value,
+                        context,
+                    )?;
+                    if global {
+                        Self::define_cell(&context.global_object(), &cells, key, context)?;
+                    }
+                    Self::define_cell(&namespace, &cells, key, context)?;
+                }
+            }
         }
+        namespace.set_integrity_level(IntegrityLevel::Sealed, context)?;
+        context.register_global_property(
+            js_string!("args"),
+            namespace,
+            Attribute::READONLY | Attribute::ENUMERABLE,
+        )?;
+        Ok(cells)
     }
 
-    pub fn register_as_property(&self, context: &mut Context, key: &str) -> JsResult<()> {
-        let value_for_getter = self.value.clone();
-        let getter = unsafe {
-            NativeFunction::from_closure(
-                move |_this: &JsValue,
-                      _args: &[JsValue],
-                      _context: &mut Context|
-                      -> JsResult<JsValue> {
-                    let value = value_for_getter.lock().unwrap();
-                    Ok(value.clone())
-                },
-            )
-        };
-
-        let value_for_setter = self.value.clone();
-        let setter = unsafe {
-            NativeFunction::from_closure(
-                move |_this: &JsValue,
-                      args: &[JsValue],
-                      _context: &mut Context|
-                      -> JsResult<JsValue> {
-                    if let Some(new_value) = args.get(0) {
-                        let mut value = value_for_setter.lock().unwrap();
-                        *value = new_value.clone();
-                    }
-                    Ok(JsValue::undefined())
-                },
-            )
-        };
+    /// Defines `key` on `object` as an accessor to the same property of `cells`.
+    fn define_cell(
+        object: &JsObject,
+        cells: &JsObject,
+        key: &str,
+        context: &mut Context,
+    ) -> JsResult<()> {
+        let captures = (cells.clone(), js_string!(key));
+        let getter = NativeFunction::from_copy_closure_with_captures(
+            |_this, _args, (cells, key), context| cells.get(key.clone(), context),
+            captures.clone(),
+        );
+        let setter = NativeFunction::from_copy_closure_with_captures(
+            |_this, args, (cells, key), context| {
+                if let Some(value) = args.first() {
+                    cells.set(key.clone(), value.clone(), true, context)?;
+                }
+                Ok(JsValue::undefined())
+            },
+            captures,
+        );
 
         let descriptor = PropertyDescriptor::builder()
             .get(getter.to_js_function(context.realm()))
@@ -138,11 +159,7 @@ impl MutableVar {
             .enumerable(true)
             .configurable(false)
             .build();
-
-        context
-            .global_object()
-            .define_property_or_throw(js_string!(key), descriptor, context)?;
-
+        object.define_property_or_throw(js_string!(key), descriptor, context)?;
         Ok(())
     }
 }
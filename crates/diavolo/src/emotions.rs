@@ -0,0 +1,183 @@
+use super::engine::config::EmotionScope;
+
+use std::collections::BTreeMap;
+
+/// Current emotion of each actor by index, carried from line to line until changed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct Emotions {
+    scope: EmotionScope,
+    /// Emotions of the current node last, with one frame per called node when scoped to nodes.
+    frames: Vec<BTreeMap<u8, String>>,
+}
+
+impl Emotions {
+    pub(crate) fn new(scope: EmotionScope) -> Self {
+        Self {
+            scope,
+            frames: Vec::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, actor: u8) -> Option<&str> {
+        self.frames.last()?.get(&actor).map(String::as_str)
+    }
+
+    /// Sets the emotion of `actor`, or resets it when `None`.
+    pub(crate) fn set(&mut self, actor: u8, emotion: Option<String>) {
+        match (self.frames.last_mut(), emotion) {
+            (Some(frame), Some(emotion)) => {
+                frame.insert(actor, emotion);
+            }
+            (Some(frame), None) => {
+                frame.remove(&actor);
+            }
+            (None, Some(emotion)) => self.frames.push(BTreeMap::from([(actor, emotion)])),
+            (None, None) => (),
+        }
+    }
+
+    pub(crate) fn enter_node(&mut self) {
+        if self.scope == EmotionScope::Node || self.frames.is_empty() {
+            self.frames.push(BTreeMap::new());
+        }
+    }
+
+    pub(crate) fn leave_node(&mut self) {
+        if self.scope == EmotionScope::Node {
+            self.frames.pop();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+/// The emotion of an actor on a view, along with the portrait it picks.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ActorState {
+    index: u8,
+    emotion: Option<String>,
+    portrait: Option<String>,
+}
+
+impl ActorState {
+    pub(crate) fn new(index: u8, emotion: Option<&str>, portrait: Option<&str>) -> Self {
+        Self {
+            index,
+            emotion: emotion.map(str::to_string),
+            portrait: portrait.map(str::to_string),
+        }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn emotion(&self) -> Option<&str> {
+        self.emotion.as_deref()
+    }
+
+    pub fn portrait(&self) -> Option<&str> {
+        self.portrait.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store, View};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_actor_emotions() {
+        use crate::{Config, EmotionScope};
+
+        let dialogue: Arc<Dialogue> = r#"
+actor:
+  num: 2
+nodes:
+  main:
+  - owner: 1
+    message: a
+    options:
+      emotion: happy
+      listeners: [0]
+  - owner: 1
+    message: b
+  - eval: actors[1].emotion = "angry"
+  - owner: 1
+    message: ${actors[1].emotion} ${actors[1].portrait}
+  - call: sub
+  - owner: 1
+    message: c
+  sub:
+  - owner: 1
+    message: d
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let actors = serde_json::json!([
+            { "name": "Bob", "portrait": "bob.png" },
+            {
+                "name": "Alice",
+                "portrait": "alice.png",
+                "emotions": { "happy": "alice_happy.png", "angry": "alice_angry.png" }
+            }
+        ]);
+        let run = |emotion_scope| {
+            let dialogue_ctx = DialogueCtx::builder()
+                .actors(actors.clone())
+                .unwrap()
+                .build();
+            let config = Config {
+                emotion_scope,
+                ..Default::default()
+            };
+            let engine = Engine::builder().config(config).build();
+            let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+            let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+            let mut states = Vec::new();
+            loop {
+                runner.dispatch(Action::Skip).unwrap();
+                runner.update_view().unwrap();
+                let View::Message(message) = runner.view() else {
+                    break;
+                };
+                let speaker = message.speaker_state().unwrap();
+                states.push((
+                    message.visible_str().to_string(),
+                    speaker.emotion().map(str::to_string),
+                    speaker.portrait().map(str::to_string),
+                    message.listener_states().to_vec(),
+                ));
+                runner.dispatch(Action::Advance).unwrap();
+            }
+            states
+        };
+
+        let states = run(EmotionScope::Dialogue);
+        let (_, emotion, portrait, listeners) = &states[0];
+        assert_eq!(emotion.as_deref(), Some("happy"));
+        assert_eq!(portrait.as_deref(), Some("alice_happy.png"));
+        assert_eq!(listeners.len(), 1);
+        assert_eq!(listeners[0].index(), 0);
+        assert_eq!(listeners[0].emotion(), None);
+        assert_eq!(listeners[0].portrait(), Some("bob.png"));
+        assert_eq!(states[1].1.as_deref(), Some("happy"));
+        assert_eq!(states[2].0, "angry alice_angry.png");
+        assert_eq!(states[3].1.as_deref(), Some("angry"));
+        assert_eq!(states[4].1.as_deref(), Some("angry"));
+
+        let states = run(EmotionScope::Node);
+        assert_eq!(states[2].1.as_deref(), Some("angry"));
+        assert_eq!((states[3].0.as_str(), states[3].1.as_deref()), ("d", None));
+        assert_eq!(states[3].2.as_deref(), Some("alice.png"));
+        assert_eq!(
+            (states[4].0.as_str(), states[4].1.as_deref()),
+            ("c", Some("angry"))
+        );
+    }
+}
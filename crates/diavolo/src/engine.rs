@@ -1,5 +1,10 @@
 pub(super) mod config;
 
+use super::clock::{Clock, SystemClock};
+use super::host::{
+    Host, HostFunction, HostItem, HostObject, IntoAsyncHostFunction, IntoHostFunction,
+};
+use super::read_history::{MemoryReadHistory, ReadHistory};
 use config::Config;
 use std::sync::Arc;
 
@@ -9,10 +14,12 @@ pub struct Engine {
 }
 
 impl Engine {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
     pub fn with_config(config: Config) -> Self {
-        Self {
-            inner: Arc::new(EngineInner { config }),
-        }
+        Self::builder().config(config).build()
     }
 
     pub fn config(&self) -> &Config {
@@ -22,11 +29,102 @@ impl Engine {
     pub fn config_mut(&mut self) -> Option<&mut Config> {
         Arc::get_mut(&mut self.inner).map(|inner| inner.config_mut())
     }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.inner.clock
+    }
+
+    pub fn read_history(&self) -> &Arc<dyn ReadHistory> {
+        &self.inner.read_history
+    }
+
+    pub(crate) fn host(&self) -> &Host {
+        &self.inner.host
+    }
 }
 
 #[derive(Debug, Default)]
+pub struct EngineBuilder {
+    config: Option<Config>,
+    clock: Option<Arc<dyn Clock>>,
+    read_history: Option<Arc<dyn ReadHistory>>,
+    host: Host,
+}
+
+impl EngineBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config.replace(config);
+        self
+    }
+
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock.replace(clock);
+        self
+    }
+
+    /// Remembers read messages there for skip-read mode, instead of only in memory.
+    pub fn read_history(mut self, read_history: Arc<dyn ReadHistory>) -> Self {
+        self.read_history.replace(read_history);
+        self
+    }
+
+    /// Registers a global function for scripts, see [`HostFunction::new`].
+    pub fn function<Args>(
+        mut self,
+        name: impl Into<String>,
+        f: impl IntoHostFunction<Args>,
+    ) -> Self {
+        self.host
+            .insert(name.into(), HostItem::Function(HostFunction::new(f)));
+        self
+    }
+
+    /// Registers a global function returning a promise, see [`HostFunction::new_async`].
+    pub fn async_function<Args>(
+        mut self,
+        name: impl Into<String>,
+        f: impl IntoAsyncHostFunction<Args>,
+    ) -> Self {
+        self.host
+            .insert(name.into(), HostItem::Function(HostFunction::new_async(f)));
+        self
+    }
+
+    pub fn object(mut self, name: impl Into<String>, object: HostObject) -> Self {
+        self.host.insert(name.into(), HostItem::Object(object));
+        self
+    }
+
+    pub fn build(self) -> Engine {
+        Engine {
+            inner: Arc::new(EngineInner {
+                config: self.config.unwrap_or_default(),
+                clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+                read_history: (self.read_history)
+                    .unwrap_or_else(|| Arc::new(MemoryReadHistory::default())),
+                host: self.host,
+            }),
+        }
+    }
+}
+
+#[derive(Debug)]
 struct EngineInner {
     config: Config,
+    clock: Arc<dyn Clock>,
+    read_history: Arc<dyn ReadHistory>,
+    host: Host,
+}
+
+impl Default for EngineInner {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            clock: Arc::new(SystemClock),
+            read_history: Arc::new(MemoryReadHistory::default()),
+            host: Host::default(),
+        }
+    }
 }
 
 impl EngineInner {
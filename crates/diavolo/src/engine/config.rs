@@ -1,36 +1,290 @@
+mod load;
+
+use crate::error::ConfigError;
 use dialogue::{Text, TypingSpeedFactor};
+
 use language_tags::LanguageTag;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Debug, Clone)]
+/// Settings of an [`Engine`](super::Engine). Config files only set the values they change,
+/// the others keeping their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub language: LanguageTag,
+    /// Language shown along with `language` as a subtitle, e.g. an English gloss of Japanese.
+    pub secondary_language: Option<LanguageTag>,
+    pub secondary_reveal: SecondaryReveal,
     pub typing: TypingConfig,
+    pub auto_play: AutoPlayConfig,
+    /// Seed for `Math.random` in scripts. A fresh seed is picked per runner when unset.
+    pub random_seed: Option<u64>,
+    pub limits: ScriptLimits,
+    pub recovery: RecoveryConfig,
+    /// Whether instantiating a runner checks the identifiers its scripts reference, rejecting
+    /// dialogues with typos before their line runs. Turn it off for dialogues defining names
+    /// only while they run, e.g. through `globalThis`, which are reported as unknown.
+    pub check_scripts: bool,
+    pub emotion_scope: EmotionScope,
+    /// Backlog entries [`Action::Rollback`](crate::Action::Rollback) can return to. Older
+    /// entries stay in the backlog, but their state is no longer kept.
+    pub rollback_limit: usize,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             language: LanguageTag::parse("en").unwrap(),
+            secondary_language: None,
+            secondary_reveal: SecondaryReveal::default(),
             typing: TypingConfig::default(),
+            auto_play: AutoPlayConfig::default(),
+            random_seed: None,
+            limits: ScriptLimits::default(),
+            recovery: RecoveryConfig::default(),
+            check_scripts: true,
+            emotion_scope: EmotionScope::default(),
+            rollback_limit: 100,
         }
     }
 }
 
 impl Config {
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        load::from_toml(source, Self::validate)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ConfigError> {
+        load::from_json(source, Self::validate)
+    }
+
+    /// Loads a `.toml` or `.json` config file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        load::from_file(path.as_ref(), Self::validate)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.typing
+            .validate()
+            .map_err(|error| error.within("typing"))?;
+        self.auto_play
+            .validate()
+            .map_err(|error| error.within("auto_play"))?;
+        self.limits
+            .validate()
+            .map_err(|error| error.within("limits"))
+    }
+
     pub fn effective_typing_speed(&self, text: &Text) -> TypingSpeed {
         self.typing.effective_speed(text, &self.language)
     }
 }
 
-#[derive(Debug, Clone)]
+/// Bounds on the work a dialogue may do, so that untrusted dialogues cannot hang the host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptLimits {
+    /// Loop iterations per script function call.
+    pub loop_iterations: u64,
+    /// Nested script function calls.
+    pub recursion: usize,
+    /// Values on the script VM stack.
+    pub stack_size: usize,
+    /// Time the scripts of a single line may take, as measured by the engine clock. Checked
+    /// every few hundred script instructions, so a slow host function or a built-in running a
+    /// script callback, like `Array.prototype.map`, only gets stopped once it returns.
+    #[serde(
+        serialize_with = "load::serialize_optional_duration",
+        deserialize_with = "load::optional_duration"
+    )]
+    pub time_budget: Option<Duration>,
+    /// Nested `call:` lines.
+    pub call_stack_depth: usize,
+    /// Lines evaluated by a single advance without reaching a view, e.g. a `goto:` cycle.
+    pub lines_per_advance: usize,
+}
+
+impl ScriptLimits {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let counts = [
+            ("loop_iterations", self.loop_iterations as usize),
+            ("recursion", self.recursion),
+            ("stack_size", self.stack_size),
+            ("call_stack_depth", self.call_stack_depth),
+            ("lines_per_advance", self.lines_per_advance),
+        ];
+        if let Some((field, _)) = counts.iter().find(|(_, count)| *count == 0) {
+            return Err(ConfigError::invalid(*field, "must be at least 1"));
+        }
+        match self.time_budget {
+            Some(Duration::ZERO) => Err(ConfigError::invalid(
+                "time_budget",
+                "must be positive, or null for no budget",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            loop_iterations: 100_000,
+            recursion: 512,
+            stack_size: 10 * 1024,
+            time_budget: Some(Duration::from_secs(1)),
+            call_stack_depth: 1024,
+            lines_per_advance: 1000,
+        }
+    }
+}
+
+/// How the runner reacts to a failing script, per kind of line it failed on. Exceeded
+/// [`ScriptLimits`] and runner bugs always abort.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecoveryConfig {
+    /// A `${}` hole in the text of a message, confirm or choice.
+    pub text: Recovery,
+    /// An `if:` condition that threw or did not evaluate to a boolean.
+    pub condition: Recovery,
+    pub eval: Recovery,
+    /// The target of a `goto:`, `call:`, `return:` or `exit:` line.
+    pub control: Recovery,
+    /// Prefix of texts rendered with [`Recovery::RenderRaw`].
+    pub raw_marker: String,
+}
+
+impl Default for RecoveryConfig {
+    fn default() -> Self {
+        Self {
+            text: Recovery::Abort,
+            condition: Recovery::Abort,
+            eval: Recovery::Abort,
+            control: Recovery::Abort,
+            raw_marker: "\u{26a0} ".to_string(),
+        }
+    }
+}
+
+/// A recovery policy. Policies that do not apply to a kind of failure abort instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recovery {
+    /// Stops the dialogue and returns the error to the host.
+    #[default]
+    Abort,
+    /// Moves on to the next line as if the failing one did not exist.
+    SkipLine,
+    /// Texts only: shows the template source prefixed with the raw marker.
+    RenderRaw,
+    /// Conditions only: skips the line as if the condition were false.
+    TreatAsFalse,
+    /// Calls the `on_error:` node of the dialogue, then continues after the failing line.
+    JumpToErrorNode,
+}
+
+/// When the text of a message in the secondary language shows while the primary one types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecondaryReveal {
+    /// All at once, as soon as the message shows.
+    #[default]
+    Instant,
+    /// As much of it as the share of the primary text typed so far.
+    Synced,
+    /// Once the primary text is fully typed.
+    Completed,
+}
+
+/// How long the emotion an actor takes on lasts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmotionScope {
+    /// Until changed, across nodes.
+    #[default]
+    Dialogue,
+    /// Until the node returns. Called nodes start with no emotions, and the emotions of the
+    /// caller are back once they return.
+    Node,
+}
+
+/// How long auto-play leaves a completed message on screen before advancing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoPlayConfig {
+    /// Wait after every message, whatever its length.
+    #[serde(
+        serialize_with = "load::serialize_duration",
+        deserialize_with = "load::duration"
+    )]
+    pub delay: Duration,
+    /// Extra wait as a multiple of the time the text takes to type at its effective speed,
+    /// so that longer and denser texts leave more time to read them.
+    pub reading_factor: f32,
+}
+
+impl Default for AutoPlayConfig {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(1),
+            reading_factor: 1.0,
+        }
+    }
+}
+
+impl AutoPlayConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        match self.reading_factor.is_finite() && self.reading_factor >= 0.0 {
+            true => Ok(()),
+            false => Err(ConfigError::invalid(
+                "reading_factor",
+                format!("must not be negative, not {}", self.reading_factor),
+            )),
+        }
+    }
+
+    /// How long to wait on `text` once it is completed.
+    pub fn delay_for(
+        &self,
+        text: &Text,
+        language: &LanguageTag,
+        typing: &TypingConfig,
+    ) -> Duration {
+        let graphemes = text.graphemes(true).count() as f32;
+        let speed = *typing.effective_speed(text, language);
+        self.delay + Duration::from_secs_f32(graphemes / speed * self.reading_factor)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TypingConfig {
     pub speed_factor: TypingSpeedFactor,
+    /// Characters per second by language. Config files add to or override the default table.
+    #[serde(deserialize_with = "load::language_speeds")]
     pub language_speeds: HashMap<LanguageTag, TypingSpeed>,
+    /// Whether the speed adapts to the scripts a text is written in, see [`ComplexityWeights`].
     pub auto_adjust: bool,
+    #[serde(
+        serialize_with = "load::serialize_duration",
+        deserialize_with = "load::duration"
+    )]
     pub start_delay: Duration,
     pub fast_forward_factor: TypingSpeedFactor,
+    /// Pause after a punctuation mark, by language. A run of marks pauses once after its last
+    /// mark, and the speed factor of the message shortens pauses as it speeds typing up.
+    /// Config files replace the marks of the languages they list.
+    #[serde(
+        serialize_with = "load::serialize_punctuation_pauses",
+        deserialize_with = "load::punctuation_pauses"
+    )]
+    pub punctuation_pauses: HashMap<LanguageTag, HashMap<char, Duration>>,
+    pub complexity_weights: ComplexityWeights,
 }
 
 impl Default for TypingConfig {
@@ -51,17 +305,88 @@ impl Default for TypingConfig {
         .map(|(lang, speed)| (LanguageTag::parse(lang).unwrap(), TypingSpeed::from(*speed)))
         .collect();
 
+        let latin = [
+            (',', 150),
+            (';', 200),
+            (':', 200),
+            ('.', 350),
+            ('!', 350),
+            ('?', 350),
+        ];
+        let cjk = [
+            ('、', 150),
+            ('，', 150),
+            ('。', 350),
+            ('！', 350),
+            ('？', 350),
+            ('…', 350),
+        ];
+        let punctuation_pauses = [
+            ("en", &latin[..]),
+            ("es", &latin),
+            ("fr", &latin),
+            ("de", &latin),
+            ("it", &latin),
+            ("ru", &latin),
+            ("ja", &cjk),
+            ("ko", &latin),
+            ("zh-CN", &cjk),
+            ("zh-TW", &cjk),
+        ]
+        .iter()
+        .map(|(lang, pauses)| {
+            let pauses = (pauses.iter())
+                .map(|&(mark, millis)| (mark, Duration::from_millis(millis)))
+                .collect();
+            (LanguageTag::parse(lang).unwrap(), pauses)
+        })
+        .collect();
+
         Self {
             speed_factor: TypingSpeedFactor::from(1.0),
             fast_forward_factor: TypingSpeedFactor::from(1.0),
             language_speeds,
             auto_adjust: true,
             start_delay: std::time::Duration::from_millis(300),
+            punctuation_pauses,
+            complexity_weights: ComplexityWeights::default(),
         }
     }
 }
 
 impl TypingConfig {
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        load::from_toml(source, Self::validate)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ConfigError> {
+        load::from_json(source, Self::validate)
+    }
+
+    /// Loads a `.toml` or `.json` typing config file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        load::from_file(path.as_ref(), Self::validate)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positive = |field: String, value: f32| match value.is_finite() && value > 0.0 {
+            true => Ok(()),
+            false => Err(ConfigError::invalid(
+                field,
+                format!("must be positive, not {value}"),
+            )),
+        };
+        positive("speed_factor".into(), *self.speed_factor)?;
+        positive("fast_forward_factor".into(), *self.fast_forward_factor)?;
+        for (language, speed) in &self.language_speeds {
+            positive(format!("language_speeds.{language}"), **speed)?;
+        }
+        for (field, weight) in self.complexity_weights.iter() {
+            positive(format!("complexity_weights.{field}"), weight)?;
+        }
+        Ok(())
+    }
+
     pub fn effective_speed(&self, text: &Text, language: &LanguageTag) -> TypingSpeed {
         let base_speed = self.speed_for_language(language);
 
@@ -102,18 +427,58 @@ impl TypingConfig {
             }
         }
 
-        let weighted_complexity = (ascii_count * 1.0
-            + hiragana_count * 0.8
-            + katakana_count * 0.8
-            + kanji_count * 0.6
-            + other_count * 0.9)
+        let weights = &self.complexity_weights;
+        let weighted_complexity = (ascii_count * weights.ascii
+            + hiragana_count * weights.hiragana
+            + katakana_count * weights.katakana
+            + kanji_count * weights.kanji
+            + other_count * weights.other)
             / total_chars;
 
         weighted_complexity
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// How fast each script reads relative to ASCII letters and digits when
+/// [`TypingConfig::auto_adjust`] is set. Lower weights type slower.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComplexityWeights {
+    pub ascii: f32,
+    pub hiragana: f32,
+    pub katakana: f32,
+    pub kanji: f32,
+    /// Any other character, including spaces and punctuation.
+    pub other: f32,
+}
+
+impl ComplexityWeights {
+    fn iter(&self) -> impl Iterator<Item = (&'static str, f32)> {
+        [
+            ("ascii", self.ascii),
+            ("hiragana", self.hiragana),
+            ("katakana", self.katakana),
+            ("kanji", self.kanji),
+            ("other", self.other),
+        ]
+        .into_iter()
+    }
+}
+
+impl Default for ComplexityWeights {
+    fn default() -> Self {
+        Self {
+            ascii: 1.0,
+            hiragana: 0.8,
+            katakana: 0.8,
+            kanji: 0.6,
+            other: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct TypingSpeed(f32);
 
 impl std::ops::Deref for TypingSpeed {
@@ -135,3 +500,96 @@ impl From<f32> for TypingSpeed {
         TypingSpeed(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config() {
+        let source = r#"
+language = "ja"
+emotion_scope = "node"
+
+[typing]
+start_delay = "150ms"
+language_speeds = { ja = 12.0 }
+punctuation_pauses = { ja = { "。" = "0.5s" } }
+complexity_weights = { kanji = 0.5 }
+
+[auto_play]
+delay = "2s"
+
+[limits]
+time_budget = 2000
+
+[recovery]
+text = "render_raw"
+"#;
+        let config = Config::from_toml(source).unwrap();
+        let tag = |tag| LanguageTag::parse(tag).unwrap();
+        assert_eq!(config.language, tag("ja"));
+        assert_eq!(config.emotion_scope, EmotionScope::Node);
+        assert_eq!(config.typing.start_delay, Duration::from_millis(150));
+        assert_eq!(config.typing.language_speeds[&tag("ja")], 12.0.into());
+        assert_eq!(config.typing.language_speeds[&tag("en")], 30.0.into());
+        let ja_pauses = &config.typing.punctuation_pauses[&tag("ja")];
+        assert_eq!(ja_pauses.len(), 1);
+        assert_eq!(ja_pauses[&'。'], Duration::from_millis(500));
+        assert!(config.typing.punctuation_pauses.contains_key(&tag("en")));
+        assert_eq!(config.typing.complexity_weights.kanji, 0.5);
+        assert_eq!(config.typing.complexity_weights.hiragana, 0.8);
+        assert_eq!(config.auto_play.delay, Duration::from_secs(2));
+        assert_eq!(config.auto_play.reading_factor, 1.0);
+        assert_eq!(config.limits.time_budget, Some(Duration::from_secs(2)));
+        assert_eq!(config.limits.recursion, ScriptLimits::default().recursion);
+        assert_eq!(config.recovery.text, Recovery::RenderRaw);
+        assert!(config.check_scripts);
+
+        let written = serde_json::to_value(&config).unwrap();
+        let read = Config::from_json(&written.to_string()).unwrap();
+        assert_eq!(serde_json::to_value(&read).unwrap(), written);
+
+        let config = Config::from_json(r#"{ "limits": { "time_budget": null } }"#).unwrap();
+        assert_eq!(config.limits.time_budget, None);
+        let typing = TypingConfig::from_json(r#"{ "fast_forward_factor": 4 }"#).unwrap();
+        assert_eq!(*typing.fast_forward_factor, 4.0);
+
+        let invalid = |source| match Config::from_toml(source) {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            result => panic!("expected an invalid value, got {result:?}"),
+        };
+        assert_eq!(
+            invalid("typing.language_speeds.ja = 0"),
+            "typing.language_speeds.ja"
+        );
+        assert_eq!(
+            invalid("typing.complexity_weights.other = -1"),
+            "typing.complexity_weights.other"
+        );
+        assert_eq!(invalid("limits.recursion = 0"), "limits.recursion");
+        assert_eq!(
+            invalid("auto_play.reading_factor = -0.5"),
+            "auto_play.reading_factor"
+        );
+        for source in [
+            "typo = 1",
+            "typing.start_delay = \"soon\"",
+            "language = \"???\"",
+        ] {
+            let error = Config::from_toml(source).unwrap_err();
+            assert!(
+                matches!(error, ConfigError::Deserialize(_)),
+                "{source}: {error}"
+            );
+        }
+        assert!(matches!(
+            Config::from_toml("language ="),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            Config::from_file("config.yaml"),
+            Err(ConfigError::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,187 @@
+use super::{TypingConfig, TypingSpeed};
+use crate::error::ConfigError;
+
+use language_tags::LanguageTag;
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+pub(super) fn from_toml<T: DeserializeOwned>(
+    source: &str,
+    validate: impl FnOnce(&T) -> Result<(), ConfigError>,
+) -> Result<T, ConfigError> {
+    let document: toml_edit::Document<&str> = toml_edit::Document::parse(source)?;
+    let value = table_to_json(document.as_table());
+    let config = serde_json::from_value(value)?;
+    validate(&config)?;
+    Ok(config)
+}
+
+pub(super) fn from_json<T: DeserializeOwned>(
+    source: &str,
+    validate: impl FnOnce(&T) -> Result<(), ConfigError>,
+) -> Result<T, ConfigError> {
+    let config = serde_json::from_str(source)?;
+    validate(&config)?;
+    Ok(config)
+}
+
+pub(super) fn from_file<T: DeserializeOwned>(
+    path: &Path,
+    validate: impl FnOnce(&T) -> Result<(), ConfigError>,
+) -> Result<T, ConfigError> {
+    let read = || {
+        std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    };
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => from_toml(&read()?, validate),
+        Some("json") => from_json(&read()?, validate),
+        _ => Err(ConfigError::Format(path.to_path_buf())),
+    }
+}
+
+/// Converts TOML to JSON so that both formats go through the same deserialization.
+fn table_to_json(table: &toml_edit::Table) -> serde_json::Value {
+    (table.iter())
+        .map(|(key, item)| (key.to_string(), item_to_json(item)))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn item_to_json(item: &toml_edit::Item) -> serde_json::Value {
+    use toml_edit::Item;
+    match item {
+        Item::None => serde_json::Value::Null,
+        Item::Value(value) => value_to_json(value),
+        Item::Table(table) => table_to_json(table),
+        Item::ArrayOfTables(tables) => tables.iter().map(table_to_json).collect(),
+    }
+}
+
+fn value_to_json(value: &toml_edit::Value) -> serde_json::Value {
+    use toml_edit::Value;
+    match value {
+        Value::String(string) => string.value().as_str().into(),
+        Value::Integer(integer) => (*integer.value()).into(),
+        Value::Float(float) => (*float.value()).into(),
+        Value::Boolean(boolean) => (*boolean.value()).into(),
+        Value::Datetime(datetime) => datetime.value().to_string().into(),
+        Value::Array(array) => array.iter().map(value_to_json).collect(),
+        Value::InlineTable(table) => (table.iter())
+            .map(|(key, value)| (key.to_string(), value_to_json(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+/// A duration written as milliseconds, or as a string with an `ms` or `s` unit like `"1.5s"`.
+struct ConfigDuration(Duration);
+
+impl<'de> Deserialize<'de> for ConfigDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Millis(u64),
+            Text(String),
+        }
+
+        let parse = |text: &str| {
+            let (number, unit) = match text.trim().strip_suffix("ms") {
+                Some(millis) => (millis, 1e-3),
+                None => (text.trim().strip_suffix('s')?, 1.0),
+            };
+            let seconds = number.trim().parse::<f64>().ok()? * unit;
+            Duration::try_from_secs_f64(seconds).ok()
+        };
+        match Raw::deserialize(deserializer)? {
+            Raw::Millis(millis) => Ok(Self(Duration::from_millis(millis))),
+            Raw::Text(text) => parse(&text).map(Self).ok_or_else(|| {
+                D::Error::custom(format!(
+                    "invalid duration {text:?}, expected e.g. \"300ms\""
+                ))
+            }),
+        }
+    }
+}
+
+/// Written back as whole milliseconds where it can be, which every config reads.
+impl Serialize for ConfigDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.subsec_nanos() % 1_000_000 {
+            0 => serializer.serialize_u64(self.0.as_millis() as u64),
+            _ => serializer.serialize_str(&format!("{}ms", self.0.as_nanos() as f64 / 1e6)),
+        }
+    }
+}
+
+pub(super) fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    ConfigDuration::deserialize(deserializer).map(|duration| duration.0)
+}
+
+pub(super) fn optional_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let duration = Option::<ConfigDuration>::deserialize(deserializer)?;
+    Ok(duration.map(|duration| duration.0))
+}
+
+pub(super) fn serialize_duration<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    ConfigDuration(*duration).serialize(serializer)
+}
+
+pub(super) fn serialize_optional_duration<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    duration.map(ConfigDuration).serialize(serializer)
+}
+
+pub(super) fn language_speeds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<LanguageTag, TypingSpeed>, D::Error> {
+    let mut speeds = TypingConfig::default().language_speeds;
+    speeds.extend(HashMap::<LanguageTag, TypingSpeed>::deserialize(
+        deserializer,
+    )?);
+    Ok(speeds)
+}
+
+pub(super) fn punctuation_pauses<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<LanguageTag, HashMap<char, Duration>>, D::Error> {
+    let mut pauses = TypingConfig::default().punctuation_pauses;
+    let overrides =
+        HashMap::<LanguageTag, HashMap<char, ConfigDuration>>::deserialize(deserializer)?;
+    pauses.extend(overrides.into_iter().map(|(language, marks)| {
+        let marks = marks
+            .into_iter()
+            .map(|(mark, pause)| (mark, pause.0))
+            .collect();
+        (language, marks)
+    }));
+    Ok(pauses)
+}
+
+pub(super) fn serialize_punctuation_pauses<S: Serializer>(
+    pauses: &HashMap<LanguageTag, HashMap<char, Duration>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    (pauses.iter())
+        .map(|(language, marks)| {
+            let marks = (marks.iter())
+                .map(|(mark, pause)| (*mark, ConfigDuration(*pause)))
+                .collect::<HashMap<_, _>>();
+            (language, marks)
+        })
+        .collect::<HashMap<_, _>>()
+        .serialize(serializer)
+}
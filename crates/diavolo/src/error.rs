@@ -0,0 +1,330 @@
+use super::check::CheckIssue;
+use dialogue::{Location, NodeKey, TemplateError};
+
+use boa_engine::{JsError, JsValue};
+use language_tags::LanguageTag;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RunnerError {
+    #[error("Script error at {location}: {message}\n  in `{script}`")]
+    Script {
+        location: Location,
+        message: String,
+        script: String,
+    },
+    #[error("Check failed at {location}: {issue}\n  in `{script}`")]
+    Check {
+        location: Location,
+        #[source]
+        issue: CheckIssue,
+        script: String,
+    },
+    #[error("Type error at {location}: expected {expected}, but `{script}` evaluated to {found}")]
+    Type {
+        location: Location,
+        expected: &'static str,
+        found: String,
+        script: String,
+    },
+    #[error("{limit} at {location}")]
+    Limit {
+        location: Location,
+        #[source]
+        limit: LimitExceeded,
+    },
+    #[error("No text in language {language} at {location}")]
+    MissingText {
+        location: Location,
+        language: LanguageTag,
+    },
+    #[error("Actor {actor} has no name in language {language} at {location}")]
+    MissingActorName {
+        location: Location,
+        actor: u8,
+        language: LanguageTag,
+    },
+    #[error("Node {node_key} referenced at {location} does not exist")]
+    NodeNotFound {
+        location: Location,
+        node_key: NodeKey,
+    },
+    #[error("Line {line} referenced at {location} does not exist")]
+    LineNotFound { location: Location, line: String },
+    #[error("Dialogue expects {expected} actors, but DialogueCtx has {found} actors")]
+    ActorCount { expected: u8, found: u8 },
+    #[error("Dialogue role {role} is not bound to an actor in DialogueCtx")]
+    UnboundRole { role: String },
+    #[error("DialogueCtx binds an actor to role {role:?}, which the dialogue does not declare")]
+    UnknownRole { role: String },
+    #[error("Invalid dialogue args: {0}")]
+    Args(String),
+    #[error("Failed to set up the script context: {0}")]
+    Setup(String),
+    #[error("Pending promise at {location} cannot settle as no host call is in flight")]
+    Stalled { location: Location },
+    /// The runner state does not match the dialogue, which points to a bug in the runner.
+    #[error("Unexpected runner state at {location}: {message}")]
+    Internal { location: Location, message: String },
+}
+
+impl RunnerError {
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Script { location, .. }
+            | Self::Check { location, .. }
+            | Self::Type { location, .. }
+            | Self::Limit { location, .. }
+            | Self::MissingText { location, .. }
+            | Self::MissingActorName { location, .. }
+            | Self::NodeNotFound { location, .. }
+            | Self::LineNotFound { location, .. }
+            | Self::Stalled { location }
+            | Self::Internal { location, .. } => Some(location),
+            Self::ActorCount { .. }
+            | Self::UnboundRole { .. }
+            | Self::UnknownRole { .. }
+            | Self::Args(_)
+            | Self::Setup(_) => None,
+        }
+    }
+
+    pub(crate) fn internal(location: &Location, message: impl Into<String>) -> Self {
+        Self::Internal {
+            location: location.clone(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A [`ScriptLimits`](crate::ScriptLimits) limit hit while running a dialogue.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LimitExceeded {
+    /// One of the limits boa enforces itself: loop iterations, recursion or stack size. Boa
+    /// raises the same error kind for all of them, so which one is only told by the message.
+    #[error("Script runtime limit exceeded: {0}")]
+    Runtime(String),
+    #[error("Evaluation took {elapsed:?}, exceeding the time budget of {budget:?}")]
+    TimeBudget { budget: Duration, elapsed: Duration },
+    #[error("Call stack depth limit ({0}) exceeded")]
+    CallStackDepth(usize),
+    #[error("More than {0} lines evaluated without reaching a view")]
+    Lines(usize),
+}
+
+impl LimitExceeded {
+    /// Recovers the limit a script hit from the uncatchable runtime limit error of boa.
+    pub(crate) fn from_js_error(error: &JsError) -> Option<Self> {
+        let native = error
+            .as_native()
+            .filter(|native| native.is_runtime_limit())?;
+        Some(Self::Runtime(native.message().to_string()))
+    }
+
+    pub(crate) fn at(self, location: &Location) -> RunnerError {
+        RunnerError::Limit {
+            location: location.clone(),
+            limit: self,
+        }
+    }
+}
+
+/// A config file which could not be read, parsed or validated.
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Config file {0:?} is neither .toml nor .json")]
+    Format(PathBuf),
+    #[error("Invalid TOML: {0}")]
+    Toml(#[from] toml_edit::TomlError),
+    #[error("Invalid config: {0}")]
+    Deserialize(#[from] serde_json::Error),
+    #[error("Invalid config value for `{field}`: {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+impl ConfigError {
+    pub(crate) fn invalid(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::Invalid {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Qualifies the field of an invalid value with the `section` of the config holding it.
+    pub(crate) fn within(self, section: &str) -> Self {
+        match self {
+            Self::Invalid { field, reason } => Self::Invalid {
+                field: format!("{section}.{field}"),
+                reason,
+            },
+            error => error,
+        }
+    }
+}
+
+/// A failed script evaluation, not yet tied to the line it happened on.
+#[derive(Debug)]
+pub(crate) struct EvalError {
+    script: String,
+    kind: EvalErrorKind,
+}
+
+#[derive(Debug)]
+enum EvalErrorKind {
+    Js(JsError),
+    Limit(LimitExceeded),
+    Type {
+        expected: &'static str,
+        found: String,
+    },
+    Template(TemplateError),
+}
+
+impl EvalError {
+    pub(crate) fn js(script: impl Into<String>) -> impl FnOnce(JsError) -> Self {
+        let script = script.into();
+        move |error| Self {
+            script,
+            kind: EvalErrorKind::Js(error),
+        }
+    }
+
+    pub(crate) fn limit(script: impl Into<String>, limit: LimitExceeded) -> Self {
+        Self {
+            script: script.into(),
+            kind: EvalErrorKind::Limit(limit),
+        }
+    }
+
+    /// Attributes the error to `script`, e.g. the whole template a failing hole is part of.
+    pub(crate) fn in_script(self, script: impl Into<String>) -> Self {
+        Self {
+            script: script.into(),
+            ..self
+        }
+    }
+
+    pub(crate) fn template(script: impl Into<String>) -> impl FnOnce(TemplateError) -> Self {
+        let script = script.into();
+        move |error| Self {
+            script,
+            kind: EvalErrorKind::Template(error),
+        }
+    }
+
+    pub(crate) fn type_mismatch(
+        script: impl Into<String>,
+        expected: &'static str,
+        found: &JsValue,
+    ) -> Self {
+        Self {
+            script: script.into(),
+            kind: EvalErrorKind::Type {
+                expected,
+                found: found.display().to_string(),
+            },
+        }
+    }
+
+    pub(crate) fn is_runtime_limit(&self) -> bool {
+        match &self.kind {
+            EvalErrorKind::Js(error) => error
+                .as_native()
+                .is_some_and(|native| native.is_runtime_limit()),
+            EvalErrorKind::Limit(_) => true,
+            EvalErrorKind::Type { .. } | EvalErrorKind::Template(_) => false,
+        }
+    }
+
+    pub(crate) fn at(self, location: &Location) -> RunnerError {
+        let location = location.clone();
+        match self.kind {
+            EvalErrorKind::Js(error) => match LimitExceeded::from_js_error(&error) {
+                Some(limit) => RunnerError::Limit { location, limit },
+                None => RunnerError::Script {
+                    location,
+                    message: error.to_string(),
+                    script: self.script,
+                },
+            },
+            EvalErrorKind::Limit(limit) => RunnerError::Limit { location, limit },
+            EvalErrorKind::Type { expected, found } => RunnerError::Type {
+                location,
+                expected,
+                found,
+                script: self.script,
+            },
+            EvalErrorKind::Template(error) => RunnerError::Script {
+                location,
+                message: error.to_string(),
+                script: self.script,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Config, Data, Dialogue, Engine, Runner, Store};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_runner_errors() {
+        // Scripts are left unchecked for their errors to surface while running.
+        let config = Config {
+            check_scripts: false,
+            ..Default::default()
+        };
+        let engine = Engine::builder().config(config).build();
+        let run = |source: &str| {
+            let dialogue: Dialogue = source.parse().unwrap();
+            let store = &Store::new(&engine, Data::default());
+            Runner::instantiate(store, Arc::new(dialogue))
+                .err()
+                .unwrap()
+        };
+
+        let error = run("nodes:\n  main:\n  - eval: 'null'\n  - if: '1 + 1'\n    message: a");
+        assert!(matches!(
+            error,
+            RunnerError::Type { ref location, expected: "a boolean", ref found, ref script }
+                if location.to_string() == "main:1" && found == "2" && script == "1 + 1"
+        ));
+
+        let error = run("nodes:\n  main:\n  - message: ${notDefined}");
+        assert!(matches!(
+            error,
+            RunnerError::Script { ref message, ref script, .. }
+                if message.contains("notDefined") && script == "${notDefined}"
+        ));
+
+        let error = run("nodes:\n  main:\n  - goto: nowhere");
+        assert!(matches!(error, RunnerError::LineNotFound { ref line, .. } if line == "nowhere"));
+
+        let error = run("nodes:\n  main:\n  - message: { ja: こんにちは }");
+        assert!(matches!(
+            error,
+            RunnerError::MissingText { ref language, .. } if language.as_str() == "en"
+        ));
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - message: a\n  - goto: 5"
+            .parse()
+            .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        assert!(!runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        assert!(matches!(
+            runner.dispatch(Action::Advance),
+            Err(RunnerError::LineNotFound { ref location, .. }) if location.to_string() == "main:1"
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub(crate) type HostFuture = Pin<Box<dyn Future<Output = Result<Value, String>> + Send>>;
+
+#[derive(Clone)]
+pub(crate) enum Callback {
+    Sync(Arc<dyn Fn(Vec<Value>) -> Result<Value, String> + Send + Sync>),
+    Async(Arc<dyn Fn(Vec<Value>) -> Result<HostFuture, String> + Send + Sync>),
+}
+
+/// A Rust function callable from scripts. Arguments and the return value cross the boundary
+/// as JSON; an `Err` is thrown into the script as an `Error` carrying its message.
+///
+/// Async host functions return a promise to the script, settled once the future completes.
+/// Their futures must be `Send`, as the runner owning them may move between threads. They
+/// cannot be called from `${}` text templates, which render synchronously.
+#[derive(Clone)]
+pub struct HostFunction {
+    arity: usize,
+    callback: Callback,
+}
+
+impl std::fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostFunction")
+            .field("arity", &self.arity)
+            .field("async", &matches!(self.callback, Callback::Async(_)))
+            .finish_non_exhaustive()
+    }
+}
+
+impl HostFunction {
+    /// Wraps a closure taking typed arguments, e.g. `|item: String| -> Result<bool, String>`.
+    /// Missing arguments are passed as `null`, so trailing `Option` parameters are optional.
+    pub fn new<Args, F: IntoHostFunction<Args>>(f: F) -> Self {
+        f.into_host_function()
+    }
+
+    /// Wraps a closure taking typed arguments and returning a future,
+    /// e.g. `|id: String| async move { load_portrait(id).await }`.
+    pub fn new_async<Args, F: IntoAsyncHostFunction<Args>>(f: F) -> Self {
+        f.into_async_host_function()
+    }
+
+    /// Wraps a closure working on the raw JSON arguments.
+    pub fn from_json(
+        arity: usize,
+        f: impl Fn(Vec<Value>) -> Result<Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            arity,
+            callback: Callback::Sync(Arc::new(f)),
+        }
+    }
+
+    /// Wraps a closure working on the raw JSON arguments and returning a future.
+    pub fn from_json_async<Fut>(
+        arity: usize,
+        f: impl Fn(Vec<Value>) -> Result<Fut, String> + Send + Sync + 'static,
+    ) -> Self
+    where
+        Fut: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        Self {
+            arity,
+            callback: Callback::Async(Arc::new(move |args| {
+                f(args).map(|future| Box::pin(future) as HostFuture)
+            })),
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub(crate) fn callback(&self) -> &Callback {
+        &self.callback
+    }
+}
+
+pub trait IntoHostFunction<Args> {
+    fn into_host_function(self) -> HostFunction;
+}
+
+macro_rules! impl_into_host_function {
+    ($arity:literal; $($arg:ident),*) => {
+        impl<F, R, E, $($arg,)*> IntoHostFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Result<R, E> + Send + Sync + 'static,
+            R: Serialize,
+            E: Display,
+            $($arg: DeserializeOwned,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_host_function(self) -> HostFunction {
+                HostFunction::from_json($arity, move |mut args| {
+                    args.resize($arity, Value::Null);
+                    let mut args = args.into_iter();
+                    $(
+                        let $arg: $arg = serde_json::from_value(args.next().unwrap_or_default())
+                            .map_err(|e| format!("invalid argument: {e}"))?;
+                    )*
+                    let value = self($($arg),*).map_err(|e| e.to_string())?;
+                    serde_json::to_value(value).map_err(|e| e.to_string())
+                })
+            }
+        }
+    };
+}
+
+impl_into_host_function!(0;);
+impl_into_host_function!(1; A1);
+impl_into_host_function!(2; A1, A2);
+impl_into_host_function!(3; A1, A2, A3);
+impl_into_host_function!(4; A1, A2, A3, A4);
+
+pub trait IntoAsyncHostFunction<Args> {
+    fn into_async_host_function(self) -> HostFunction;
+}
+
+macro_rules! impl_into_async_host_function {
+    ($arity:literal; $($arg:ident),*) => {
+        impl<F, Fut, R, E, $($arg,)*> IntoAsyncHostFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<R, E>> + Send + 'static,
+            R: Serialize,
+            E: Display,
+            $($arg: DeserializeOwned,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_async_host_function(self) -> HostFunction {
+                HostFunction::from_json_async($arity, move |mut args| {
+                    args.resize($arity, Value::Null);
+                    let mut args = args.into_iter();
+                    $(
+                        let $arg: $arg = serde_json::from_value(args.next().unwrap_or_default())
+                            .map_err(|e| format!("invalid argument: {e}"))?;
+                    )*
+                    let future = self($($arg),*);
+                    Ok(async move {
+                        let value = future.await.map_err(|e| e.to_string())?;
+                        serde_json::to_value(value).map_err(|e| e.to_string())
+                    })
+                })
+            }
+        }
+    };
+}
+
+impl_into_async_host_function!(0;);
+impl_into_async_host_function!(1; A1);
+impl_into_async_host_function!(2; A1, A2);
+impl_into_async_host_function!(3; A1, A2, A3);
+impl_into_async_host_function!(4; A1, A2, A3, A4);
+
+/// A named group of host functions and constant properties, exposed to scripts as one object.
+#[derive(Debug, Clone, Default)]
+pub struct HostObject {
+    pub(crate) functions: IndexMap<String, HostFunction>,
+    pub(crate) properties: IndexMap<String, Value>,
+}
+
+impl HostObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn function<Args>(
+        mut self,
+        name: impl Into<String>,
+        f: impl IntoHostFunction<Args>,
+    ) -> Self {
+        self.functions.insert(name.into(), f.into_host_function());
+        self
+    }
+
+    pub fn async_function<Args>(
+        mut self,
+        name: impl Into<String>,
+        f: impl IntoAsyncHostFunction<Args>,
+    ) -> Self {
+        self.functions
+            .insert(name.into(), f.into_async_host_function());
+        self
+    }
+
+    pub fn property(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum HostItem {
+    Function(HostFunction),
+    Object(HostObject),
+}
+
+/// Everything registered on the engine, defined as globals in every runner's script context.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Host(IndexMap<String, HostItem>);
+
+impl std::ops::Deref for Host {
+    type Target = IndexMap<String, HostItem>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Host {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Data, Dialogue, Engine, Runner, Store, View};
+
+    #[test]
+    fn test_host_functions() {
+        use std::sync::{Arc, Mutex};
+
+        let inventory = Arc::new(Mutex::new(Vec::<String>::new()));
+        let engine = Engine::builder()
+            .function("give_item", {
+                let inventory = inventory.clone();
+                move |item: String| {
+                    if item.is_empty() {
+                        return Err("item name is empty");
+                    }
+                    inventory.lock().unwrap().push(item);
+                    Ok(true)
+                }
+            })
+            .function("has_flag", |flag: String| {
+                Ok::<_, String>(flag == "met_king")
+            })
+            .object(
+                "player",
+                HostObject::new()
+                    .property("level", serde_json::json!(3))
+                    .function("greet", |name: String, suffix: Option<String>| {
+                        Ok::<_, String>(format!("Hi {name}{}", suffix.unwrap_or_default()))
+                    }),
+            )
+            .build();
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: give_item("potion")
+  - if: has_flag("met_queen")
+    message: never
+  - message: ${player.greet("hero")} lv${player.level}
+"#
+        .parse()
+        .unwrap();
+
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("Hi hero lv3"));
+        assert_eq!(*inventory.lock().unwrap(), vec!["potion"]);
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - eval: give_item('')".parse().unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let error = Runner::instantiate(store, Arc::new(dialogue))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("main:0"), "{error}");
+        assert!(error.to_string().contains("item name is empty"), "{error}");
+    }
+
+    #[test]
+    fn test_async_host_functions() {
+        use std::future::Future;
+        use std::sync::{Arc, Mutex};
+        use std::task::{Context, Poll, Waker};
+
+        let portrait = Arc::new(Mutex::new(None::<String>));
+        let engine = Engine::builder()
+            .async_function("load_portrait", {
+                let portrait = portrait.clone();
+                move |_name: String| {
+                    let portrait = portrait.clone();
+                    std::future::poll_fn(move |_| match portrait.lock().unwrap().take() {
+                        Some(portrait) => Poll::Ready(Ok::<_, String>(portrait)),
+                        None => Poll::Pending,
+                    })
+                }
+            })
+            .async_function("is_ready", |ready: bool| async move {
+                ready.then_some(true).ok_or("not ready")
+            })
+            .build();
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: load_portrait("hero").then(p => { portrait = p })
+  - if: is_ready(true)
+    message: ${portrait}
+"#
+        .parse()
+        .unwrap();
+
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        runner.update_view().unwrap();
+        assert!(runner.is_pending());
+        assert_eq!(runner.view(), &View::Pending);
+        assert!(!runner.dispatch(Action::Advance).unwrap());
+
+        portrait.lock().unwrap().replace("smile".to_string());
+        runner.update_view().unwrap();
+        assert!(runner.is_pending());
+
+        let mut cx = Context::from_waker(Waker::noop());
+        {
+            let mut settle = std::pin::pin!(runner.settle());
+            assert!(matches!(settle.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+        }
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("smile"));
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - if: is_ready(false)\n    message: x"
+            .parse()
+            .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        let mut settle = std::pin::pin!(runner.settle());
+        let result = settle.as_mut().poll(&mut cx);
+        assert!(matches!(result, Poll::Ready(Err(e)) if e.to_string().contains("not ready")));
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - message: ${is_ready(true)}"
+            .parse()
+            .unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let error = Runner::instantiate(store, Arc::new(dialogue))
+            .err()
+            .unwrap();
+        assert!(
+            error.to_string().contains("cannot wait for promises"),
+            "{error}"
+        );
+    }
+}
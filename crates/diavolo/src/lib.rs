@@ -1,20 +1,48 @@
+mod backlog;
 mod boa_ctx;
+mod check;
+mod clock;
 mod data;
 mod dialogue_ctx;
+mod emotions;
 mod engine;
+mod error;
+mod host;
 mod line_state;
+mod observer;
+mod read_history;
+mod replay;
 mod runner;
+mod script_value;
+mod stable_hash;
 mod state_machine;
 mod store;
+mod typing;
 mod view;
 mod visiting_states;
 
 pub extern crate dialogue;
 
+pub use backlog::{Backlog, BacklogEntry, BacklogMessage};
+pub use check::CheckIssue;
+pub use clock::{Clock, ManualClock, SystemClock};
 pub use data::Data;
 pub use dialogue::Dialogue;
 pub use dialogue_ctx::DialogueCtx;
-pub use engine::{Engine, config::Config};
+pub use emotions::ActorState;
+pub use engine::{
+    Engine, EngineBuilder,
+    config::{
+        AutoPlayConfig, ComplexityWeights, Config, EmotionScope, Recovery, RecoveryConfig,
+        ScriptLimits, SecondaryReveal, TypingConfig, TypingSpeed,
+    },
+};
+pub use error::{ConfigError, LimitExceeded, RunnerError};
+pub use host::{HostFunction, HostObject, IntoAsyncHostFunction, IntoHostFunction};
+pub use observer::Observer;
+pub use read_history::{FileReadHistory, MemoryReadHistory, ReadHistory, ReadKey};
+pub use replay::{Recorder, Replayer};
 pub use runner::{Action, Runner};
+pub use script_value::ScriptValue;
 pub use store::Store;
 pub use view::{Selectable, View};
@@ -1,6 +1,6 @@
 use super::LineState;
+use crate::script_value::ScriptValue;
 
-use boa_engine::JsValue;
 use dialogue::NodeKey;
 use std::time::Instant;
 
@@ -18,18 +18,18 @@ impl LineState for CallState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CallState {
     pub visited_at: Instant,
     pub node_key: dialogue::NodeKey,
-    pub returned_value: Option<JsValue>,
+    pub returned_value: Option<ScriptValue>,
     pub returned_at: Option<Instant>,
 }
 
 impl CallState {
-    pub fn new(node_key: NodeKey) -> Self {
+    pub fn new(visited_at: Instant, node_key: NodeKey) -> Self {
         Self {
-            visited_at: Instant::now(),
+            visited_at,
             node_key,
             returned_at: None,
             returned_value: None,
@@ -40,12 +40,12 @@ impl CallState {
         self.returned_at.is_some()
     }
 
-    pub fn ret(&mut self, value: JsValue) {
+    pub fn ret(&mut self, value: ScriptValue, now: Instant) {
         if self.returned_at.is_some() {
             tracing::warn!("Call already returned");
             return;
         }
-        self.returned_at.replace(Instant::now());
+        self.returned_at.replace(now);
         self.returned_value.replace(value);
     }
 }
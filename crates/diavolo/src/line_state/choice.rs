@@ -1,8 +1,9 @@
 use super::LineState;
 use super::message::MessageState;
-use dialogue::{ChoiceKey, ChoiceTexts, Texts};
+use dialogue::{ChoiceKey, ChoiceTexts, MessageOptions, Texts};
 
-use std::time::Instant;
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
 
 impl LineState for ChoiceState {
     fn visited_at(&self) -> Instant {
@@ -18,31 +19,45 @@ impl LineState for ChoiceState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ChoiceState {
     pub visited_at: Instant,
     pub texts: ChoiceTexts,
     pub selected: Option<Selected>,
     pub message_state: Option<MessageState>,
+    /// Time spent paused since the timeout started, which does not count against it.
+    pub total_paused: Duration,
 }
 
 impl ChoiceState {
-    pub fn new(texts: ChoiceTexts) -> Self {
+    pub fn new(visited_at: Instant, texts: ChoiceTexts) -> Self {
         Self {
-            visited_at: Instant::now(),
+            visited_at,
             texts,
             selected: None,
             message_state: None,
+            total_paused: Duration::default(),
         }
     }
 
-    pub fn new_with_message(texts: ChoiceTexts, fast_forward: bool, messages: Texts) -> Self {
-        let visited_at = Instant::now();
+    pub fn new_with_message(
+        visited_at: Instant,
+        texts: ChoiceTexts,
+        fast_forward: bool,
+        messages: Texts,
+        options: MessageOptions,
+    ) -> Self {
         Self {
             visited_at,
             texts,
             selected: None,
-            message_state: Some(MessageState::with_sync(visited_at, fast_forward, messages)),
+            message_state: Some(MessageState::new(
+                visited_at,
+                fast_forward,
+                messages,
+                options,
+            )),
+            total_paused: Duration::default(),
         }
     }
 
@@ -50,12 +65,12 @@ impl ChoiceState {
         self.selected.is_some()
     }
 
-    pub fn select(&mut self, choice_key: &ChoiceKey) {
+    pub fn select(&mut self, choice_key: &ChoiceKey, now: Instant) {
         if self.selected.is_some() {
             tracing::warn!("Choice already selected");
         }
         self.selected = Some(Selected {
-            selected_at: Instant::now(),
+            selected_at: now,
             choice_key: choice_key.clone(),
         });
     }
@@ -70,21 +85,37 @@ impl ChoiceState {
             .ok_or("No message visiting state to commit fast forward".into())
     }
 
-    pub fn complete_message_or_panic(&mut self) {
+    /// Commits a pause to the message, and to the timeout if it had started by then.
+    pub fn commit_pause(&mut self, paused_at: Instant, now: Instant) {
+        let timeout_start = match &mut self.message_state {
+            Some(message_state) => {
+                message_state.commit_pause(paused_at, now);
+                message_state.completed_at
+            }
+            None => Some(self.visited_at),
+        };
+        if let Some(timeout_start) = timeout_start {
+            self.total_paused
+                .add_assign(now.saturating_duration_since(paused_at.max(timeout_start)));
+        }
+    }
+
+    pub fn complete_message(&mut self, now: Instant) -> Result<(), Box<dyn std::error::Error>> {
         self.message_state
             .as_mut()
-            .expect("No message visiting state to complete")
-            .complete();
+            .ok_or("No message visiting state to complete")?
+            .complete(now);
+        Ok(())
     }
 
-    pub fn try_skip_message(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn try_skip_message(&mut self, now: Instant) -> Result<(), Box<dyn std::error::Error>> {
         match &mut self.message_state {
             None => Err("No message visiting state to skip".into()),
             Some(message_state) => {
                 if message_state.is_completed() {
                     return Err("Message already completed, cannot skip".into());
                 }
-                message_state.skip();
+                message_state.skip(now);
                 Ok(())
             }
         }
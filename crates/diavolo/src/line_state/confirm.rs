@@ -1,6 +1,6 @@
 use super::LineState;
 use super::message::MessageState;
-use dialogue::{ConfirmResponse, Texts};
+use dialogue::{ConfirmResponse, MessageOptions, Texts};
 
 use std::time::Instant;
 
@@ -18,7 +18,7 @@ impl LineState for ConfirmState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ConfirmState {
     pub visited_at: Instant,
     pub response_texts: Option<ConfirmResponse>,
@@ -42,20 +42,17 @@ impl std::ops::DerefMut for ConfirmState {
 
 impl ConfirmState {
     pub fn new(
+        visited_at: Instant,
         initial_fast_forward: bool,
         texts: Texts,
+        options: MessageOptions,
         response_texts: Option<ConfirmResponse>,
     ) -> Self {
-        let visited_at = Instant::now();
         Self {
             visited_at,
             response_texts,
             confirmed: None,
-            message_state: MessageState::with_sync(
-                visited_at,
-                initial_fast_forward,
-                texts,
-            ),
+            message_state: MessageState::new(visited_at, initial_fast_forward, texts, options),
         }
     }
 }
@@ -1,18 +1,14 @@
 use super::LineState;
 use std::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EvalState {
     visited_at: Instant,
-    pub value: boa_engine::JsValue,
 }
 
 impl EvalState {
-    pub fn new(value: boa_engine::JsValue) -> Self {
-        Self {
-            visited_at: Instant::now(),
-            value,
-        }
+    pub fn new(visited_at: Instant) -> Self {
+        Self { visited_at }
     }
 }
 
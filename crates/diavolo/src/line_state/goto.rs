@@ -1,16 +1,16 @@
 use super::LineState;
 use std::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GotoState {
     visited_at: Instant,
     pub line_id_or_index: String,
 }
 
 impl GotoState {
-    pub fn new(line_id_or_index: String) -> Self {
+    pub fn new(visited_at: Instant, line_id_or_index: String) -> Self {
         Self {
-            visited_at: Instant::now(),
+            visited_at,
             line_id_or_index,
         }
     }
@@ -1,9 +1,11 @@
 use super::LineState;
+use crate::typing::TypingTimeline;
 
-use dialogue::Texts;
+use dialogue::{MessageOptions, Texts};
 
 use std::{
     ops::AddAssign,
+    sync::OnceLock,
     time::{Duration, Instant},
 };
 
@@ -21,68 +23,90 @@ impl LineState for MessageState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MessageState {
     pub visited_at: Instant,
     pub texts: Texts,
+    /// The options of the message with their templates evaluated.
+    pub options: MessageOptions,
     pub completed_at: Option<Instant>,
     pub skipped_at: Option<Instant>,
     pub total_fast_forward: Duration,
     pub initial_fast_forward: bool,
+    /// Time spent paused while visiting the message, which does not count as typing.
+    pub total_paused: Duration,
+    timeline: OnceLock<TypingTimeline>,
 }
 
-impl Default for MessageState {
-    fn default() -> Self {
+impl MessageState {
+    pub fn new(
+        visited_at: Instant,
+        initial_fast_forward: bool,
+        texts: Texts,
+        options: MessageOptions,
+    ) -> Self {
         Self {
-            visited_at: Instant::now(),
-            texts: Texts::default(),
+            visited_at,
+            texts,
+            options,
             completed_at: Option::default(),
             skipped_at: Option::default(),
             total_fast_forward: Duration::default(),
-            initial_fast_forward: bool::default(),
+            initial_fast_forward,
+            total_paused: Duration::default(),
+            timeline: OnceLock::new(),
         }
     }
-}
 
-impl MessageState {
-    pub fn new(initial_fast_forward: bool, texts: Texts) -> Self {
-        Self {
-            initial_fast_forward,
-            texts,
-            ..Default::default()
-        }
+    /// The typing timeline of the message, computed by `init` the first time it is shown.
+    pub(crate) fn timeline(&self, init: impl FnOnce() -> TypingTimeline) -> &TypingTimeline {
+        self.timeline.get_or_init(init)
     }
 
-    pub fn with_sync(visited_at: Instant, initial_fast_forward: bool, texts: Texts) -> Self {
-        Self {
-            visited_at,
-            initial_fast_forward,
-            texts,
-            ..Default::default()
-        }
+    /// The typing timeline of the message, if it was shown since the last reset.
+    pub(crate) fn built_timeline(&self) -> Option<&TypingTimeline> {
+        self.timeline.get()
+    }
+
+    /// Drops the typing timeline, so that the message computes it again for another text.
+    pub(crate) fn reset_timeline(&mut self) -> Option<TypingTimeline> {
+        self.timeline.take()
+    }
+
+    /// Moves the start of the message so that typing for `elapsed` becomes typing for `target`.
+    pub(crate) fn retime(&mut self, elapsed: Duration, target: Duration) {
+        self.visited_at = match elapsed.checked_sub(target) {
+            Some(ahead) => self.visited_at + ahead,
+            None => (self.visited_at.checked_sub(target - elapsed)).unwrap_or(self.visited_at),
+        };
     }
 
     pub fn commit_fast_forward(&mut self, duration: Duration) {
         self.total_fast_forward.add_assign(duration);
     }
 
+    pub fn commit_pause(&mut self, paused_at: Instant, now: Instant) {
+        self.total_paused
+            .add_assign(now.saturating_duration_since(paused_at.max(self.visited_at)));
+    }
+
     pub fn is_completed(&self) -> bool {
         self.completed_at.is_some()
     }
 
-    pub fn complete(&mut self) {
+    pub fn complete(&mut self, now: Instant) {
         if self.completed_at.is_some() {
             tracing::warn!("Already in complete state");
             return;
         }
-        self.completed_at.replace(Instant::now());
+        self.completed_at.replace(now);
     }
 
     pub fn is_skipped(&self) -> bool {
         self.skipped_at.is_some()
     }
 
-    pub fn skip(&mut self) {
+    pub fn skip(&mut self, now: Instant) {
         if self.skipped_at.is_some() {
             tracing::warn!("Already in skip state");
             return;
@@ -90,7 +114,6 @@ impl MessageState {
             tracing::warn!("Already in complete state, skipping has no effect");
             return;
         }
-        let now = Instant::now();
         self.skipped_at.replace(now);
         self.completed_at.replace(now);
     }
@@ -1,6 +1,6 @@
 use super::LineState;
+use crate::script_value::ScriptValue;
 
-use boa_engine::JsValue;
 use std::time::Instant;
 
 impl LineState for ReturnState {
@@ -17,17 +17,14 @@ impl LineState for ReturnState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ReturnState {
     visited_at: Instant,
-    pub value: JsValue,
+    pub value: ScriptValue,
 }
 
 impl ReturnState {
-    pub fn new(value: JsValue) -> Self {
-        Self {
-            visited_at: Instant::now(),
-            value,
-        }
+    pub fn new(visited_at: Instant, value: ScriptValue) -> Self {
+        Self { visited_at, value }
     }
 }
@@ -0,0 +1,144 @@
+use super::engine::config::Recovery;
+use super::error::RunnerError;
+use super::script_value::ScriptValue;
+
+use dialogue::{ChoiceKey, LineIf, LineType, Location, NodeKey};
+
+/// Receives notifications about what the runner does, including control lines that never
+/// produce a view. Every method has a no-op default, so implementors only pick what they need.
+#[allow(unused_variables)]
+pub trait Observer: Send {
+    /// A line passed its `if:` condition (or had none) and is about to be evaluated.
+    fn on_line_enter(&mut self, location: &Location, line_type: &LineType) {}
+
+    fn on_line_skipped_by_if(&mut self, location: &Location, line_if: &LineIf) {}
+
+    /// The message of a message, confirm or choice line has been fully revealed,
+    /// either by typing to the end or by a skip.
+    fn on_message_completed(&mut self, location: &Location, text: &str) {}
+
+    fn on_choice_selected(&mut self, location: &Location, choice_key: &ChoiceKey) {}
+
+    fn on_confirmed(&mut self, location: &Location, approved: bool) {}
+
+    fn on_call(&mut self, location: &Location, node_key: &NodeKey) {}
+
+    /// The node containing `location` returned, either through a `return:` line or by
+    /// running out of lines.
+    fn on_return(&mut self, location: &Location, value: &ScriptValue) {}
+
+    fn on_exit(&mut self, code: u8) {}
+
+    fn on_script_error(&mut self, location: &Location, error: &dyn std::error::Error) {}
+
+    /// A script error was not returned to the host, but handled by `recovery`.
+    fn on_error_recovered(&mut self, error: &RunnerError, recovery: Recovery) {}
+}
+
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Box<dyn Observer>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Box<dyn Observer>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn notify(&mut self, mut f: impl FnMut(&mut dyn Observer)) {
+        self.0.iter_mut().for_each(|observer| f(observer.as_mut()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, Data, Dialogue, Engine, Runner, Store};
+
+    #[test]
+    fn test_observer() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        impl Observer for Events {
+            fn on_line_enter(&mut self, location: &Location, _line_type: &LineType) {
+                let event = format!("enter {}:{}", location.node_key, *location.line_position);
+                self.0.lock().unwrap().push(event);
+            }
+
+            fn on_line_skipped_by_if(&mut self, location: &Location, _line_if: &LineIf) {
+                let event = format!("skip {}", *location.line_position);
+                self.0.lock().unwrap().push(event);
+            }
+
+            fn on_message_completed(&mut self, _location: &Location, text: &str) {
+                self.0.lock().unwrap().push(format!("completed {text}"));
+            }
+
+            fn on_choice_selected(&mut self, _location: &Location, choice_key: &ChoiceKey) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("selected {}", **choice_key));
+            }
+
+            fn on_call(&mut self, _location: &Location, node_key: &NodeKey) {
+                self.0.lock().unwrap().push(format!("call {node_key}"));
+            }
+
+            fn on_return(&mut self, location: &Location, value: &crate::ScriptValue) {
+                let event = format!("return {} {value}", location.node_key);
+                self.0.lock().unwrap().push(event);
+            }
+
+            fn on_exit(&mut self, code: u8) {
+                self.0.lock().unwrap().push(format!("exit {code}"));
+            }
+        }
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - if: "false"
+    message: never
+  - call: sub
+  - message: hi
+  - choice:
+      foo: Foo
+  - exit: 2
+  sub:
+  - return: 42
+"#
+        .parse()
+        .unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let engine = Engine::default();
+        let store = &Store::new(&engine, Data::default());
+        let observer: Box<dyn Observer> = Box::new(Events(events.clone()));
+        let mut runner =
+            Runner::instantiate_with_observers(store, Arc::new(dialogue), vec![observer]).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner
+            .dispatch(Action::Select(ChoiceKey::new("foo")))
+            .unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "skip 0",
+                "enter main:1",
+                "call sub",
+                "enter sub:0",
+                "return sub 42",
+                "enter main:2",
+                "completed hi",
+                "enter main:3",
+                "selected foo",
+                "enter main:4",
+                "exit 2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use super::stable_hash::StableHasher;
+
+use dialogue::{Dialogue, Line, Location};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Identifies a message line the player has read, across sessions: a stable hash of the
+/// dialogue name, node key, line id (or position without one) and the line itself, so that
+/// editing a line makes it unread again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReadKey(u64);
+
+impl ReadKey {
+    pub(crate) fn new(
+        dialogue: &Dialogue,
+        location: &Location,
+        line: &Line,
+    ) -> Result<Self, serde_json::Error> {
+        use std::hash::Hasher;
+
+        let position = match &line.id {
+            Some(id) => serde_json::json!(id),
+            None => serde_json::json!(*location.line_position),
+        };
+        let key = serde_json::json!({
+            "dialogue": dialogue.name,
+            "node": location.node_key,
+            "line": position,
+            "revision": serde_json::to_value(line)?,
+        });
+
+        let mut hasher = StableHasher::default();
+        hasher.write_json(&key);
+        Ok(Self(hasher.finish()))
+    }
+
+    /// The keys of every line of `dialogue` that `history` holds as read.
+    pub(crate) fn read_in(dialogue: &Dialogue, history: &dyn ReadHistory) -> Vec<Self> {
+        (dialogue.nodes.iter())
+            .flat_map(|(node_key, node)| {
+                node.iter().enumerate().map(|(position, line)| {
+                    let location = Location {
+                        node_key: node_key.clone(),
+                        line_position: position.into(),
+                    };
+                    Self::new(dialogue, &location, line).ok()
+                })
+            })
+            .flatten()
+            .filter(|&key| history.is_read(key))
+            .collect()
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ReadKey {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+/// Where the runner remembers which messages have been read, for skip-read mode. Shared by
+/// every runner of an engine, so implementations use interior mutability.
+pub trait ReadHistory: Debug + Send + Sync {
+    fn is_read(&self, key: ReadKey) -> bool;
+
+    fn mark_read(&self, key: ReadKey) -> std::io::Result<()>;
+}
+
+/// Read history kept for the lifetime of the engine only.
+#[derive(Debug, Default)]
+pub struct MemoryReadHistory(Mutex<HashSet<ReadKey>>);
+
+impl FromIterator<ReadKey> for MemoryReadHistory {
+    fn from_iter<I: IntoIterator<Item = ReadKey>>(keys: I) -> Self {
+        Self(Mutex::new(keys.into_iter().collect()))
+    }
+}
+
+impl ReadHistory for MemoryReadHistory {
+    fn is_read(&self, key: ReadKey) -> bool {
+        self.0.lock().unwrap().contains(&key)
+    }
+
+    fn mark_read(&self, key: ReadKey) -> std::io::Result<()> {
+        self.0.lock().unwrap().insert(key);
+        Ok(())
+    }
+}
+
+/// Read history persisted to a file, one hexadecimal key per line. New keys are appended as
+/// they are read, so the file survives a crash mid-session.
+#[derive(Debug)]
+pub struct FileReadHistory {
+    path: PathBuf,
+    keys: MemoryReadHistory,
+    file: Mutex<File>,
+}
+
+impl FileReadHistory {
+    /// Loads the history at `path`, creating the file if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let mut keys = HashSet::new();
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let key = u64::from_str_radix(line, 16).map_err(|e| {
+                        std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Invalid read key {:?} in {}: {}", line, path.display(), e),
+                        )
+                    })?;
+                    keys.insert(ReadKey(key));
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+
+        let file = File::options().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            keys: MemoryReadHistory(Mutex::new(keys)),
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ReadHistory for FileReadHistory {
+    fn is_read(&self, key: ReadKey) -> bool {
+        self.keys.is_read(key)
+    }
+
+    fn mark_read(&self, key: ReadKey) -> std::io::Result<()> {
+        if !self.keys.0.lock().unwrap().insert(key) {
+            return Ok(());
+        }
+        writeln!(self.file.lock().unwrap(), "{:016x}", key.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_read_history() {
+        let path = std::env::temp_dir().join(format!("diavolo-read-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let history = FileReadHistory::open(&path).unwrap();
+        assert!(!history.is_read(ReadKey(1)));
+        history.mark_read(ReadKey(1)).unwrap();
+        history.mark_read(ReadKey(u64::MAX)).unwrap();
+        history.mark_read(ReadKey(1)).unwrap();
+        assert!(history.is_read(ReadKey(1)));
+        drop(history);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let history = FileReadHistory::open(&path).unwrap();
+        assert!(history.is_read(ReadKey(1)));
+        assert!(history.is_read(ReadKey(u64::MAX)));
+        assert!(!history.is_read(ReadKey(2)));
+
+        std::fs::write(&path, "not a key\n").unwrap();
+        assert!(FileReadHistory::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_skip_read() {
+        use crate::{Action, Data, Dialogue, Engine, Runner, Store};
+        use std::sync::Arc;
+
+        let source = r#"
+name: skip
+nodes:
+  main:
+  - message: First
+  - message: Second
+  - choice:
+      a: A
+  - message: Third
+"#;
+        let history: Arc<dyn ReadHistory> = Arc::new(MemoryReadHistory::default());
+        let start = |source: &str| {
+            let dialogue: Dialogue = source.parse().unwrap();
+            let engine = Engine::builder().read_history(history.clone()).build();
+            let store = Store::new(&engine, Data::default());
+            Runner::instantiate(&store, Arc::new(dialogue)).unwrap()
+        };
+
+        let mut runner = start(source);
+        assert!(runner.dispatch(Action::ToggleSkipRead).unwrap());
+        runner.update_view().unwrap();
+        assert!(!runner.is_skip_read());
+        assert_eq!(runner.view().message(), Some(""));
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+
+        let mut runner = start(source);
+        runner.dispatch(Action::ToggleSkipRead).unwrap();
+        runner.update_view().unwrap();
+        assert!(runner.is_skip_read());
+        assert_eq!(runner.view().message(), Some(""));
+        runner.update_view().unwrap();
+        assert!(runner.view().is_choice());
+        runner.update_view().unwrap();
+        assert!(!runner.is_skip_read());
+
+        let mut runner = start(&source.replace("Second", "Second, edited"));
+        runner.dispatch(Action::ToggleSkipRead).unwrap();
+        runner.update_view().unwrap();
+        runner.update_view().unwrap();
+        assert!(!runner.is_skip_read());
+        assert!(runner.view().is_message());
+        assert!(!runner.view().has_message_finished());
+    }
+}
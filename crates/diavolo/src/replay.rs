@@ -0,0 +1,370 @@
+mod recorder;
+mod replayer;
+
+pub use recorder::Recorder;
+pub use replayer::Replayer;
+
+use super::engine::config::Config;
+use super::runner::Action;
+use super::stable_hash::StableHasher;
+use super::view::View;
+use super::view::message::{MessageLifecycle, MessageView};
+
+use dialogue::Dialogue;
+use language_tags::LanguageTag;
+use serde::{Deserialize, Serialize};
+
+/// One line of a recording. The first line is always a `Header`. Times are counted from the
+/// start of the runner.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Record {
+    Header(Box<Header>),
+    /// A dispatched action, and the error it failed with if it did.
+    Action {
+        at_ns: u64,
+        action: Action,
+        handled: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A poll of the view, with the new view if it changed, and the error it failed with if
+    /// it did.
+    View {
+        at_ns: u64,
+        view: Option<ViewRecord>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Header {
+    dialogue_hash: String,
+    /// The config of the engine, which typing, auto-play and scripts depend on.
+    config: Config,
+    /// Time the runner had been running for when recording started. Whatever happened before
+    /// is not part of the recording.
+    started_ns: u64,
+    /// Modes of the runner when recording started.
+    skip_read: bool,
+    auto_play: bool,
+    language: Option<LanguageTag>,
+    /// The actor definitions the host gave, if any, rather than the parsed actors.
+    actors: Option<serde_json::Value>,
+    system_actor: bool,
+    args: Option<serde_json::Value>,
+    seed: u64,
+    /// Lines of the dialogue already read when recording started, which skip-read passes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    read: Vec<u64>,
+}
+
+/// Comparable summary of a `View`, leaving out the instants it carries.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum ViewRecord {
+    None,
+    Pending,
+    Terminated {
+        code: u8,
+    },
+    Message {
+        message: MessageRecord,
+    },
+    Confirm {
+        message: MessageRecord,
+        confirmed: bool,
+    },
+    Choice {
+        message: Option<MessageRecord>,
+        choices: Vec<String>,
+        selected: Option<String>,
+        expired: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct MessageRecord {
+    actor: String,
+    visible: String,
+    completed: bool,
+}
+
+impl From<&View<'_>> for ViewRecord {
+    fn from(view: &View) -> Self {
+        match view {
+            View::None => ViewRecord::None,
+            View::Pending => ViewRecord::Pending,
+            View::Terminated(code) => ViewRecord::Terminated { code: *code },
+            View::Message(message_view) => ViewRecord::Message {
+                message: message_view.into(),
+            },
+            View::Confirm(confirm_view) => ViewRecord::Confirm {
+                message: (&**confirm_view).into(),
+                confirmed: confirm_view.is_confirmed(),
+            },
+            View::Choice(choice_view) => ViewRecord::Choice {
+                message: choice_view.message_view().as_ref().map(Into::into),
+                choices: choice_view
+                    .choices()
+                    .iter()
+                    .map(|(key, _)| key.to_string())
+                    .collect(),
+                selected: choice_view.selected().map(|key| key.to_string()),
+                expired: choice_view.is_expired(),
+            },
+        }
+    }
+}
+
+impl From<&MessageView<'_>> for MessageRecord {
+    fn from(message_view: &MessageView) -> Self {
+        MessageRecord {
+            actor: message_view.view_actor().name().to_string(),
+            visible: message_view.visible_str().to_string(),
+            completed: matches!(message_view.lifecycle(), MessageLifecycle::Completed(_)),
+        }
+    }
+}
+
+pub(crate) fn dialogue_hash(dialogue: &Dialogue) -> Result<String, serde_json::Error> {
+    use std::hash::Hasher;
+
+    let mut hasher = StableHasher::default();
+    hasher.write_json(&serde_json::to_value(dialogue)?);
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Action, Config, Data, Dialogue, DialogueCtx, Engine, ManualClock, Recorder, Replayer,
+        Runner, RunnerError, Store, TypingConfig,
+    };
+    use dialogue::ChoiceKey;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_record_and_replay() {
+        let dialogue: Arc<Dialogue> = r#"
+args:
+  name: string
+nodes:
+  main:
+  - message: Hello, ${name}
+  - id: q
+    choice:
+      foo: Foo
+      bar: Bar
+  - message: ${lines.q.selected} ${Math.floor(Math.random() * 1000000)}
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let clock = Arc::new(ManualClock::default());
+        let engine = Engine::builder().clock(clock.clone()).build();
+        let dialogue_ctx = DialogueCtx::builder()
+            .args(serde_json::json!({ "name": "diavolo" }))
+            .build();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        let mut recorder = Recorder::new(runner, Vec::new()).unwrap();
+
+        for step in 0..10 {
+            clock.advance(Duration::from_millis(100));
+            recorder.update_view().unwrap();
+            if step == 2 {
+                recorder.dispatch(Action::Skip).unwrap();
+            }
+        }
+        assert!(recorder.dispatch(Action::Advance).unwrap());
+        clock.advance(Duration::from_millis(500));
+        recorder.update_view().unwrap();
+        assert!(
+            recorder
+                .dispatch(Action::Select(ChoiceKey::new("bar")))
+                .unwrap()
+        );
+        recorder.dispatch(Action::Skip).unwrap();
+        recorder.update_view().unwrap();
+        let recorded_message = recorder.view().message().unwrap().to_string();
+        assert!(recorded_message.starts_with("bar "));
+
+        let (_, recording) = recorder.into_inner().unwrap();
+        let replayer = Replayer::from_reader(recording.as_slice()).unwrap();
+        replayer.replay(&dialogue, Engine::builder()).unwrap();
+
+        let tampered = String::from_utf8(recording)
+            .unwrap()
+            .replace(&recorded_message, "bar -1");
+        let replayer = Replayer::from_reader(tampered.as_bytes()).unwrap();
+        assert!(replayer.replay(&dialogue, Engine::builder()).is_err());
+
+        let other: Arc<Dialogue> = "args:\n  name: string\nnodes:\n  main: []"
+            .parse()
+            .map(Arc::new)
+            .unwrap();
+        assert!(replayer.replay(&other, Engine::builder()).is_err());
+    }
+
+    #[test]
+    fn test_record_failed_dispatch() {
+        let dialogue: Arc<Dialogue> = r#"
+nodes:
+  main:
+  - id: q
+    choice:
+      foo: Foo
+      bar: Bar
+  - eval: if (lines.q.selected === "bar") throw new Error("broken")
+  - message: done
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let clock = Arc::new(ManualClock::default());
+        let engine = Engine::builder().clock(clock.clone()).build();
+        let store = &Store::new(&engine, Data::default());
+        let runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        let mut recorder = Recorder::new(runner, Vec::new()).unwrap();
+        recorder.dispatch(Action::Skip).unwrap();
+        clock.advance(Duration::from_millis(100));
+        let error = recorder
+            .dispatch(Action::Select(ChoiceKey::new("bar")))
+            .unwrap_err();
+        let error = error.downcast::<RunnerError>().unwrap();
+        assert!(
+            matches!(*error, RunnerError::Script { ref location, .. } if location.to_string() == "main:1")
+        );
+
+        let (_, recording) = recorder.into_inner().unwrap();
+        let recording = String::from_utf8(recording).unwrap();
+        let failed = recording.lines().last().unwrap();
+        assert!(failed.contains(r#""action":{"Select":"bar"}"#), "{failed}");
+        assert!(failed.contains("Error: broken"), "{failed}");
+        let replayer = Replayer::from_reader(recording.as_bytes()).unwrap();
+        replayer.replay(&dialogue, Engine::builder()).unwrap();
+
+        let tampered = recording.replace("Error: broken", "something else");
+        let replayer = Replayer::from_reader(tampered.as_bytes()).unwrap();
+        let mismatch = replayer
+            .replay(&dialogue, Engine::builder())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            mismatch.starts_with("Error mismatch at record 2"),
+            "{mismatch}"
+        );
+    }
+
+    #[test]
+    fn test_record_wrapped_runner() {
+        let dialogue: Arc<Dialogue> = "nodes:\n  main:\n  - message: abcdefghij"
+            .parse()
+            .map(Arc::new)
+            .unwrap();
+        let clock = Arc::new(ManualClock::default());
+        let config = Config {
+            typing: TypingConfig {
+                start_delay: Duration::ZERO,
+                auto_adjust: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .build();
+        let store = &Store::new(&engine, Data::default());
+        let runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+
+        clock.advance(Duration::from_millis(100));
+        let mut recorder = Recorder::new(runner, Vec::new()).unwrap();
+        recorder.update_view().unwrap();
+        assert_eq!(recorder.view().as_message().unwrap().visible_str(), "abc");
+        assert!(recorder.update_view().unwrap().is_none());
+        clock.advance(Duration::from_millis(100));
+        recorder.update_view().unwrap();
+
+        let (_, recording) = recorder.into_inner().unwrap();
+        let recording = String::from_utf8(recording).unwrap();
+        let unchanged = r#"{"type":"view","at_ns":100000000,"view":null}"#;
+        assert_eq!(recording.lines().nth(2), Some(unchanged));
+        let replayer = Replayer::from_reader(recording.as_bytes()).unwrap();
+        replayer.replay(&dialogue, Engine::builder()).unwrap();
+
+        let changed = recording.replacen(unchanged, recording.lines().nth(1).unwrap(), 1);
+        let replayer = Replayer::from_reader(changed.as_bytes()).unwrap();
+        assert!(replayer.replay(&dialogue, Engine::builder()).is_err());
+    }
+
+    #[test]
+    fn test_replay_skip_read() {
+        use crate::{MemoryReadHistory, ReadHistory};
+
+        let dialogue: Arc<Dialogue> = "nodes:\n  main:\n  - message: a\n  - message: b"
+            .parse()
+            .map(Arc::new)
+            .unwrap();
+        let history: Arc<dyn ReadHistory> = Arc::new(MemoryReadHistory::default());
+        let engine = Engine::builder().read_history(history).build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+
+        let store = &Store::new(&engine, Data::default());
+        let runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        let mut recorder = Recorder::new(runner, Vec::new()).unwrap();
+        recorder.dispatch(Action::ToggleSkipRead).unwrap();
+        recorder.update_view().unwrap();
+        assert!(!recorder.runner().is_skip_read());
+        recorder.dispatch(Action::Skip).unwrap();
+        recorder.update_view().unwrap();
+        assert_eq!(
+            recorder.view().message(),
+            Some("b"),
+            "skipped to the unread line"
+        );
+
+        let (_, recording) = recorder.into_inner().unwrap();
+        let replayer = Replayer::from_reader(recording.as_slice()).unwrap();
+        replayer.replay(&dialogue, Engine::builder()).unwrap();
+    }
+
+    #[test]
+    fn test_replay_host_functions() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let dialogue: Arc<Dialogue> = "nodes:\n  main:\n  - message: ${roll()} ${roll()}"
+            .parse()
+            .map(Arc::new)
+            .unwrap();
+        let engine = || {
+            let rolls = AtomicU32::new(0);
+            Engine::builder().function("roll", move || {
+                Ok::<_, String>(rolls.fetch_add(1, Ordering::Relaxed) + 1)
+            })
+        };
+        let store = &Store::new(&engine().build(), Data::default());
+        let runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        let mut recorder = Recorder::new(runner, Vec::new()).unwrap();
+        recorder.dispatch(Action::Skip).unwrap();
+        recorder.update_view().unwrap();
+        assert_eq!(recorder.view().message(), Some("1 2"));
+
+        let (_, recording) = recorder.into_inner().unwrap();
+        let replayer = Replayer::from_reader(recording.as_slice()).unwrap();
+        replayer.replay(&dialogue, engine()).unwrap();
+        assert!(
+            replayer.replay(&dialogue, Engine::builder()).is_err(),
+            "scripts should not find the host function"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use super::super::read_history::ReadKey;
+use super::super::runner::{Action, Runner};
+use super::super::view::View;
+use super::{Header, Record, ViewRecord, dialogue_hash};
+
+use std::io::Write;
+
+/// Wraps a runner and writes every dispatched action and every poll of the view to `writer`
+/// as JSON lines, so that a [`Replayer`](super::Replayer) can reproduce the session later.
+pub struct Recorder<W: Write> {
+    runner: Runner,
+    writer: W,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(runner: Runner, mut writer: W) -> std::io::Result<Self> {
+        let header = {
+            let data = runner.store().data.lock().unwrap();
+            let dialogue_ctx = &data.dialogue_ctx;
+            let state_machine = &data.state_machine;
+            Header {
+                dialogue_hash: dialogue_hash(runner.dialogue())?,
+                config: runner.store().engine.config().clone(),
+                started_ns: Self::elapsed_ns(&runner),
+                skip_read: state_machine.skip_read,
+                auto_play: state_machine.auto_play,
+                language: data.language.clone(),
+                actors: dialogue_ctx.actors_json().cloned(),
+                system_actor: dialogue_ctx.has_system_actor(),
+                args: dialogue_ctx.args().as_ref().map(|args| (**args).clone()),
+                seed: runner.seed(),
+                read: ReadKey::read_in(runner.dialogue(), &**runner.store().engine.read_history())
+                    .iter()
+                    .map(ReadKey::as_u64)
+                    .collect(),
+            }
+        };
+        Self::write_record(&mut writer, &Record::Header(Box::new(header)))?;
+
+        Ok(Self { runner, writer })
+    }
+
+    pub fn runner(&self) -> &Runner {
+        &self.runner
+    }
+
+    pub fn view(&self) -> &View<'static> {
+        self.runner.view()
+    }
+
+    /// Errors of the runner are recorded along with the action before being returned.
+    pub fn dispatch(&mut self, action: Action) -> Result<bool, Box<dyn std::error::Error>> {
+        let at_ns = Self::elapsed_ns(&self.runner);
+        let result = self.runner.dispatch(action.clone());
+        Self::write_record(
+            &mut self.writer,
+            &Record::Action {
+                at_ns,
+                action,
+                handled: result.as_ref().is_ok_and(|handled| *handled),
+                error: result.as_ref().err().map(ToString::to_string),
+            },
+        )?;
+        Ok(result?)
+    }
+
+    /// Records every call, whether the view changed or not, so that the replay polls at the
+    /// same instants.
+    pub fn update_view(&mut self) -> Result<Option<&View<'static>>, Box<dyn std::error::Error>> {
+        let at_ns = Self::elapsed_ns(&self.runner);
+        let (changed, error) = match self.runner.update_view() {
+            Ok(view) => (view.is_some(), None),
+            Err(e) => (false, Some(e)),
+        };
+        Self::write_record(
+            &mut self.writer,
+            &Record::View {
+                at_ns,
+                view: changed.then(|| ViewRecord::from(self.runner.view())),
+                error: error.as_ref().map(ToString::to_string),
+            },
+        )?;
+        match error {
+            Some(e) => Err(e.into()),
+            None => Ok(changed.then(|| self.runner.view())),
+        }
+    }
+
+    pub fn into_inner(mut self) -> std::io::Result<(Runner, W)> {
+        self.writer.flush()?;
+        Ok((self.runner, self.writer))
+    }
+
+    fn elapsed_ns(runner: &Runner) -> u64 {
+        let clock = runner.store().clock();
+        clock.elapsed(runner.started_at()).as_nanos() as u64
+    }
+
+    fn write_record(writer: &mut W, record: &Record) -> std::io::Result<()> {
+        serde_json::to_writer(&mut *writer, record)?;
+        writer.write_all(b"\n")
+    }
+}
@@ -0,0 +1,167 @@
+use super::super::clock::ManualClock;
+use super::super::data::Data;
+use super::super::dialogue_ctx::DialogueCtx;
+use super::super::engine::EngineBuilder;
+use super::super::error::RunnerError;
+use super::super::read_history::{MemoryReadHistory, ReadKey};
+use super::super::runner::{Action, Runner};
+use super::super::store::Store;
+use super::{Header, Record, ViewRecord, dialogue_hash};
+
+use dialogue::Dialogue;
+
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Feeds a recording made by a [`Recorder`](super::Recorder) into a fresh runner driven by a
+/// [`ManualClock`], checking that the same views come out in the same order.
+#[derive(Debug)]
+pub struct Replayer {
+    header: Header,
+    records: Vec<Record>,
+}
+
+impl Replayer {
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut records = reader
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str::<Record>(&line?)?))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?
+            .into_iter();
+
+        let header = match records.next() {
+            Some(Record::Header(header)) => *header,
+            _ => return Err("Recording should start with a header record".into()),
+        };
+
+        Ok(Self {
+            header,
+            records: records.collect(),
+        })
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.header.seed
+    }
+
+    pub fn dialogue_ctx(&self) -> Result<DialogueCtx, Box<dyn std::error::Error>> {
+        let builder = DialogueCtx::builder().system_actor(self.header.system_actor);
+        let builder = match &self.header.actors {
+            Some(actors) => builder.actors(actors.clone())?,
+            None => builder,
+        };
+        Ok(match &self.header.args {
+            Some(args) => builder.args(args.clone()),
+            None => builder,
+        }
+        .build())
+    }
+
+    /// Replays the recording against `dialogue` with the recorded config, checking that every
+    /// action and poll of the view has the same outcome as when recorded. `engine` provides
+    /// the host functions and objects scripts call, and gets the replay clock and a read
+    /// history starting with the lines read when recording started.
+    pub fn replay(
+        &self,
+        dialogue: &Arc<Dialogue>,
+        engine: EngineBuilder,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let hash = dialogue_hash(dialogue)?;
+        if hash != self.header.dialogue_hash {
+            return Err(format!(
+                "Dialogue hash mismatch: recorded {}, got {}",
+                self.header.dialogue_hash, hash
+            )
+            .into());
+        }
+
+        let mut config = self.header.config.clone();
+        config.random_seed = Some(self.header.seed);
+        let clock = Arc::new(ManualClock::default());
+        let read_history: MemoryReadHistory = self
+            .header
+            .read
+            .iter()
+            .copied()
+            .map(ReadKey::from)
+            .collect();
+        let engine = engine
+            .config(config)
+            .clock(clock.clone())
+            .read_history(Arc::new(read_history))
+            .build();
+        let store = Store::new(&engine, Data::with_ctx(self.dialogue_ctx()?));
+        let mut runner = Runner::instantiate(&store, dialogue.clone())?;
+
+        clock.set_elapsed(Duration::from_nanos(self.header.started_ns));
+        let modes = [
+            (self.header.skip_read, Action::ToggleSkipRead),
+            (self.header.auto_play, Action::ToggleAutoPlay),
+        ];
+        for (_, action) in modes.into_iter().filter(|(on, _)| *on) {
+            runner.dispatch(action)?;
+        }
+        if let Some(language) = &self.header.language {
+            runner.dispatch(Action::SetLanguage(language.clone()))?;
+        }
+
+        for (index, record) in self.records.iter().enumerate() {
+            let position = index + 1;
+            match record {
+                Record::Header(_) => {
+                    return Err(format!("Unexpected header record at {position}").into());
+                }
+                Record::Action {
+                    at_ns,
+                    action,
+                    handled,
+                    error,
+                } => {
+                    clock.set_elapsed(Duration::from_nanos(*at_ns));
+                    let replayed = runner.dispatch(action.clone());
+                    Self::check_error(position, error, replayed.as_ref().err())?;
+                    if replayed.is_ok_and(|replayed| replayed != *handled) {
+                        return Err(format!(
+                            "Action {action:?} at record {position} was handled: {handled}, \
+                             replayed: {}",
+                            !handled
+                        )
+                        .into());
+                    }
+                }
+                Record::View { at_ns, view, error } => {
+                    clock.set_elapsed(Duration::from_nanos(*at_ns));
+                    let replayed = runner.update_view().map(|view| view.map(ViewRecord::from));
+                    Self::check_error(position, error, replayed.as_ref().err())?;
+                    let replayed = replayed.unwrap_or_default();
+                    if replayed != *view {
+                        return Err(format!(
+                            "View mismatch at record {position}: recorded {view:?}, \
+                             replayed {replayed:?}"
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_error(
+        position: usize,
+        recorded: &Option<String>,
+        replayed: Option<&RunnerError>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let replayed = replayed.map(ToString::to_string);
+        match replayed == *recorded {
+            true => Ok(()),
+            false => Err(format!(
+                "Error mismatch at record {position}: recorded {recorded:?}, replayed {replayed:?}"
+            )
+            .into()),
+        }
+    }
+}
@@ -1,33 +1,104 @@
 mod action_handler_impl;
 mod evaluated_line;
+mod script_thread;
 
-use super::boa_ctx::BoaCtx;
+use super::backlog::{Backlog, BacklogEntry, Checkpoint};
+use super::boa_ctx::{BoaCtx, Evaluated, HostCall};
+use super::check::check;
+use super::data::Data;
+use super::emotions::Emotions;
+use super::engine::Engine;
+use super::engine::config::{Recovery, RecoveryConfig};
+use super::error::{EvalError, LimitExceeded, RunnerError};
 use super::line_state::{ChoiceState, ConfirmState, MessageState};
+use super::observer::{Observer, Observers};
+use super::read_history::ReadKey;
+use super::script_value::ScriptValue;
 use super::store::Store;
 use super::view::View;
-use dialogue::{ChoiceKey, ConfirmResponse, Dialogue, LineType};
+use super::view::message::MessageLifecycle;
+use action_handler_impl::ActionError;
+use dialogue::{ChoiceKey, ConfirmResponse, Dialogue, Line, LineIf, LineType, Location};
 pub(crate) use evaluated_line::EvaluatedLine;
+pub(crate) use script_thread::{ScriptSnapshot, ScriptThread};
 
+use boa_engine::JsError;
+use language_tags::LanguageTag;
+use serde::{Deserialize, Serialize};
 use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Instant;
 
-pub struct Runner<'e, 'd> {
-    store: &'e mut Store<'e>,
-    dialogue: &'d Dialogue,
-    boa_ctx: BoaCtx,
+/// Runs a dialogue. The runner owns everything it needs and may move between threads: its
+/// script context, which cannot, lives on a thread of its own for as long as the runner.
+pub struct Runner {
+    store: Store,
+    dialogue: Arc<Dialogue>,
+    scripts: ScriptThread,
     view: View<'static>,
+    backlog: Backlog,
+    seed: u64,
+    started_at: Instant,
+    observers: Observers,
+    pending: Option<Pending>,
 }
 
-impl<'engine, 'dialogue> Runner<'engine, 'dialogue> {
-    pub fn instantiate(
-        store: &'engine mut Store<'engine>,
-        dialogue: &'dialogue Dialogue,
-    ) -> Result<Runner<'engine, 'dialogue>, Box<dyn std::error::Error>> {
+impl Runner {
+    pub fn new(engine: Engine, dialogue: Arc<Dialogue>, data: Data) -> Result<Runner, RunnerError> {
+        Self::new_with_observers(engine, dialogue, data, Vec::new())
+    }
+
+    /// Like [`Runner::new`], but the observers also see the lines evaluated while reaching
+    /// the first view.
+    pub fn new_with_observers(
+        engine: Engine,
+        dialogue: Arc<Dialogue>,
+        data: Data,
+        observers: Vec<Box<dyn Observer>>,
+    ) -> Result<Runner, RunnerError> {
+        Self::start(Store::new(&engine, data), dialogue, observers)
+    }
+
+    /// Runs `dialogue` on the data of `store`, which stays shared with the runner.
+    pub fn instantiate(store: &Store, dialogue: Arc<Dialogue>) -> Result<Runner, RunnerError> {
+        Self::instantiate_with_observers(store, dialogue, Vec::new())
+    }
+
+    /// Like [`Runner::instantiate`], but the observers also see the lines evaluated while
+    /// reaching the first view.
+    pub fn instantiate_with_observers(
+        store: &Store,
+        dialogue: Arc<Dialogue>,
+        observers: Vec<Box<dyn Observer>>,
+    ) -> Result<Runner, RunnerError> {
+        Self::start(store.clone(), dialogue, observers)
+    }
+
+    fn start(
+        store: Store,
+        dialogue: Arc<Dialogue>,
+        observers: Vec<Box<dyn Observer>>,
+    ) -> Result<Runner, RunnerError> {
+        let seed = store.engine.config().random_seed.unwrap_or_else(|| {
+            use std::hash::{BuildHasher, RandomState};
+            RandomState::new().hash_one(0)
+        });
+        let backlog = Backlog::new(store.engine.config().rollback_limit);
+        let started_at = store.clock().now();
+
         Self {
             store,
             dialogue,
-            boa_ctx: BoaCtx::default(),
+            scripts: ScriptThread::spawn()?,
             view: View::default(),
+            backlog,
+            seed,
+            started_at,
+            observers: Observers::default(),
+            pending: None,
         }
+        .with_observers(observers)
         .init()
     }
 
@@ -35,10 +106,10 @@ impl<'engine, 'dialogue> Runner<'engine, 'dialogue> {
         matches!(
             View::new(
                 &self.store.engine,
-                &mut self.store.data.lock().unwrap(),
+                &self.store.data.lock().unwrap(),
                 &self.dialogue.nodes,
             ),
-            View::Terminated(_)
+            Ok(View::Terminated(_))
         )
     }
 
@@ -46,198 +117,659 @@ impl<'engine, 'dialogue> Runner<'engine, 'dialogue> {
         &self.view
     }
 
-    pub fn update_view(&mut self) -> Option<&View<'static>> {
+    pub fn backlog(&self) -> &Backlog {
+        &self.backlog
+    }
+
+    pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// When the runner started, as told by the engine clock.
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub fn dialogue(&self) -> &Arc<Dialogue> {
+        &self.dialogue
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn is_skip_read(&self) -> bool {
+        self.store.data.lock().unwrap().state_machine.skip_read
+    }
+
+    pub fn is_auto_play(&self) -> bool {
+        self.store.data.lock().unwrap().state_machine.auto_play
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.store.data.lock().unwrap().state_machine.is_paused()
+    }
+
+    /// Runs `source` in the script context of the runner.
+    #[cfg(test)]
+    pub(crate) fn eval_for_assert(&mut self, source: &str) {
+        let source = source.to_string();
+        self.scripts.run(move |ctx| ctx.eval_for_assert(&source));
+    }
+
+    #[cfg(test)]
+    pub(crate) fn parsed_scripts(&self) -> usize {
+        self.scripts.run(|ctx| ctx.parsed_scripts())
+    }
+
+    /// Waits for the host futures an `eval:` or `if:` line is pending on and runs the line
+    /// again, until the runner is no longer pending.
+    pub async fn settle(&mut self) -> Result<(), RunnerError> {
+        while self.pending.is_some() {
+            self.check_stalled()?;
+            std::future::poll_fn(|cx| self.poll_host_calls(cx)).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the view if it changed since the last call. In skip-read mode, first skips
+    /// past the current message if it has been read before, and in auto-play mode, past a
+    /// completed message whose reading delay is over.
+    pub fn update_view(&mut self) -> Result<Option<&View<'static>>, RunnerError> {
+        if self.pending.is_some() {
+            let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+            if let Poll::Ready(Err(e)) = self.poll_host_calls(&mut cx) {
+                return Err(e);
+            }
+        }
+        self.skip_read()?;
+        self.auto_play()?;
+
         let mut data = self.store.data.lock().unwrap();
 
         let updated = self
             .view
-            .update(&self.store.engine, &mut data, &self.dialogue.nodes);
+            .update(&self.store.engine, &mut data, &self.dialogue.nodes)?;
 
         if updated && self.view.has_message_finished() {
             tracing::debug!("Completing message automatically as it has finished");
             tracing::trace!("Current view: {:?}", self.view);
-            data.complete_message_or_panic(&self.view);
+            data.complete_message(&self.view, self.store.clock().now())?;
             self.view
-                .update(&self.store.engine, &mut data, &self.dialogue.nodes);
+                .update(&self.store.engine, &mut data, &self.dialogue.nodes)?;
+
+            let location = data.state_machine.location();
+            if let Some(text) = self.view.message_text() {
+                self.observers
+                    .notify(|o| o.on_message_completed(location, text));
+            }
+            self.mark_read(&data);
         }
 
-        updated.then_some(&self.view)
+        Ok(updated.then_some(&self.view))
     }
 
-    pub fn dispatch(&mut self, action: Action) -> bool {
+    /// Returns `Ok(false)` if the action does not apply to the current view, e.g. advancing
+    /// while a message is still typing.
+    pub fn dispatch(&mut self, action: Action) -> Result<bool, RunnerError> {
+        let input = matches!(
+            action,
+            Action::Advance | Action::Skip | Action::Confirm(_) | Action::Select(_)
+        );
         let result = match action {
+            _ if input && self.is_paused() => Err("Runner is paused, cannot handle input".into()),
             Action::Advance => self.handle_advance(),
             Action::ToggleFastForward => self.handle_toggle_fast_forward(),
+            Action::ToggleSkipRead => self.handle_toggle_skip_read(),
+            Action::ToggleAutoPlay => self.handle_toggle_auto_play(),
+            Action::SetVoicePlaying(playing) => self.handle_set_voice_playing(playing),
+            Action::Pause => self.handle_pause(),
+            Action::Resume => self.handle_resume(),
             Action::Skip => self.handle_skip(),
             Action::Confirm(approved) => self.handle_confirm(approved),
             Action::Select(ref choice_key) => self.handle_select(choice_key),
+            Action::Rollback(n) => self.handle_rollback(n),
+            Action::SetLanguage(ref language) => self.handle_set_language(language),
         };
 
         match result {
-            Ok(_) => true,
-            Err(e) => {
-                tracing::warn!("Error handling action {:?}: {}", action, e);
-                false
+            Ok(_) => Ok(true),
+            Err(ActionError::Rejected(reason)) => {
+                tracing::warn!("Error handling action {:?}: {}", action, reason);
+                Ok(false)
             }
+            Err(ActionError::Runner(e)) => Err(e),
         }
     }
 }
 
-impl Runner<'_, '_> {
-    fn init(mut self) -> Result<Self, Box<dyn std::error::Error>> {
+impl Runner {
+    fn with_observers(mut self, observers: Vec<Box<dyn Observer>>) -> Self {
+        observers
+            .into_iter()
+            .for_each(|observer| self.observers.push(observer));
+        self
+    }
+
+    fn init(mut self) -> Result<Self, RunnerError> {
         let mut data = self.store.data.lock().unwrap();
 
+        data.dialogue_ctx.bind_roles(&self.dialogue.actor.roles)?;
         if self.dialogue.actor_num() != data.dialogue_ctx.actors_count() {
-            return Err(format!(
-                "Dialogue expects {} actors, but DialogueCtx has {} actors",
-                self.dialogue.actor_num(),
-                data.dialogue_ctx.actors_count()
-            )
-            .into());
+            return Err(RunnerError::ActorCount {
+                expected: self.dialogue.actor_num(),
+                found: data.dialogue_ctx.actors_count(),
+            });
         }
 
-        self.boa_ctx.define_properties(self.store.data.clone())?;
+        let args = (data.dialogue_ctx.parsed_args(&self.dialogue.args))
+            .map_err(|e| RunnerError::Args(e.to_string()))?
+            .unwrap_or_default();
+        data.args.replace(args);
+        drop(data);
 
-        if let Some(args) = data.dialogue_ctx.parsed_args(&self.dialogue.args)? {
-            args.register_in_boa_context(&mut self.boa_ctx)?;
-        };
+        let (store, dialogue, seed) = (self.store.clone(), self.dialogue.clone(), self.seed);
+        (self.scripts).run(move |ctx| Self::set_up(ctx, &store, &dialogue, seed))?;
 
-        data.call(&self.dialogue.nodes);
+        let config = self.store.engine.config();
+        let mut data = self.store.data.lock().unwrap();
+        data.emotions = Emotions::new(config.emotion_scope);
+        data.call(&self.dialogue.nodes, config.limits.call_stack_depth)?;
         drop(data);
         self.advance()?;
 
         Ok(self)
     }
 
-    fn advance(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut skipped: Option<()> = None;
+    /// Prepares the script context the lines run in, and parses every script of `dialogue`.
+    fn set_up(
+        ctx: &mut BoaCtx,
+        store: &Store,
+        dialogue: &Dialogue,
+        seed: u64,
+    ) -> Result<(), RunnerError> {
+        let setup_error = |e: JsError| RunnerError::Setup(e.to_string());
+        let config = store.engine.config();
+        ctx.define_properties(store.data.clone(), store.clock().clone())
+            .map_err(setup_error)?;
+        ctx.define_dialogue_ctx(store.data.clone(), &config.language)
+            .map_err(setup_error)?;
+        ctx.define_random(seed).map_err(setup_error)?;
+        ctx.define_host(store.engine.host()).map_err(setup_error)?;
+        ctx.set_limits(&config.limits, store.clock().clone());
+        let render_raw = config.recovery.text == Recovery::RenderRaw;
+        ctx.set_raw_marker(render_raw.then(|| config.recovery.raw_marker.clone()));
+        if let Some(args) = store.data.lock().unwrap().args.as_ref() {
+            ctx.define_args(args).map_err(setup_error)?;
+        }
 
-        loop {
-            let mut data = self.store.data.lock().unwrap();
-            let state_machine = &data.state_machine;
-            if state_machine.call_stack.is_empty() {
-                tracing::debug!("Call stack is empty, dialogue execution finished");
-                break Ok(());
+        ctx.compile(&dialogue.nodes)
+            .map_err(|(location, e)| e.at(&location))?;
+        if config.check_scripts {
+            check(dialogue, ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Skips and advances past the current message in skip-read mode if it has been read
+    /// before, or leaves the mode at anything else.
+    fn skip_read(&mut self) -> Result<(), RunnerError> {
+        if !self.continues_skip_read()? {
+            return Ok(());
+        }
+
+        let result = self.handle_skip().and_then(|_| self.handle_advance());
+        match result {
+            // Left right away at an unread line, so that no later update changes the mode
+            // without changing the view, which a recording would miss.
+            Ok(()) => self.continues_skip_read().map(|_| ()),
+            Err(e) => {
+                self.store.data.lock().unwrap().state_machine.skip_read = false;
+                match e {
+                    ActionError::Rejected(reason) => {
+                        tracing::debug!("Leaving skip-read mode: {}", reason);
+                        Ok(())
+                    }
+                    ActionError::Runner(e) => Err(e),
+                }
             }
+        }
+    }
 
-            if let None = skipped.take() {
-                if let Err(e) = Self::try_commit_fast_forward(&mut data, &self.dialogue.nodes, true)
-                {
-                    tracing::debug!("Failed to commit fast forward: {}", e);
-                };
+    /// Whether skip-read mode can skip the current line, leaving the mode at an unread
+    /// message or a selection.
+    fn continues_skip_read(&mut self) -> Result<bool, RunnerError> {
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &data.state_machine;
+        if !state_machine.skip_read || state_machine.is_paused() || self.pending.is_some() {
+            return Ok(false);
+        }
+        let read = match View::new(&self.store.engine, &data, &self.dialogue.nodes) {
+            Ok(View::Message(_)) => self.is_read(&data),
+            Ok(_) => false,
+            Err(e) => {
+                data.state_machine.skip_read = false;
+                return Err(e);
+            }
+        };
+        if !read {
+            tracing::debug!("Leaving skip-read mode at an unread message or a selection");
+            data.state_machine.skip_read = false;
+        }
+        Ok(read)
+    }
+
+    /// Advances past the current message in auto-play mode once it has been completed for
+    /// its reading delay and its voice is over, or leaves the mode at a confirm or choice.
+    /// Resuming from a pause restarts the delay.
+    fn auto_play(&mut self) -> Result<(), RunnerError> {
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &data.state_machine;
+        if !state_machine.auto_play
+            || state_machine.voice_playing
+            || state_machine.is_paused()
+            || self.pending.is_some()
+        {
+            return Ok(());
+        }
+
+        let config = self.store.engine.config();
+        let waited = match View::new(&self.store.engine, &data, &self.dialogue.nodes)? {
+            View::Message(message) => match message.lifecycle() {
+                MessageLifecycle::Completed(completed_at) => {
+                    let mut delay = (config.auto_play).delay_for(
+                        message.text(),
+                        data.language(config),
+                        &config.typing,
+                    );
+                    if state_machine.is_fast_forward() {
+                        delay = delay.div_f32(*config.typing.fast_forward_factor);
+                    }
+                    let since = [state_machine.voice_ended_at, state_machine.resumed_at]
+                        .into_iter()
+                        .flatten()
+                        .fold(*completed_at, Instant::max);
+                    self.store.clock().elapsed(since) >= delay
+                }
+                _ => false,
+            },
+            View::Confirm(_) | View::Choice(_) | View::Terminated(_) => {
+                tracing::debug!("Leaving auto-play mode at a selection");
+                data.state_machine.auto_play = false;
+                return Ok(());
+            }
+            View::Pending | View::None => false,
+        };
+        if !waited {
+            return Ok(());
+        }
+        drop(data);
+
+        match self.handle_advance() {
+            Ok(()) => Ok(()),
+            Err(ActionError::Rejected(reason)) => {
+                tracing::debug!("Auto-play could not advance: {}", reason);
+                Ok(())
+            }
+            Err(ActionError::Runner(e)) => {
+                self.store.data.lock().unwrap().state_machine.auto_play = false;
+                Err(e)
             }
+        }
+    }
+
+    fn read_key(&self, data: &Data) -> Option<ReadKey> {
+        let state_machine = &data.state_machine;
+        let line = state_machine.current_line(&self.dialogue.nodes)?;
+        ReadKey::new(&self.dialogue, state_machine.location(), line)
+            .inspect_err(|e| tracing::warn!("Failed to identify the line as read: {}", e))
+            .ok()
+    }
+
+    fn is_read(&self, data: &Data) -> bool {
+        (self.read_key(data)).is_some_and(|key| self.store.engine.read_history().is_read(key))
+    }
+
+    /// Remembers the message of the current line as read. A history that fails to persist
+    /// it is not worth stopping the dialogue for.
+    fn mark_read(&self, data: &Data) {
+        let Some(key) = self.read_key(data) else {
+            return;
+        };
+        if let Err(e) = self.store.engine.read_history().mark_read(key) {
+            tracing::warn!("Failed to mark message as read: {}", e);
+        }
+    }
+
+    /// Captures the data and what scripts left, before the line at `location` runs.
+    fn checkpoint(&self, location: &Location) -> Result<Checkpoint, RunnerError> {
+        let script = self.scripts.snapshot(location)?;
+        let data = self.store.data.lock().unwrap();
+        Ok(Checkpoint::capture(&data, script))
+    }
+
+    fn location(&self) -> Location {
+        let data = self.store.data.lock().unwrap();
+        data.state_machine.location().clone()
+    }
+
+    fn check_stalled(&self) -> Result<(), RunnerError> {
+        let waiting = |pending: &Pending| pending.calls.iter().any(HostCall::is_waiting);
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| !waiting(pending))
+        {
+            let data = self.store.data.lock().unwrap();
+            let location = data.state_machine.location().clone();
+            return Err(RunnerError::Stalled { location });
+        }
+        Ok(())
+    }
+
+    /// Polls the futures of the pending line once, and runs the line again once all of them
+    /// completed.
+    fn poll_host_calls(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<(), RunnerError>> {
+        let Some(pending) = &mut self.pending else {
+            return Poll::Ready(Ok(()));
+        };
+        pending.calls.iter_mut().for_each(|call| call.poll(cx));
+        if pending.calls.iter().any(HostCall::is_waiting) {
+            return Poll::Pending;
+        }
+
+        let Some(Pending {
+            calls,
+            entered,
+            checkpoint,
+        }) = self.pending.take()
+        else {
+            unreachable!("the runner is pending");
+        };
+        tracing::debug!("Host calls completed, running the pending line again");
+        self.store.data.lock().unwrap().pending = false;
+        let resumed = Resumed { calls, entered };
+        Poll::Ready(self.advance_from(Some(resumed), checkpoint))
+    }
+
+    fn advance(&mut self) -> Result<(), RunnerError> {
+        self.store.data.lock().unwrap().state_machine.reset_voice();
+        self.advance_from(None, None)
+    }
 
-            if data.state_machine.is_last_line(&self.dialogue.nodes) {
-                tracing::debug!("Cannot advance further, returning to caller");
-                data.ret();
-                continue;
+    /// Runs lines until one produces a view. With `resumed`, the current line runs again
+    /// with the results of its host calls instead of moving on to the next one first, and
+    /// keeps `checkpoint` if it was taken at that line already.
+    fn advance_from(
+        &mut self,
+        mut resumed: Option<Resumed>,
+        mut checkpoint: Option<Checkpoint>,
+    ) -> Result<(), RunnerError> {
+        let mut skipped: Option<()> = None;
+        let mut lines = 0;
+
+        loop {
+            if resumed.is_none() {
+                let mut data = self.store.data.lock().unwrap();
+                let state_machine = &data.state_machine;
+                if state_machine.call_stack.is_empty() {
+                    tracing::debug!("Call stack is empty, dialogue execution finished");
+                    let code = data.exit_code.unwrap_or_default();
+                    self.observers.notify(|o| o.on_exit(code));
+                    break Ok(());
+                }
+
+                if skipped.take().is_none() {
+                    let now = self.store.clock().now();
+                    if let Err(e) =
+                        Self::try_commit_fast_forward(&mut data, &self.dialogue.nodes, true, now)
+                    {
+                        tracing::debug!("Failed to commit fast forward: {}", e);
+                    };
+                }
+
+                if data.state_machine.is_last_line(&self.dialogue.nodes) {
+                    tracing::debug!("Cannot advance further, returning to caller");
+                    let location = data.state_machine.location().clone();
+                    let value = data.ret(self.store.clock().now())?;
+                    self.observers.notify(|o| o.on_return(&location, &value));
+                    continue;
+                }
+                data.state_machine.advance();
             }
-            data.state_machine.advance();
-            drop(data);
 
-            match self.evaluate()? {
+            let flow = match self.evaluate(resumed.take(), &mut lines, &mut checkpoint) {
+                Ok(flow) => flow,
+                Err(failure) => self.recover(failure)?,
+            };
+
+            match flow {
                 ControlFlow::Continue(ContinueReason::Skip) => skipped = Some(()),
                 ControlFlow::Continue(ContinueReason::ControlLine) => (),
-                ControlFlow::Break(_) => break Ok(()),
+                ControlFlow::Break(BreakReason::Pending { entered }) => {
+                    tracing::debug!("Waiting for host calls");
+                    self.store.data.lock().unwrap().pending = true;
+                    self.pending = Some(Pending {
+                        calls: self.scripts.run(|ctx| ctx.take_host_calls()),
+                        entered,
+                        checkpoint,
+                    });
+                    break Ok(());
+                }
+                ControlFlow::Break(BreakReason::View) => {
+                    let entry = {
+                        let data = self.store.data.lock().unwrap();
+                        let view = View::new(&self.store.engine, &data, &self.dialogue.nodes)?;
+                        BacklogEntry::from_view(&view)
+                    };
+                    if let (Some(entry), Some(mut checkpoint)) = (entry, checkpoint) {
+                        checkpoint.record_calls(self.scripts.run(|ctx| ctx.take_host_calls()));
+                        self.backlog.push(entry, checkpoint);
+                    }
+                    break Ok(());
+                }
             }
         }
     }
 
-    fn evaluate(&mut self) -> Result<ControlFlow<(), ContinueReason>, Box<dyn std::error::Error>> {
-        let mut loop_counter = 0;
+    /// Applies the recovery policy configured for `failure`, or returns its error if the
+    /// policy is to abort or does not apply.
+    fn recover(
+        &mut self,
+        failure: Failure,
+    ) -> Result<ControlFlow<BreakReason, ContinueReason>, RunnerError> {
+        let Failure { kind, error } = failure;
+        if let Some(location) = error.location() {
+            self.report_raw_texts(location);
+        }
+        let config = self.store.engine.config();
+        let recovery = kind
+            .map(|kind| kind.policy(&config.recovery))
+            .unwrap_or_default();
+
+        let mut data = self.store.data.lock().unwrap();
+        data.state_machine.skip_read = false;
+        let flow = match (recovery, kind) {
+            (Recovery::SkipLine, _) | (Recovery::TreatAsFalse, Some(FailureKind::Condition)) => {
+                Some(ContinueReason::Skip)
+            }
+            (Recovery::JumpToErrorNode, _) if data.error_node_depth.is_none() => {
+                match &self.dialogue.on_error {
+                    Some(node_key) => {
+                        data.call_node(
+                            node_key.clone(),
+                            &self.dialogue.nodes,
+                            config.limits.call_stack_depth,
+                        )?;
+                        data.error_node_depth = Some(data.state_machine.call_stack.len());
+                        Some(ContinueReason::ControlLine)
+                    }
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        drop(data);
+
+        match flow {
+            Some(reason) => {
+                tracing::warn!("Recovered with {:?}: {}", recovery, error);
+                self.observers
+                    .notify(|o| o.on_error_recovered(&error, recovery));
+                Ok(ControlFlow::Continue(reason))
+            }
+            None => {
+                tracing::warn!("{error}");
+                if let Some(location) = error.location() {
+                    self.observers
+                        .notify(|o| o.on_script_error(location, &error));
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Reports the texts rendered with [`Recovery::RenderRaw`] on the line at `location`.
+    fn report_raw_texts(&mut self, location: &Location) {
+        let at = location.clone();
+        let recovered = self.scripts.run(move |ctx| {
+            let recovered = ctx.take_recovered().into_iter();
+            recovered.map(|error| error.at(&at)).collect::<Vec<_>>()
+        });
+        for error in recovered {
+            tracing::warn!("Recovered with {:?}: {}", Recovery::RenderRaw, error);
+            self.observers
+                .notify(|o| o.on_error_recovered(&error, Recovery::RenderRaw));
+        }
+    }
+
+    /// Evaluates the current line, and the lines a `goto:` jumps to. A checkpoint is taken
+    /// before a message, confirm or choice line runs, unless it runs again and has one.
+    fn evaluate(
+        &mut self,
+        mut resumed: Option<Resumed>,
+        lines: &mut usize,
+        checkpoint: &mut Option<Checkpoint>,
+    ) -> Result<ControlFlow<BreakReason, ContinueReason>, Failure> {
+        let limits = self.store.engine.config().limits.clone();
+        let dialogue = self.dialogue.clone();
 
         loop {
-            loop_counter += 1;
-            if loop_counter > 1000 {
-                return Err("Infinite loop detected in dialogue evaluation".into());
+            let runs_again = resumed.is_some();
+            let Resumed { calls, entered } = resumed.take().unwrap_or_default();
+            let (location, line_if, line_type) = {
+                let data = self.store.data.lock().unwrap();
+                let state_machine = &data.state_machine;
+                let line_if = state_machine.current_line_if(&dialogue.nodes);
+                let line_type = state_machine.current_line_type(&dialogue.nodes);
+                (state_machine.location().clone(), line_if, line_type)
+            };
+
+            *lines += 1;
+            if *lines > limits.lines_per_advance {
+                return Err(LimitExceeded::Lines(limits.lines_per_advance)
+                    .at(&location)
+                    .into());
             }
 
-            let line_if_result = {
-                (self.store.data.lock().unwrap())
-                    .state_machine
-                    .current_line_if(&self.dialogue.nodes)
+            let line_type =
+                line_type.ok_or_else(|| RunnerError::internal(&location, "No line to evaluate"))?;
+            let view_line = matches!(
+                line_type,
+                LineType::Message(_) | LineType::Confirm(_) | LineType::Choice(_)
+            );
+            if view_line && (!runs_again || checkpoint.is_none()) {
+                *checkpoint = Some(self.checkpoint(&location)?);
             }
-            .map(|line_if| self.boa_ctx.eval_if(&line_if))
-            .transpose()?
-            .unwrap_or(true);
+
+            let line_if_result = self.scripts.run({
+                let (dialogue, location) = (dialogue.clone(), location.clone());
+                move |ctx| {
+                    ctx.start_line(&location, calls);
+                    let line_if = line_at(&dialogue, &location).r#if.as_ref();
+                    Self::eval_line_if(ctx, line_if, &location)
+                }
+            })?;
+            let line_if_result = match line_if_result {
+                Evaluated::Ready(result) => result,
+                Evaluated::Pending => {
+                    break Ok(ControlFlow::Break(BreakReason::Pending { entered: false }));
+                }
+            };
 
             if !line_if_result {
                 tracing::debug!("Line if condition evaluated to false, skipping line");
+                if let Some(line_if) = line_if {
+                    self.observers
+                        .notify(|o| o.on_line_skipped_by_if(&location, line_if));
+                }
                 break Ok(ControlFlow::Continue(ContinueReason::Skip));
             }
 
-            let data = self.store.data.lock().unwrap();
-            let state_machine = &data.state_machine;
-            let line_type = state_machine
-                .current_line_type(&self.dialogue.nodes)
-                .expect("Current line type should exist");
-            drop(data);
-
-            let ctx = &mut self.boa_ctx;
-
-            let evaluated_line = {
-                match line_type {
-                    LineType::Message(message) => {
-                        EvaluatedLine::Message(ctx.eval_texts(&message.texts)?)
-                    }
-                    LineType::Confirm(confirm) => {
-                        let texts = ctx.eval_texts(&confirm.message.texts)?;
-                        let response_texts = confirm
-                            .options
-                            .as_ref()
-                            .and_then(|options| options.response.as_ref())
-                            .map(|response_texts| {
-                                Ok::<_, boa_engine::JsError>(ConfirmResponse {
-                                    yes: ctx.eval_texts(&response_texts.yes)?,
-                                    no: ctx.eval_texts(&response_texts.no)?,
-                                })
-                            })
-                            .transpose()?;
-                        EvaluatedLine::Confirm(texts, response_texts)
-                    }
-                    LineType::Choice(choice) => {
-                        let choice_texts = ctx.eval_choice_texts(&choice.texts)?;
-                        let texts = choice
-                            .message()
-                            .map(|message| ctx.eval_texts(&message.texts))
-                            .transpose()?;
-                        EvaluatedLine::Choice(choice_texts, texts)
-                    }
-                    LineType::Eval(eval) => {
-                        tracing::debug!("Evaluating Eval line: {}", eval.source);
-                        EvaluatedLine::Eval(ctx.eval(boa_engine::Source::from_bytes(&eval.source))?)
-                    }
-                    LineType::Goto(goto) => {
-                        let text = ctx.eval_text(&goto.pre_evaluation_value.as_str().into())?;
-                        EvaluatedLine::Goto(text.to_string())
-                    }
-                    LineType::Call(call) => {
-                        EvaluatedLine::Call(ctx.eval_text(&call.pre_evaluation_node_key)?.into())
-                    }
-                    LineType::Return(r#return) => {
-                        EvaluatedLine::Return(ctx.eval_str(&r#return.pre_evaluation_value)?)
-                    }
-                    LineType::Exit(exit) => {
-                        use dialogue::ExitValue;
-                        let code = match &exit.value {
-                            ExitValue::PreEvaluation(source) => ctx
-                                .eval(boa_engine::Source::from_bytes(source))?
-                                .to_uint8(ctx)?,
-                            ExitValue::ExitCode(code) => *code,
-                        };
-                        self.store.data.lock().unwrap().exit(code);
-                        break Ok(ControlFlow::Continue(ContinueReason::ControlLine));
-                    }
+            if !entered {
+                self.observers
+                    .notify(|o| o.on_line_enter(&location, line_type));
+            }
+
+            let evaluated_line = self.scripts.run({
+                let (dialogue, location) = (dialogue.clone(), location.clone());
+                move |ctx| Self::eval_line(ctx, &line_at(&dialogue, &location).r#type, &location)
+            })?;
+            let evaluated_line = match evaluated_line {
+                Evaluated::Ready(EvaluatedLine::Exit(code)) => {
+                    self.store.data.lock().unwrap().exit(code);
+                    break Ok(ControlFlow::Continue(ContinueReason::ControlLine));
+                }
+                Evaluated::Ready(evaluated_line) => evaluated_line,
+                Evaluated::Pending => {
+                    break Ok(ControlFlow::Break(BreakReason::Pending { entered: true }));
                 }
             };
 
+            self.report_raw_texts(&location);
+
+            let now = self.store.clock().now();
             let mut data = self.store.data.lock().unwrap();
-            data.visit_line(evaluated_line);
+            let owner = match line_type {
+                LineType::Message(message) => Some(&message.owner),
+                LineType::Confirm(confirm) => Some(&confirm.message.owner),
+                LineType::Choice(choice) => choice.message().map(|message| &message.owner),
+                _ => None,
+            };
+            let emotion = owner.and_then(|owner| {
+                let emotion = evaluated_line.message_options()?.emotion.clone()?;
+                Some((data.dialogue_ctx.actors().index_of(owner)?, emotion))
+            });
+            data.visit_line(evaluated_line, now)?;
+            if let Some((actor, emotion)) = emotion {
+                data.emotions.set(actor, Some(emotion));
+            }
 
             match &line_type {
-                LineType::Goto(_) => data.goto(&self.dialogue.nodes),
-                LineType::Call(_) => data.call(&self.dialogue.nodes),
-                LineType::Return(_) => data.ret(),
+                LineType::Goto(_) => data
+                    .goto(&dialogue.nodes)
+                    .map_err(|e| Failure::new(FailureKind::Control, e))?,
+                LineType::Call(_) => {
+                    data.call(&dialogue.nodes, limits.call_stack_depth)
+                        .map_err(|e| Failure::new(FailureKind::Control, e))?;
+                    let node_key = data.state_machine.current_node_key();
+                    self.observers.notify(|o| o.on_call(&location, node_key));
+                }
+                LineType::Return(_) => {
+                    let value = data.ret(now)?;
+                    self.observers.notify(|o| o.on_return(&location, &value));
+                }
                 _ => (),
             }
 
@@ -246,22 +778,134 @@ impl Runner<'_, '_> {
                 LineType::Eval(_) | LineType::Call(_) | LineType::Return(_) => {
                     break Ok(ControlFlow::Continue(ContinueReason::ControlLine));
                 }
-                _ => break Ok(ControlFlow::Break(())),
+                _ => break Ok(ControlFlow::Break(BreakReason::View)),
             }
         }
     }
 
+    /// Evaluates the `if:` of the line at `location` on the script thread. Lines without one
+    /// always run.
+    fn eval_line_if(
+        ctx: &mut BoaCtx,
+        line_if: Option<&LineIf>,
+        location: &Location,
+    ) -> Result<Evaluated<bool>, Failure> {
+        let Some(line_if) = line_if else {
+            return Ok(Evaluated::Ready(true));
+        };
+        let script_error = |e: EvalError| Failure::new(FailureKind::Condition, e.at(location));
+        match ctx.eval_if(line_if).map_err(script_error)? {
+            Evaluated::Ready(value) => value.as_boolean().map(Evaluated::Ready).ok_or_else(|| {
+                script_error(EvalError::type_mismatch(&**line_if, "a boolean", &value))
+            }),
+            Evaluated::Pending => Ok(Evaluated::Pending),
+        }
+    }
+
+    /// Evaluates the scripts and templates of the line at `location` on the script thread.
+    fn eval_line(
+        ctx: &mut BoaCtx,
+        line_type: &LineType,
+        location: &Location,
+    ) -> Result<Evaluated<EvaluatedLine>, Failure> {
+        let script_error = |kind| move |e: EvalError| Failure::new(kind, e.at(location));
+        let text_error = script_error(FailureKind::Text);
+        let eval_error = script_error(FailureKind::Eval);
+        let control_error = script_error(FailureKind::Control);
+
+        let evaluated_line = match line_type {
+            LineType::Message(message) => EvaluatedLine::Message(
+                ctx.eval_texts(&message.texts).map_err(text_error)?,
+                (ctx.eval_message_options(message.options.as_ref())).map_err(text_error)?,
+            ),
+            LineType::Confirm(confirm) => {
+                let texts = ctx.eval_texts(&confirm.message.texts).map_err(text_error)?;
+                let options = (ctx.eval_message_options(confirm.message.options.as_ref()))
+                    .map_err(text_error)?;
+                let response_texts = confirm
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.response.as_ref())
+                    .map(|response_texts| {
+                        Ok::<_, EvalError>(ConfirmResponse {
+                            yes: ctx.eval_texts(&response_texts.yes)?,
+                            no: ctx.eval_texts(&response_texts.no)?,
+                        })
+                    })
+                    .transpose()
+                    .map_err(text_error)?;
+                EvaluatedLine::Confirm(texts, options, response_texts)
+            }
+            LineType::Choice(choice) => {
+                let choice_texts = ctx.eval_choice_texts(&choice.texts).map_err(text_error)?;
+                let message = choice
+                    .message()
+                    .map(|message| {
+                        Ok::<_, EvalError>((
+                            ctx.eval_texts(&message.texts)?,
+                            ctx.eval_message_options(message.options.as_ref())?,
+                        ))
+                    })
+                    .transpose()
+                    .map_err(text_error)?;
+                EvaluatedLine::Choice(choice_texts, message)
+            }
+            LineType::Eval(eval) => {
+                tracing::debug!("Evaluating Eval line: {}", eval.source);
+                match ctx.eval_script(&eval.source).map_err(eval_error)? {
+                    Evaluated::Ready(_) => EvaluatedLine::Eval,
+                    Evaluated::Pending => return Ok(Evaluated::Pending),
+                }
+            }
+            LineType::Goto(goto) => {
+                let text = ctx
+                    .eval_text(&goto.pre_evaluation_value.as_str().into())
+                    .map_err(control_error)?;
+                EvaluatedLine::Goto(text.to_string())
+            }
+            LineType::Call(call) => EvaluatedLine::Call(
+                ctx.eval_text(&call.pre_evaluation_node_key)
+                    .map_err(control_error)?
+                    .into(),
+            ),
+            LineType::Return(r#return) => {
+                let source = &r#return.pre_evaluation_value;
+                let value = ctx.eval_str(source).and_then(|value| {
+                    ScriptValue::from_js(&value, ctx).map_err(EvalError::js(source.as_ref()))
+                });
+                EvaluatedLine::Return(value.map_err(control_error)?)
+            }
+            LineType::Exit(exit) => {
+                use dialogue::ExitValue;
+                let code = match &exit.value {
+                    ExitValue::PreEvaluation(source) => ctx
+                        .eval_str(source)
+                        .and_then(|value| {
+                            value.to_uint8(ctx).map_err(EvalError::js(source.as_str()))
+                        })
+                        .map_err(control_error)?,
+                    ExitValue::ExitCode(code) => *code,
+                };
+                EvaluatedLine::Exit(code)
+            }
+        };
+        Ok(Evaluated::Ready(evaluated_line))
+    }
+
     fn try_commit_fast_forward(
         data: &mut super::data::Data,
         nodes: &dialogue::Nodes,
         re_enter: bool,
+        now: std::time::Instant,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let fast_forward = &mut data.state_machine.fast_forward;
 
-        let start = (fast_forward.is_some() && re_enter)
-            .then(|| fast_forward.replace(std::time::Instant::now()))
-            .unwrap_or(fast_forward.take())
-            .ok_or("Not in fast forward state")?;
+        let start = if fast_forward.is_some() && re_enter {
+            fast_forward.replace(now)
+        } else {
+            fast_forward.take()
+        }
+        .ok_or("Not in fast forward state")?;
 
         let msg = "No current line to commit fast forward. Probably the first line has not been visited yet";
         let current_line_type = data.state_machine.current_line_type(nodes).ok_or(msg)?;
@@ -269,24 +913,32 @@ impl Runner<'_, '_> {
         match current_line_type {
             LineType::Message(_) => data
                 .visiting_state_mut::<MessageState>()
-                .map(|message_state| message_state.commit_fast_forward(start.elapsed()))
+                .map(|message_state| {
+                    message_state.commit_fast_forward(now.saturating_duration_since(start))
+                })
                 .ok_or("No message visiting state found"),
             LineType::Confirm(_) => data
                 .visiting_state_mut::<ConfirmState>()
-                .map(|cs| cs.commit_fast_forward(start.elapsed()))
+                .map(|cs| cs.commit_fast_forward(now.saturating_duration_since(start)))
                 .ok_or("No confirm visiting state found"),
             LineType::Choice(_) => data
                 .visiting_state_mut::<ChoiceState>()
-                .map(|cs| cs.try_commit_fast_forward(start.elapsed()))
+                .map(|cs| cs.try_commit_fast_forward(now.saturating_duration_since(start)))
                 .transpose()?
                 .ok_or("No choice visiting state found"),
-            _ => Err("Cannot commit fast forward on non-message line".into()),
+            _ => Err("Cannot commit fast forward on non-message line"),
         }?;
 
         Ok(())
     }
 }
 
+/// The line at `location`, which the runner made sure exists before handing it over to the
+/// script thread.
+fn line_at<'d>(dialogue: &'d Dialogue, location: &Location) -> &'d Line {
+    &dialogue.nodes[&location.node_key][*location.line_position]
+}
+
 #[derive(Debug)]
 enum ContinueReason {
     Skip,
@@ -294,18 +946,111 @@ enum ContinueReason {
 }
 
 #[derive(Debug)]
+enum BreakReason {
+    View,
+    /// Whether the line was entered, i.e. the host calls are not those of its `if:`.
+    Pending {
+        entered: bool,
+    },
+}
+
+/// An `if:` or `eval:` line waiting for async host calls, which runs again once all of them
+/// completed.
+#[derive(Debug)]
+struct Pending {
+    calls: Vec<HostCall>,
+    entered: bool,
+    checkpoint: Option<Checkpoint>,
+}
+
+/// What a line running again after [`Pending`] takes over from its previous run.
+#[derive(Debug, Default)]
+struct Resumed {
+    calls: Vec<HostCall>,
+    entered: bool,
+}
+
+/// What a failing line was doing, which picks its [`Recovery`] policy.
+#[derive(Debug, Clone, Copy)]
+enum FailureKind {
+    Text,
+    Condition,
+    Eval,
+    Control,
+}
+
+impl FailureKind {
+    fn policy(self, config: &RecoveryConfig) -> Recovery {
+        match self {
+            Self::Text => config.text,
+            Self::Condition => config.condition,
+            Self::Eval => config.eval,
+            Self::Control => config.control,
+        }
+    }
+}
+
+/// An error raised while evaluating a line. Errors without a kind are never recovered.
+#[derive(Debug)]
+struct Failure {
+    kind: Option<FailureKind>,
+    error: RunnerError,
+}
+
+impl Failure {
+    fn new(kind: FailureKind, error: RunnerError) -> Self {
+        let recoverable = !matches!(
+            error,
+            RunnerError::Limit { .. } | RunnerError::Internal { .. }
+        );
+        Self {
+            kind: recoverable.then_some(kind),
+            error,
+        }
+    }
+}
+
+impl From<RunnerError> for Failure {
+    fn from(error: RunnerError) -> Self {
+        Self { kind: None, error }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Action {
     Advance,
     ToggleFastForward,
+    /// Enters or leaves skip-read mode, which skips messages read in any session so far,
+    /// one per [`Runner::update_view`], and leaves at unread messages, confirms, choices
+    /// and errors.
+    ToggleSkipRead,
+    /// Enters or leaves auto-play mode, which advances past completed messages after the
+    /// delay of [`AutoPlayConfig`](crate::AutoPlayConfig), and leaves at confirms and choices.
+    ToggleAutoPlay,
+    /// Reports whether the host is playing the voice of the current message. Auto-play
+    /// waits for the voice to end before its delay starts.
+    SetVoicePlaying(bool),
     Skip,
+    /// Freezes typing, choice timeouts and auto-play until [`Action::Resume`]. Input
+    /// actions are rejected meanwhile.
+    Pause,
+    Resume,
     Confirm(bool),
     Select(ChoiceKey),
+    /// Rolls back `n` entries of the backlog, restoring the call stack, visiting states and
+    /// what scripts left to the line of that entry, which runs again with the results of its
+    /// host calls rather than calling them again. Only the last
+    /// [`Config::rollback_limit`](crate::Config::rollback_limit) entries can be returned to.
+    Rollback(usize),
+    /// Shows texts in another language from now on, keeping the share of the current message
+    /// already typed. Rejected if the current view has no text in that language.
+    SetLanguage(LanguageTag),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Data, Dialogue, DialogueCtx, Engine, Store};
+    use crate::{Data, Dialogue, Engine, Store};
 
     #[test]
     fn test_confirm() {
@@ -323,11 +1068,11 @@ nodes:
         .unwrap();
 
         let engine = Engine::default();
-        let mut store: Store<'_> = Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(&mut store, &dialogue).unwrap();
-        runner.dispatch(Action::Skip);
-        runner.dispatch(Action::Confirm(true));
-        dbg!(runner.update_view());
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Confirm(true)).unwrap();
+        dbg!(runner.update_view().unwrap());
         assert!(matches!(runner.view(), View::Terminated(_)));
     }
 
@@ -345,9 +1090,9 @@ nodes:
         .unwrap();
 
         let engine = Engine::default();
-        let mut store = Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(&mut store, &dialogue).unwrap();
-        runner.update_view();
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
+        runner.update_view().unwrap();
         assert!(matches!(runner.view(), View::Message(_)));
     }
 
@@ -363,9 +1108,9 @@ nodes:
         .unwrap();
 
         let engine = Engine::default();
-        let mut store = Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(&mut store, &dialogue).unwrap();
-        runner.update_view();
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
+        runner.update_view().unwrap();
         assert_eq!(runner.view(), &View::Terminated(0));
     }
 
@@ -387,14 +1132,14 @@ nodes:
         .unwrap();
 
         let engine = Engine::default();
-        let mut store = Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(&mut store, &dialogue).unwrap();
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
         let source = r#"
             assert_eq(lines[0].returned, undefined, "Returned value should be undefined");
             assert_eq(lines[1].returned, 42, "Returned value should be 42");
         "#;
-        runner.boa_ctx.eval_for_assert(source);
-        runner.update_view();
+        runner.eval_for_assert(source);
+        runner.update_view().unwrap();
         assert!(matches!(runner.view(), View::Message(_)));
     }
 
@@ -403,19 +1148,19 @@ nodes:
         let engine = Engine::default();
 
         let dialogue1: Dialogue = "nodes:\n  main:\n  - exit: 3".parse().unwrap();
-        let store = &mut Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(store, &dialogue1).unwrap();
-        assert_eq!(runner.update_view(), Some(&View::Terminated(3)));
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue1)).unwrap();
+        assert_eq!(runner.update_view().unwrap(), Some(&View::Terminated(3)));
 
         let dialogue2: Dialogue = "nodes:\n  main:\n  - exit: '1 + 2'".parse().unwrap();
-        let store = &mut Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(store, &dialogue2).unwrap();
-        assert_eq!(runner.update_view(), Some(&View::Terminated(3)));
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue2)).unwrap();
+        assert_eq!(runner.update_view().unwrap(), Some(&View::Terminated(3)));
 
         let dialogue3: Dialogue = "nodes:\n  main:".parse().unwrap();
-        let store = &mut Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(store, &dialogue3).unwrap();
-        assert_eq!(runner.update_view(), Some(&View::Terminated(0)));
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue3)).unwrap();
+        assert_eq!(runner.update_view().unwrap(), Some(&View::Terminated(0)));
     }
 
     #[test]
@@ -435,8 +1180,8 @@ nodes:
         .unwrap();
 
         let engine = Engine::default();
-        let store = &mut Store::new(&engine, Data::default());
-        let mut runner = Runner::instantiate(store, &dialogue).unwrap();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
 
         let source = r#"
             assert_eq(lines[0].id, "line1", "Line ID should be line1");
@@ -453,16 +1198,359 @@ nodes:
             assert_eq(lines[1].visited, false, "Line visited should be false");
             assert_eq(lines[1].visited_count, 0, "Line visited count should be 0");
         "#;
-        runner.boa_ctx.eval_for_assert(source);
+        runner.eval_for_assert(source);
 
-        runner.dispatch(Action::Select(ChoiceKey::new("foo")));
-        runner.dispatch(Action::Advance);
+        runner
+            .dispatch(Action::Select(ChoiceKey::new("foo")))
+            .unwrap();
+        runner.dispatch(Action::Advance).unwrap();
 
         let source = r#"
             assert_eq(lines.line1.selected, "foo", "Line ID should be line1");
             assert_eq(lines.line2.visited, true, "Line visited should be true");
             assert_eq(lines.line2.visited_count, 1, "Line visited count should be 1");
         "#;
-        runner.boa_ctx.eval_for_assert(source);
+        runner.eval_for_assert(source);
+    }
+
+    #[test]
+    fn test_recovery() {
+        use crate::{Config, Observer, Recovery, RecoveryConfig, RunnerError};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Recovered(Arc<Mutex<Vec<Recovery>>>);
+
+        impl Observer for Recovered {
+            fn on_error_recovered(&mut self, _error: &RunnerError, recovery: Recovery) {
+                self.0.lock().unwrap().push(recovery);
+            }
+        }
+
+        let dialogue: Dialogue = r#"
+on_error: oops
+nodes:
+  main:
+  - message: a${notDefined}
+  - if: notDefined
+    message: never
+  - eval: notDefined()
+  - goto: nowhere
+  - message: end
+  oops:
+  - message: oops
+"#
+        .parse()
+        .unwrap();
+
+        let config = Config {
+            recovery: RecoveryConfig {
+                text: Recovery::RenderRaw,
+                condition: Recovery::TreatAsFalse,
+                eval: Recovery::SkipLine,
+                control: Recovery::JumpToErrorNode,
+                raw_marker: "!".to_string(),
+            },
+            check_scripts: false,
+            ..Default::default()
+        };
+        let engine = Engine::builder().config(config).build();
+        let store = &Store::new(&engine, Data::default());
+        let recovered = Recovered::default();
+        let events = recovered.0.clone();
+        let mut runner = Runner::instantiate_with_observers(
+            store,
+            Arc::new(dialogue),
+            vec![Box::new(recovered)],
+        )
+        .unwrap();
+
+        let mut messages = Vec::new();
+        while !runner.is_terminated() {
+            runner.dispatch(Action::Skip).unwrap();
+            runner.update_view().unwrap();
+            messages.extend(runner.view().message().map(str::to_string));
+            runner.dispatch(Action::Advance).unwrap();
+        }
+        assert_eq!(messages, ["!a${notDefined}", "oops", "end"]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                Recovery::RenderRaw,
+                Recovery::TreatAsFalse,
+                Recovery::SkipLine,
+                Recovery::JumpToErrorNode,
+            ]
+        );
+
+        let dialogue: Dialogue = "nodes:\n  main:\n  - goto: nowhere".parse().unwrap();
+        let store = &Store::new(&engine, Data::default());
+        assert!(matches!(
+            Runner::instantiate(store, Arc::new(dialogue)),
+            Err(RunnerError::LineNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_auto_play() {
+        use crate::{Config, ManualClock};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - message: Hi
+  - message: Yo
+  - message: Ho
+  - confirm: OK?
+"#
+        .parse()
+        .unwrap();
+
+        let mut config = Config::default();
+        config.typing.auto_adjust = false;
+        config.typing.start_delay = Duration::ZERO;
+        config
+            .typing
+            .language_speeds
+            .insert("en".parse().unwrap(), 10.0.into());
+        let clock = Arc::new(ManualClock::default());
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+        let after = |millis, runner: &mut Runner| {
+            clock.advance(Duration::from_millis(millis));
+            runner.update_view().unwrap();
+            runner.view().message().map(str::to_owned)
+        };
+
+        assert!(runner.dispatch(Action::ToggleAutoPlay).unwrap());
+        assert_eq!(after(200, &mut runner).as_deref(), Some("Hi"));
+        // A second of delay, and 0.2s more to read two graphemes at 10 per second.
+        assert_eq!(after(1100, &mut runner).as_deref(), Some("Hi"));
+        runner.dispatch(Action::SetVoicePlaying(true)).unwrap();
+        assert_eq!(after(200, &mut runner).as_deref(), Some("Hi"));
+        runner.dispatch(Action::SetVoicePlaying(false)).unwrap();
+        assert_eq!(after(1100, &mut runner).as_deref(), Some("Hi"));
+        assert_eq!(after(150, &mut runner).as_deref(), Some(""));
+        assert!(runner.is_auto_play());
+
+        // The voice of a line advanced past is over even if the host never said so.
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::SetVoicePlaying(true)).unwrap();
+        assert!(runner.dispatch(Action::Advance).unwrap());
+        runner.dispatch(Action::Skip).unwrap();
+        assert_eq!(after(0, &mut runner).as_deref(), Some("Ho"));
+        after(1300, &mut runner);
+        assert!(matches!(runner.view(), View::Confirm(_)));
+        after(0, &mut runner);
+        assert!(!runner.is_auto_play());
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        use crate::{Config, ManualClock};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - message: abcdefghij
+  - choice:
+      foo: foo
+      bar: bar
+    options:
+      timeout: 10.0
+  - choice:
+      foo: foo
+    options:
+      timeout: 2.0
+      message:
+        texts: abcdefghij
+"#
+        .parse()
+        .unwrap();
+
+        let mut config = Config::default();
+        config.typing.auto_adjust = false;
+        config.typing.start_delay = Duration::ZERO;
+        config
+            .typing
+            .language_speeds
+            .insert("en".parse().unwrap(), 10.0.into());
+        let clock = Arc::new(ManualClock::default());
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        clock.advance(Duration::from_millis(350));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abc"));
+
+        assert!(runner.dispatch(Action::Pause).unwrap());
+        assert!(!runner.dispatch(Action::Pause).unwrap());
+        clock.advance(Duration::from_secs(5));
+        runner.update_view().unwrap();
+        assert!(runner.view().is_paused());
+        assert_eq!(runner.view().message(), Some("abc"));
+        assert!(!runner.dispatch(Action::Skip).unwrap());
+        assert!(!runner.dispatch(Action::ToggleFastForward).unwrap());
+
+        assert!(runner.dispatch(Action::Resume).unwrap());
+        assert!(!runner.dispatch(Action::Resume).unwrap());
+        runner.update_view().unwrap();
+        assert!(!runner.view().is_paused());
+        assert_eq!(runner.view().message(), Some("abc"));
+        clock.advance(Duration::from_millis(200));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abcde"));
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        clock.advance(Duration::from_secs(4));
+        runner.dispatch(Action::Pause).unwrap();
+        clock.advance(Duration::from_secs(20));
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        assert!(choice.is_paused());
+        assert_eq!(choice.remaining_time(), Duration::from_secs(6));
+        assert!(
+            !runner
+                .dispatch(Action::Select(ChoiceKey::new("foo")))
+                .unwrap()
+        );
+
+        runner.dispatch(Action::Resume).unwrap();
+        clock.advance(Duration::from_secs(5));
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        assert!(!choice.is_expired());
+        assert_eq!(choice.remaining_time(), Duration::from_secs(1));
+
+        runner
+            .dispatch(Action::Select(ChoiceKey::new("foo")))
+            .unwrap();
+        clock.advance(Duration::from_millis(350));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abc"));
+        runner.dispatch(Action::Pause).unwrap();
+        clock.advance(Duration::from_secs(5));
+        runner.dispatch(Action::Resume).unwrap();
+        clock.advance(Duration::from_millis(200));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abcde"));
+        runner.dispatch(Action::Skip).unwrap();
+        clock.advance(Duration::from_millis(1500));
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        assert_eq!(
+            choice.remaining_time(),
+            Duration::from_millis(500),
+            "the pause while typing does not count towards the timeout"
+        );
+    }
+
+    #[test]
+    fn test_send_runner() {
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::task::{Context, Waker};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let portrait = Arc::new(Mutex::new(None::<String>));
+        let engine = Engine::builder()
+            .function("count", {
+                let calls = calls.clone();
+                move || {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, String>(())
+                }
+            })
+            .async_function("load_portrait", {
+                let portrait = portrait.clone();
+                move |_name: String| {
+                    let portrait = portrait.clone();
+                    std::future::poll_fn(move |_| match portrait.lock().unwrap().take() {
+                        Some(portrait) => Poll::Ready(Ok::<_, String>(portrait)),
+                        None => Poll::Pending,
+                    })
+                }
+            })
+            .build();
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: count(); load_portrait("hero").then(p => { portrait = p })
+  - message: ${portrait}
+"#
+        .parse()
+        .unwrap();
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
+        assert!(runner.is_pending());
+
+        portrait.lock().unwrap().replace("smile".to_string());
+        let mut runner = std::thread::spawn(move || {
+            let mut cx = Context::from_waker(Waker::noop());
+            let settled = std::pin::pin!(runner.settle()).as_mut().poll(&mut cx);
+            assert!(matches!(settled, Poll::Ready(Ok(()))));
+            runner
+        })
+        .join()
+        .unwrap();
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("smile"));
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            1,
+            "the line should run again with the results of its first run"
+        );
+    }
+
+    #[test]
+    fn test_script_context_across_lines() {
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - eval: |
+      a = { x: 1 }; b = a; a.x = 2;
+      counter = (() => { let n = 0; return () => ++n })();
+      class Party { constructor() { this.members = new Set(["Alice"]) } }
+      party = new Party(); gold = new Map([["Alice", 10]]); since = new Date(0);
+  - message: ${b.x} ${counter()}
+  - eval: party.members.add("Bob"); gold.set("Bob", 5)
+  - message: ${counter()} ${[...party.members]} ${gold.get("Bob")} ${since.getTime()}
+"#
+        .parse()
+        .unwrap();
+
+        let engine = Engine::default();
+        let store = Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("2 1"),
+            "globals referring to the same object should see its changes"
+        );
+        runner.dispatch(Action::Advance).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(
+            runner.view().message(),
+            Some("2 Alice,Bob 5 0"),
+            "closures and objects of any kind should live on across advances"
+        );
     }
 }
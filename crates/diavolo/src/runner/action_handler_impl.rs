@@ -1,20 +1,49 @@
+use super::super::error::RunnerError;
+use super::super::line_state::{ChoiceState, ConfirmState, MessageState};
 use super::super::view::View;
-use super::super::line_state::{
-    ChoiceState, ConfirmState, MessageState,
-};
-use super::Runner;
+use super::{Resumed, Runner};
 
 use dialogue::ChoiceKey;
+use language_tags::LanguageTag;
 
-impl Runner<'_, '_> {
-    pub(super) fn handle_advance(self: &mut Self) -> Result<(), Box<dyn std::error::Error>> {
+/// Why an action was not handled: it does not apply to the current view, or the runner
+/// failed while carrying it out.
+pub(super) enum ActionError {
+    Rejected(String),
+    Runner(RunnerError),
+}
+
+impl From<RunnerError> for ActionError {
+    fn from(e: RunnerError) -> Self {
+        Self::Runner(e)
+    }
+}
+
+impl From<&str> for ActionError {
+    fn from(reason: &str) -> Self {
+        Self::Rejected(reason.to_string())
+    }
+}
+
+impl From<String> for ActionError {
+    fn from(reason: String) -> Self {
+        Self::Rejected(reason)
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for ActionError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        Self::Rejected(e.to_string())
+    }
+}
+
+impl Runner {
+    pub(super) fn handle_advance(&mut self) -> Result<(), ActionError> {
         tracing::debug!("Advance requested");
 
-        match View::new(
-            &self.store.engine,
-            &mut self.store.data.lock().unwrap(),
-            &self.dialogue.nodes,
-        ) {
+        let data = self.store.data.lock().unwrap();
+
+        match View::new(&self.store.engine, &data, &self.dialogue.nodes)? {
             View::Terminated(code) => {
                 return Err(format!(
                     "Dialogue has already terminated with exit code {}, cannot advance",
@@ -33,70 +62,165 @@ impl Runner<'_, '_> {
                     .is_confirmed()
                     .then_some(())
                     .ok_or("Confirm is not confirmed yet, cannot advance")?;
+                let confirmed = data
+                    .visiting_state::<ConfirmState>()
+                    .and_then(|cs| cs.confirmed);
+                self.backlog.record_confirmed(confirmed);
+                if let Some(approved) = confirmed {
+                    let location = data.state_machine.location();
+                    self.observers
+                        .notify(|o| o.on_confirmed(location, approved));
+                }
             }
             View::Choice(choice) => {
                 choice
                     .is_selected()
                     .then_some(())
                     .ok_or("Choice is not selected yet, cannot advance")?;
+                self.backlog.record_selected(choice.selected().cloned());
+                if let Some(choice_key) = choice.selected() {
+                    let location = data.state_machine.location();
+                    self.observers
+                        .notify(|o| o.on_choice_selected(location, choice_key));
+                }
             }
-            _ => todo!("Unimplemented line type"),
+            View::Pending => {
+                return Err("Waiting for a pending promise to settle, cannot advance".into());
+            }
+            View::None => return Err("No view to advance from".into()),
         }
+        drop(data);
 
         Ok(self.advance()?)
     }
 
-    pub(super) fn handle_toggle_fast_forward(
-        self: &mut Self,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub(super) fn handle_toggle_fast_forward(&mut self) -> Result<(), ActionError> {
         tracing::debug!("Toggle fast forward requested");
         let mut data = self.store.data.lock().unwrap();
         let state_machine = &mut data.state_machine;
+        if state_machine.is_paused() {
+            return Err("Runner is paused, cannot toggle fast forward".into());
+        }
 
         if !state_machine.is_fast_forward() {
             tracing::debug!("Enter fast forward");
-            state_machine
-                .fast_forward
-                .replace(std::time::Instant::now());
+            state_machine.fast_forward.replace(self.store.clock().now());
         } else {
             tracing::debug!("Release fast forward");
-            Self::try_commit_fast_forward(&mut data, &self.dialogue.nodes, false)?;
+            let now = self.store.clock().now();
+            Self::try_commit_fast_forward(&mut data, &self.dialogue.nodes, false, now)?;
         }
 
         Ok(())
     }
 
-    pub(super) fn handle_skip(self: &mut Self) -> Result<(), Box<dyn std::error::Error>> {
+    pub(super) fn handle_toggle_skip_read(&mut self) -> Result<(), ActionError> {
+        tracing::debug!("Toggle skip-read requested");
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &mut data.state_machine;
+        state_machine.skip_read = !state_machine.skip_read;
+        Ok(())
+    }
+
+    pub(super) fn handle_toggle_auto_play(&mut self) -> Result<(), ActionError> {
+        tracing::debug!("Toggle auto-play requested");
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &mut data.state_machine;
+        state_machine.auto_play = !state_machine.auto_play;
+        Ok(())
+    }
+
+    pub(super) fn handle_set_voice_playing(&mut self, playing: bool) -> Result<(), ActionError> {
+        tracing::debug!("Voice playing: {}", playing);
+        let now = self.store.clock().now();
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &mut data.state_machine;
+        if state_machine.voice_playing && !playing {
+            state_machine.voice_ended_at = Some(now);
+        }
+        state_machine.voice_playing = playing;
+        Ok(())
+    }
+
+    pub(super) fn handle_pause(&mut self) -> Result<(), ActionError> {
+        tracing::debug!("Pause requested");
+        let now = self.store.clock().now();
+        let mut data = self.store.data.lock().unwrap();
+        if data.state_machine.is_paused() {
+            return Err("Runner is already paused".into());
+        }
+        data.state_machine.paused = Some(now);
+        Ok(())
+    }
+
+    /// Resumes a paused runner, committing the time spent paused to the current line so that
+    /// typing, choice timeouts and fast forward carry on from where they were.
+    pub(super) fn handle_resume(&mut self) -> Result<(), ActionError> {
+        tracing::debug!("Resume requested");
+        let now = self.store.clock().now();
+        let mut data = self.store.data.lock().unwrap();
+        let state_machine = &mut data.state_machine;
+        let paused_at = state_machine.paused.take().ok_or("Runner is not paused")?;
+        let paused_for = now.saturating_duration_since(paused_at);
+        if let Some(start) = &mut state_machine.fast_forward {
+            *start = match *start < paused_at {
+                true => *start + paused_for,
+                false => now,
+            };
+        }
+        state_machine.resumed_at = Some(now);
+
+        if let Some(choice_state) = data.visiting_state_mut::<ChoiceState>() {
+            choice_state.commit_pause(paused_at, now);
+        } else if let Some(message_state) = data.message_state_mut() {
+            message_state.commit_pause(paused_at, now);
+        }
+        Ok(())
+    }
+
+    pub(super) fn handle_skip(&mut self) -> Result<(), ActionError> {
         tracing::debug!("Skip requested");
+        let now = self.store.clock().now();
         let mut data = self.store.data.lock().unwrap();
-        let view = View::new(&self.store.engine, &mut data, &self.dialogue.nodes);
+        let view = View::new(&self.store.engine, &data, &self.dialogue.nodes)?;
+        let text = view.message_text().cloned();
 
-        match view {
-            View::Message(_) => {
-                data.visiting_state_mut_or_panic::<MessageState>()
-                    .skip();
-                Ok(())
+        let message_state = match view {
+            View::Message(_) => Some(data.current_state_mut::<MessageState>()?),
+            View::Confirm(_) => Some(&mut data.current_state_mut::<ConfirmState>()?.message_state),
+            View::Choice(_) => None,
+            _ => {
+                return Err("Current view state is neither message nor choice, cannot skip".into());
+            }
+        };
+
+        let completed = match message_state {
+            Some(message_state) => {
+                let completed = !message_state.is_completed();
+                message_state.skip(now);
+                completed
             }
-            View::Confirm(_) => {
-                data.visiting_state_mut_or_panic::<ConfirmState>()
-                    .message_state
-                    .skip();
-                Ok(())
+            None => {
+                data.current_state_mut::<ChoiceState>()?
+                    .try_skip_message(now)?;
+                true
             }
-            View::Choice(_) => data
-                .visiting_state_mut_or_panic::<ChoiceState>()
-                .try_skip_message(),
-            _ => Err("Current view state is neither message nor choice, cannot skip".into()),
+        };
+
+        if let Some(text) = text.filter(|_| completed) {
+            let location = data.state_machine.location();
+            self.observers
+                .notify(|o| o.on_message_completed(location, &text));
+            self.mark_read(&data);
         }
+
+        Ok(())
     }
 
-    pub(super) fn handle_confirm(
-        self: &mut Self,
-        approved: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub(super) fn handle_confirm(&mut self, approved: bool) -> Result<(), ActionError> {
         tracing::debug!("Confirm response: {:?}", approved);
         let mut data = self.store.data.lock().unwrap();
-        let view = View::new(&self.store.engine, &mut data, &self.dialogue.nodes);
+        let view = View::new(&self.store.engine, &data, &self.dialogue.nodes)?;
 
         match view {
             View::Confirm(confirm) => {
@@ -104,7 +228,7 @@ impl Runner<'_, '_> {
                     return Err("Confirm already responded or message is not completed yet".into());
                 }
 
-                data.visiting_state_mut_or_panic::<ConfirmState>()
+                data.current_state_mut::<ConfirmState>()?
                     .confirmed
                     .replace(approved);
                 drop(data);
@@ -115,13 +239,10 @@ impl Runner<'_, '_> {
         }
     }
 
-    pub(super) fn handle_select(
-        self: &mut Self,
-        choice_key: &ChoiceKey,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    pub(super) fn handle_select(&mut self, choice_key: &ChoiceKey) -> Result<(), ActionError> {
         tracing::debug!("Choice selected: {:?}", choice_key);
         let mut data = self.store.data.lock().unwrap();
-        let view = View::new(&self.store.engine, &mut data, &self.dialogue.nodes);
+        let view = View::new(&self.store.engine, &data, &self.dialogue.nodes)?;
 
         match view {
             View::Choice(choice) => {
@@ -136,8 +257,8 @@ impl Runner<'_, '_> {
                     return Err("Choice selection period has expired".into());
                 }
 
-                data.visiting_state_mut_or_panic::<ChoiceState>()
-                    .select(choice_key);
+                data.current_state_mut::<ChoiceState>()?
+                    .select(choice_key, self.store.clock().now());
                 drop(data);
 
                 self.handle_advance()
@@ -145,4 +266,86 @@ impl Runner<'_, '_> {
             _ => Err("Current view state is not choice".into()),
         }
     }
+
+    pub(super) fn handle_rollback(&mut self, n: usize) -> Result<(), ActionError> {
+        tracing::debug!("Rollback requested: {} entries", n);
+        if n == 0 {
+            return Err("Cannot roll back 0 entries, which would replay the current line".into());
+        }
+        let checkpoint = self.backlog.rewind(n).ok_or_else(|| {
+            format!(
+                "Cannot roll back {} entries, only the last {} can be",
+                n,
+                self.backlog.rewindable()
+            )
+        })?;
+
+        let location = self.location();
+        self.scripts.restore(checkpoint.script(), &location)?;
+        let mut data = self.store.data.lock().unwrap();
+        if self.pending.take().is_some() {
+            tracing::debug!("Dropping the pending promise");
+            data.pending = false;
+        }
+        let calls = checkpoint.restore(&mut data);
+        data.state_machine.reset_voice();
+        drop(data);
+
+        let resumed = Resumed {
+            calls,
+            entered: false,
+        };
+        Ok(self.advance_from(Some(resumed), None)?)
+    }
+
+    pub(super) fn handle_set_language(
+        &mut self,
+        language: &LanguageTag,
+    ) -> Result<(), ActionError> {
+        tracing::debug!("Language change requested: {}", language);
+        let engine = &self.store.engine;
+        let nodes = &self.dialogue.nodes;
+        let mut data = self.store.data.lock().unwrap();
+        let now = data.state_machine.view_time(self.store.clock().now());
+        let progress = View::new(engine, &data, nodes)?.typing_progress();
+
+        let previous = data.language.replace(language.clone());
+        let previous_timeline = data.message_state_mut().and_then(|ms| ms.reset_timeline());
+        if let Err(error) = View::new(engine, &data, nodes) {
+            data.language = previous;
+            if let Some(message_state) = data.message_state_mut() {
+                message_state.reset_timeline();
+                if let Some(timeline) = previous_timeline {
+                    message_state.timeline(|| timeline);
+                }
+            }
+            return Err(match error {
+                RunnerError::MissingText { .. } | RunnerError::MissingActorName { .. } => {
+                    error.to_string().into()
+                }
+                error => error.into(),
+            });
+        }
+
+        if let Some(progress) = progress {
+            let fast_forward = data.state_machine.fast_forward;
+            let location = data.state_machine.location().clone();
+            let message_state = data
+                .message_state_mut()
+                .ok_or("No message state to retime")?;
+            let elapsed = View::typing_elapsed(
+                fast_forward.as_ref(),
+                message_state,
+                &engine.config().typing,
+                now,
+            );
+            let timeline = message_state.built_timeline().ok_or_else(|| {
+                RunnerError::internal(&location, "The view built no typing timeline to retime")
+            })?;
+            let target = timeline.elapsed_for((progress * timeline.len() as f32).round() as usize);
+            message_state.retime(elapsed, target);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,91 @@
+use super::super::boa_ctx::BoaCtx;
+use super::super::error::RunnerError;
+use dialogue::Location;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+
+type Job = Box<dyn FnOnce(&mut BoaCtx) + Send>;
+
+/// The thread the script context of a runner lives on, as boa values cannot leave the thread
+/// they were created on. The context lives as long as the runner, so that later lines see the
+/// very values earlier ones left, and the runner waits for every job it hands over.
+pub(crate) struct ScriptThread {
+    jobs: Sender<Job>,
+}
+
+/// A snapshot of the script context, see [`BoaCtx::snapshot`]. It is kept on the script
+/// thread until the handle is dropped.
+#[derive(Debug)]
+pub(crate) struct ScriptSnapshot {
+    id: u64,
+    jobs: Sender<Job>,
+}
+
+impl ScriptThread {
+    pub fn spawn() -> Result<Self, RunnerError> {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("diavolo-scripts".to_string())
+            .spawn(move || {
+                let mut ctx = BoaCtx::default();
+                for job in receiver {
+                    job(&mut ctx);
+                }
+                tracing::debug!("Script thread finished");
+            })
+            .map_err(|e| RunnerError::Setup(e.to_string()))?;
+        Ok(Self { jobs })
+    }
+
+    /// Runs `f` on the script thread and returns its result. A panic in `f`, e.g. in a host
+    /// function, resumes on the calling thread.
+    pub fn run<T: Send + 'static>(&self, f: impl FnOnce(&mut BoaCtx) -> T + Send + 'static) -> T {
+        let (reply, result) = mpsc::sync_channel(1);
+        let job: Job = Box::new(move |ctx| {
+            let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(|| f(ctx))));
+        });
+        (self.jobs)
+            .send(job)
+            .expect("the script thread should live as long as the runner");
+        match result.recv().expect("the script thread should reply") {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Takes a snapshot of what scripts left so far, while the runner is at `location`.
+    pub fn snapshot(&self, location: &Location) -> Result<ScriptSnapshot, RunnerError> {
+        let id = self.run(|ctx| ctx.snapshot().map_err(|e| e.to_string()));
+        let id = id.map_err(|e| {
+            RunnerError::internal(location, format!("Failed to take a script snapshot: {e}"))
+        })?;
+        Ok(ScriptSnapshot {
+            id,
+            jobs: self.jobs.clone(),
+        })
+    }
+
+    /// Returns the script context to `snapshot`, while the runner is at `location`.
+    pub fn restore(
+        &self,
+        snapshot: &ScriptSnapshot,
+        location: &Location,
+    ) -> Result<(), RunnerError> {
+        let id = snapshot.id;
+        let restored = self.run(move |ctx| ctx.restore(id).map_err(|e| e.to_string()));
+        restored.map_err(|e| {
+            RunnerError::internal(
+                location,
+                format!("Failed to restore a script snapshot: {e}"),
+            )
+        })
+    }
+}
+
+impl Drop for ScriptSnapshot {
+    fn drop(&mut self) {
+        let id = self.id;
+        let _ = self.jobs.send(Box::new(move |ctx| ctx.forget(id)));
+    }
+}
@@ -0,0 +1,207 @@
+use boa_engine::object::builtins::JsArray;
+use boa_engine::{Context, JsNativeError, JsObject, JsResult, JsValue, Source, js_string};
+use indexmap::IndexMap;
+
+use std::fmt::{self, Display};
+
+/// A script value copied out of the script context, such as the value a `return:` line
+/// returns or an arg, so that it can live in the data shared with other threads.
+///
+/// Arrays and plain objects are copied with their own enumerable properties. Functions are
+/// kept as their source and evaluated again in the global scope, so they do not see what they
+/// closed over. Other values, like classes, whose source boa does not keep, their instances
+/// or a `Map`, cannot be copied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ScriptValue {
+    #[default]
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<ScriptValue>),
+    Object(IndexMap<String, ScriptValue>),
+    Function(String),
+}
+
+impl ScriptValue {
+    pub(crate) fn from_js(value: &JsValue, context: &mut Context) -> JsResult<Self> {
+        Self::copy(value, &mut Vec::new(), context)
+    }
+
+    fn copy(value: &JsValue, parents: &mut Vec<JsObject>, context: &mut Context) -> JsResult<Self> {
+        if value.is_undefined() {
+            return Ok(Self::Undefined);
+        } else if value.is_null() {
+            return Ok(Self::Null);
+        } else if let Some(boolean) = value.as_boolean() {
+            return Ok(Self::Bool(boolean));
+        } else if let Some(number) = value.as_number() {
+            return Ok(Self::Number(number));
+        } else if let Some(string) = value.as_string() {
+            return Ok(Self::String(string.to_std_string_escaped()));
+        }
+        let Some(object) = value.as_object() else {
+            return Err(Self::uncopyable(format!("a {}", value.type_of())));
+        };
+        if parents
+            .iter()
+            .any(|parent| JsObject::equals(parent, &object))
+        {
+            return Err(Self::uncopyable("an object containing itself".to_string()));
+        }
+
+        if object.is_callable() {
+            let source = value.to_string(context)?.to_std_string_escaped();
+            if source.contains("[native code]") {
+                return Err(Self::uncopyable(
+                    "a class or a built-in function".to_string(),
+                ));
+            }
+            return Ok(Self::Function(source));
+        }
+
+        parents.push(object.clone());
+        let copy = if object.is_array() {
+            let array = JsArray::from_object(object.clone())?;
+            (0..array.length(context)?)
+                .map(|index| Self::copy(&array.get(index, context)?, parents, context))
+                .collect::<JsResult<_>>()
+                .map(Self::Array)
+        } else if Self::is_plain(&object, context) {
+            let keys = context
+                .intrinsics()
+                .constructors()
+                .object()
+                .constructor()
+                .get(js_string!("keys"), context)?
+                .as_callable()
+                .expect("Object.keys should be a function")
+                .call(&JsValue::undefined(), std::slice::from_ref(value), context)?;
+            let keys = JsArray::from_object(keys.as_object().expect("keys should be an array"))?;
+            (0..keys.length(context)?)
+                .map(|index| {
+                    let key = keys.get(index, context)?.to_string(context)?;
+                    let value = object.get(key.clone(), context)?;
+                    Ok((
+                        key.to_std_string_escaped(),
+                        Self::copy(&value, parents, context)?,
+                    ))
+                })
+                .collect::<JsResult<_>>()
+                .map(Self::Object)
+        } else {
+            Err(Self::uncopyable(Self::describe(&object, context)))
+        };
+        parents.pop();
+        copy
+    }
+
+    /// Whether `object` is a plain object, created by a literal or with a `null` prototype.
+    fn is_plain(object: &JsObject, context: &Context) -> bool {
+        let object_prototype = context.intrinsics().constructors().object().prototype();
+        object
+            .prototype()
+            .is_none_or(|prototype| JsObject::equals(&prototype, &object_prototype))
+    }
+
+    /// Names `object` by its constructor, e.g. "a Map".
+    fn describe(object: &JsObject, context: &mut Context) -> String {
+        let name = object
+            .get(js_string!("constructor"), context)
+            .ok()
+            .and_then(|constructor| constructor.as_object())
+            .and_then(|constructor| constructor.get(js_string!("name"), context).ok())
+            .and_then(|name| name.as_string())
+            .map(|name| name.to_std_string_escaped())
+            .filter(|name| !name.is_empty());
+        match name {
+            Some(name) => format!("a {name}"),
+            None => "an object with a prototype".to_string(),
+        }
+    }
+
+    fn uncopyable(what: String) -> boa_engine::JsError {
+        JsNativeError::typ()
+            .with_message(format!("{what} cannot be kept for later lines"))
+            .into()
+    }
+
+    pub(crate) fn to_js(&self, context: &mut Context) -> JsResult<JsValue> {
+        Ok(match self {
+            Self::Undefined => JsValue::undefined(),
+            Self::Null => JsValue::null(),
+            Self::Bool(boolean) => (*boolean).into(),
+            Self::Number(number) => (*number).into(),
+            Self::String(string) => js_string!(string.as_str()).into(),
+            Self::Array(values) => {
+                let values = (values.iter())
+                    .map(|value| value.to_js(context))
+                    .collect::<JsResult<Vec<_>>>()?;
+                JsArray::from_iter(values, context).into()
+            }
+            Self::Object(properties) => {
+                let object = JsObject::with_object_proto(context.intrinsics());
+                for (key, value) in properties {
+                    let value = value.to_js(context)?;
+                    object.create_data_property_or_throw(
+                        js_string!(key.as_str()),
+                        value,
+                        context,
+                    )?;
+                }
+                object.into()
+            }
+            Self::Function(source) => {
+                // Methods like `f() {}` only parse within an object literal.
+                match context.eval(Source::from_bytes(&format!("({source})"))) {
+                    Ok(function) => function,
+                    Err(_) => {
+                        let object =
+                            context.eval(Source::from_bytes(&format!("({{{source}}})")))?;
+                        let object = object.as_object().expect("an object literal");
+                        let key = object.own_property_keys(context)?.into_iter().next();
+                        match key {
+                            Some(key) => object.get(key, context)?,
+                            None => JsValue::undefined(),
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Formats the value the way scripts write it, with strings quoted.
+impl Display for ScriptValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Undefined => write!(f, "undefined"),
+            Self::Null => write!(f, "null"),
+            Self::Bool(boolean) => write!(f, "{boolean}"),
+            Self::Number(number) if number.is_infinite() => {
+                write!(f, "{}Infinity", if *number < 0.0 { "-" } else { "" })
+            }
+            Self::Number(number) => write!(f, "{number}"),
+            Self::String(string) => write!(f, "{string:?}"),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    let separator = if index == 0 { "" } else { ", " };
+                    write!(f, "{separator}{value}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(properties) if properties.is_empty() => write!(f, "{{}}"),
+            Self::Object(properties) => {
+                write!(f, "{{ ")?;
+                for (index, (key, value)) in properties.iter().enumerate() {
+                    let separator = if index == 0 { "" } else { ", " };
+                    write!(f, "{separator}{key}: {value}")?;
+                }
+                write!(f, " }}")
+            }
+            Self::Function(source) => write!(f, "{source}"),
+        }
+    }
+}
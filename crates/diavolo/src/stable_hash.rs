@@ -0,0 +1,91 @@
+use std::hash::Hasher;
+
+/// FNV-1a hasher whose output does not change across processes or Rust versions,
+/// unlike `DefaultHasher`. Suitable for values persisted to disk.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl StableHasher {
+    /// Hashes a JSON value with object keys in sorted order, so that map ordering does not
+    /// affect the result.
+    pub fn write_json(&mut self, value: &serde_json::Value) {
+        use serde_json::Value;
+
+        match value {
+            Value::Null => self.write_u8(0),
+            Value::Bool(b) => {
+                self.write_u8(1);
+                self.write_u8(*b as u8);
+            }
+            Value::Number(n) => {
+                self.write_u8(2);
+                self.write_str(&n.to_string());
+            }
+            Value::String(s) => {
+                self.write_u8(3);
+                self.write_str(s);
+            }
+            Value::Array(values) => {
+                self.write_u8(4);
+                self.write_len(values.len());
+                values.iter().for_each(|v| self.write_json(v));
+            }
+            Value::Object(map) => {
+                self.write_u8(5);
+                self.write_len(map.len());
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(key, _)| *key);
+                for (key, value) in entries {
+                    self.write_str(key);
+                    self.write_json(value);
+                }
+            }
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_len(s.len());
+        self.write(s.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_key_order() {
+        let a = serde_json::json!({ "x": 1, "y": [true, "z"] });
+        let b = serde_json::json!({ "y": [true, "z"], "x": 1 });
+
+        let mut hasher_a = StableHasher::default();
+        hasher_a.write_json(&a);
+        let mut hasher_b = StableHasher::default();
+        hasher_b.write_json(&b);
+
+        assert_eq!(hasher_a.finish(), hasher_b.finish());
+    }
+}
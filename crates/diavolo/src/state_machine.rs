@@ -8,6 +8,16 @@ use std::time::Instant;
 pub struct StateMachine {
     pub call_stack: CallStack,
     pub fast_forward: Option<Instant>,
+    /// Skip-read mode, which skips messages read before until something else shows up.
+    pub skip_read: bool,
+    /// Auto-play mode, which advances past completed messages after a reading delay.
+    pub auto_play: bool,
+    pub voice_playing: bool,
+    pub voice_ended_at: Option<Instant>,
+    pub resumed_at: Option<Instant>,
+    /// When the runner was paused. Views are computed as of then until it resumes, which
+    /// freezes typing and choice timeouts.
+    pub paused: Option<Instant>,
 }
 
 impl Deref for StateMachine {
@@ -28,6 +38,21 @@ impl StateMachine {
     pub fn is_fast_forward(&self) -> bool {
         self.fast_forward.is_some()
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Forgets the voice of the line being left, which cannot hold auto-play on the next one.
+    pub fn reset_voice(&mut self) {
+        self.voice_playing = false;
+        self.voice_ended_at = None;
+    }
+
+    /// The time views are computed at: `now`, or the start of the pause.
+    pub fn view_time(&self, now: Instant) -> Instant {
+        self.paused.unwrap_or(now)
+    }
 }
 
 use dialogue::{Line, LineIf, LineType, Node, Nodes};
@@ -49,7 +74,7 @@ impl<'d> StateMachine {
         self.current_line(nodes).and_then(|line| line.r#if.as_ref())
     }
 
-    fn current_line(&self, nodes: &'d Nodes) -> Option<&'d Line> {
+    pub fn current_line(&self, nodes: &'d Nodes) -> Option<&'d Line> {
         self.current_node(nodes)
             .and_then(|node| node.get(*self.call_stack.current_line_position()))
     }
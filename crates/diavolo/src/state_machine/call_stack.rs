@@ -1,8 +1,9 @@
+use super::super::error::LimitExceeded;
 use dialogue::{LinePosition, Location, NodeKey};
 
 use std::ops::{Deref, DerefMut};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct CallStack(Vec<Location>);
 
 impl Deref for CallStack {
@@ -20,12 +21,12 @@ impl DerefMut for CallStack {
 }
 
 impl CallStack {
-    pub fn call(&mut self, node_key: NodeKey) {
-        const MAX_DEPTH: usize = 1024;
-        if self.len() >= MAX_DEPTH {
-            panic!("Maximum call stack depth ({}) exceeded.", MAX_DEPTH);
+    pub fn call(&mut self, node_key: NodeKey, max_depth: usize) -> Result<(), LimitExceeded> {
+        if self.len() >= max_depth {
+            return Err(LimitExceeded::CallStackDepth(max_depth));
         }
         self.push(Location::uninitialized(node_key));
+        Ok(())
     }
 
     pub fn advance(&mut self) {
@@ -1,18 +1,25 @@
+use super::clock::Clock;
 use super::data::Data;
 use super::engine::Engine;
 
 use std::sync::{Arc, Mutex};
 
-pub struct Store<'e> {
-    pub engine: &'e Engine,
+/// The engine and the data a runner works on. Clones share the same data.
+#[derive(Clone)]
+pub struct Store {
+    pub engine: Engine,
     pub data: Arc<Mutex<Data>>,
 }
 
-impl<'e> Store<'e> {
-    pub fn new(engine: &'e Engine, data: Data) -> Store<'e> {
+impl Store {
+    pub fn new(engine: &Engine, data: Data) -> Store {
         Store {
-            engine,
+            engine: engine.clone(),
             data: Arc::new(Mutex::new(data)),
         }
     }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        self.engine.clock()
+    }
 }
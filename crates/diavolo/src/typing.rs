@@ -0,0 +1,141 @@
+use super::engine::config::TypingConfig;
+use dialogue::TypingSpeedFactor;
+
+use language_tags::LanguageTag;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// When each grapheme cluster of a message is revealed, computed once when the message is
+/// first shown.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TypingTimeline {
+    /// Graphemes revealed per second of typing.
+    speed: f32,
+    /// Total punctuation pause elapsed before each grapheme cluster is revealed.
+    pauses: Vec<Duration>,
+}
+
+impl TypingTimeline {
+    pub(crate) fn new(
+        text: &str,
+        language: &LanguageTag,
+        typing: &TypingConfig,
+        speed_factor: TypingSpeedFactor,
+    ) -> Self {
+        let speed = *typing.effective_speed(&text.into(), language) * *speed_factor;
+        let pauses_by_char = typing.punctuation_pauses.get(language);
+        let pause_of = |grapheme: &str| {
+            let last = grapheme.chars().next_back()?;
+            let pause = pauses_by_char?.get(&last)?.as_secs_f32() / *speed_factor;
+            Some(Duration::try_from_secs_f32(pause).unwrap_or(Duration::MAX))
+        };
+
+        let mut pauses = Vec::new();
+        let mut total = Duration::ZERO;
+        // A run of punctuation pauses once, after its last mark, for its longest pause.
+        let mut pending = Duration::ZERO;
+        for grapheme in text.graphemes(true) {
+            match pause_of(grapheme) {
+                Some(pause) => pending = pending.max(pause),
+                None => total = total.saturating_add(std::mem::take(&mut pending)),
+            }
+            pauses.push(total);
+        }
+
+        Self { speed, pauses }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pauses.len()
+    }
+
+    /// Number of grapheme clusters revealed after typing for `elapsed`.
+    pub(crate) fn visible_graphemes(&self, elapsed: Duration) -> usize {
+        let is_revealed = |index: usize| {
+            let typing = elapsed.saturating_sub(self.pauses[index]);
+            // Saturating, as a speed overflowing durations reveals everything at once.
+            (typing.as_secs_f32() * self.speed) as usize > index
+        };
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            match is_revealed(mid) {
+                true => low = mid + 1,
+                false => high = mid,
+            }
+        }
+        low
+    }
+
+    /// Typing time after which `graphemes` grapheme clusters are revealed, halfway to the next.
+    pub(crate) fn elapsed_for(&self, graphemes: usize) -> Duration {
+        let pause = graphemes
+            .checked_sub(1)
+            .map_or(Duration::ZERO, |last| self.pauses[last]);
+        let typing = Duration::try_from_secs_f32((graphemes as f32 + 0.5) / self.speed);
+        pause.saturating_add(typing.unwrap_or(Duration::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing_timeline() {
+        use crate::{Config, Data, Dialogue, Engine, ManualClock, Runner, Store};
+        use std::sync::Arc;
+
+        let source = "nodes:\n  main:\n  - message: \"Hi, \u{1F468}\u{200D}\u{1F467}e\u{301}\"";
+        let dialogue: Dialogue = source.parse().unwrap();
+
+        let en = LanguageTag::parse("en").unwrap();
+        let mut config = Config::default();
+        config.typing.auto_adjust = false;
+        config.typing.start_delay = Duration::ZERO;
+        config
+            .typing
+            .language_speeds
+            .insert(en.clone(), 10.0.into());
+        config.typing.punctuation_pauses.insert(
+            en,
+            [(',', Duration::from_millis(500))].into_iter().collect(),
+        );
+        let clock = Arc::new(ManualClock::with_unix_time(Duration::from_secs(1000)));
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        let mut visible_at = |millis| {
+            clock.advance(Duration::from_millis(millis));
+            runner.update_view().unwrap();
+            runner.view().message().unwrap().to_string()
+        };
+        assert_eq!(visible_at(350), "Hi,");
+        assert_eq!(visible_at(500), "Hi,");
+        assert_eq!(visible_at(100), "Hi, ");
+        assert_eq!(visible_at(100), "Hi, \u{1F468}\u{200D}\u{1F467}");
+        assert_eq!(visible_at(100), "Hi, \u{1F468}\u{200D}\u{1F467}e\u{301}");
+    }
+
+    #[test]
+    fn test_extreme_speeds() {
+        let en = LanguageTag::parse("en").unwrap();
+        let mut typing = TypingConfig::default();
+        typing.punctuation_pauses.insert(
+            en.clone(),
+            [(',', Duration::from_millis(500))].into_iter().collect(),
+        );
+
+        let slow = TypingTimeline::new("a, b", &en, &typing, f32::MIN_POSITIVE.into());
+        assert_eq!(slow.visible_graphemes(Duration::from_secs(3600)), 0);
+        assert_eq!(slow.elapsed_for(4), Duration::MAX);
+
+        let fast = TypingTimeline::new("a, b", &en, &typing, f32::MAX.into());
+        assert_eq!(fast.visible_graphemes(Duration::from_secs(1)), 4);
+        assert_eq!(fast.elapsed_for(4), Duration::ZERO);
+    }
+}
@@ -6,19 +6,30 @@ use choice::ChoiceView;
 use confirm::ConfirmView;
 use message::{MessageLifecycle, MessageView};
 
+use super::clock::ClockHandle;
 use super::data::Data;
 use super::dialogue_ctx::ViewActor;
+use super::emotions::ActorState;
 use super::engine::Engine;
+use super::engine::config::{Config, SecondaryReveal};
+use super::error::RunnerError;
 use super::line_state::{ChoiceState, ConfirmState, MessageState};
+use super::typing::TypingTimeline;
 
-use dialogue::{Choice, ChoiceKey, Confirm, LineType, Message, Nodes, Text, TypingSpeedFactor};
+use dialogue::{
+    Choice, ChoiceKey, Confirm, LineType, Location, Message, Nodes, Text, Texts, TypingSpeedFactor,
+};
+use language_tags::LanguageTag;
 
 use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub enum View<'a> {
     #[default]
     None,
+    /// The runner waits for a promise returned by an `eval:` or `if:` line to settle.
+    Pending,
     Terminated(u8),
     Message(MessageView<'a>),
     Confirm(ConfirmView<'a>),
@@ -29,6 +40,7 @@ impl<'a> View<'a> {
     pub fn into_owned(self) -> View<'static> {
         match self {
             View::None => View::None,
+            View::Pending => View::Pending,
             View::Terminated(code) => View::Terminated(code),
             View::Message(mv) => View::Message(mv.into_owned()),
             View::Confirm(cv) => View::Confirm(cv.into_owned()),
@@ -38,37 +50,75 @@ impl<'a> View<'a> {
 }
 
 impl View<'_> {
-    pub fn new<'a>(engine: &'a Engine, data: &'a Data, nodes: &'a Nodes) -> View<'a> {
+    pub fn new<'a>(
+        engine: &'a Engine,
+        data: &'a Data,
+        nodes: &'a Nodes,
+    ) -> Result<View<'a>, RunnerError> {
         let state_machine = &data.state_machine;
-        if state_machine.call_stack.is_empty() {
-            View::Terminated(data.exit_code.unwrap_or_default())
-        } else {
-            let line_type = data
-                .state_machine
-                .current_line_type(nodes)
-                .expect("Current line type should exist when call stack is not empty");
-
-            match line_type {
-                LineType::Message(message) => {
-                    View::Message(Self::message_view(engine, data, message, None))
-                }
-                LineType::Confirm(confirm) => {
-                    View::Confirm(Self::confirm_view(engine, data, confirm))
-                }
-                LineType::Choice(choice) => View::Choice(Self::choice_view(engine, data, choice)),
-                _ => todo!("Unimplemented line type"),
-            }
+        if data.pending {
+            return Ok(View::Pending);
+        } else if state_machine.call_stack.is_empty() {
+            return Ok(View::Terminated(data.exit_code.unwrap_or_default()));
         }
+
+        let location = state_machine.location();
+        let line_type = state_machine
+            .current_line_type(nodes)
+            .ok_or_else(|| RunnerError::internal(location, "No line at the current location"))?;
+
+        Ok(match line_type {
+            LineType::Message(message) => {
+                View::Message(Self::message_view(engine, data, message, None)?)
+            }
+            LineType::Confirm(confirm) => View::Confirm(Self::confirm_view(engine, data, confirm)?),
+            LineType::Choice(choice) => View::Choice(Self::choice_view(engine, data, choice)?),
+            _ => return Err(RunnerError::internal(location, "Control lines have no view")),
+        })
     }
 
-    pub fn update<'a>(&mut self, engine: &'a Engine, data: &'a mut Data, nodes: &'a Nodes) -> bool {
-        let view = Self::new(engine, data, nodes);
+    pub fn update<'a>(
+        &mut self,
+        engine: &'a Engine,
+        data: &'a mut Data,
+        nodes: &'a Nodes,
+    ) -> Result<bool, RunnerError> {
+        let view = Self::new(engine, data, nodes)?;
 
         if *self != view {
             *self = view.into_owned();
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
+        }
+    }
+
+    /// The share of the current message revealed so far, while it is typing.
+    pub(crate) fn typing_progress(&self) -> Option<f32> {
+        let message = match self {
+            View::Message(message_view) => message_view,
+            View::Confirm(confirm_view) => &confirm_view.message_view,
+            View::Choice(choice_view) => choice_view.message_view().as_ref()?,
+            _ => return None,
+        };
+        match message.lifecycle() {
+            MessageLifecycle::Typing(visible) => {
+                let total = message.text().graphemes(true).count();
+                Some(*visible as f32 / total as f32)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn message_text(&self) -> Option<&Text> {
+        match self {
+            View::Message(message_view) => Some(message_view.text()),
+            View::Confirm(confirm_view) => Some(confirm_view.text()),
+            View::Choice(choice_view) => choice_view
+                .message_view()
+                .as_ref()
+                .map(|message_view| message_view.text()),
+            _ => None,
         }
     }
 
@@ -77,88 +127,190 @@ impl View<'_> {
         data: &'a Data,
         message: &'a Message,
         message_state: Option<&'a MessageState>,
-    ) -> MessageView<'a> {
+    ) -> Result<MessageView<'a>, RunnerError> {
         let config = engine.config();
+        let language = data.language(config);
         let state_machine = &data.state_machine;
-
-        let actor = data
-            .dialogue_ctx
-            .actor(&message.owner)
-            .expect("actor for the message owner should exist");
-
-        let view_actor = actor.view_actor(&config.language);
-
-        let message_state = message_state.unwrap_or_else(|| data.visiting_state_or_panic::<MessageState>());
-
-        let text = &message_state
-            .texts
-            .get(&config.language)
-            .expect("text for the specified language should exist");
-
-        let line_speed_factor = message
-            .options
-            .as_ref()
-            .and_then(|opts| opts.speed)
-            .unwrap_or_default();
-
-        let visible_chars_count = Self::visible_chars_count(
-            line_speed_factor,
+        let location = state_machine.location();
+
+        let actors = data.dialogue_ctx.actors();
+        let no_actor = || {
+            RunnerError::internal(location, format!("No actor for owner {}", *message.owner))
+        };
+        let index = actors.index_of(&message.owner).ok_or_else(no_actor)?;
+        let actor = actors.get_by_ref(&message.owner).ok_or_else(no_actor)?;
+
+        let message_state = match message_state {
+            Some(message_state) => message_state,
+            None => data.current_state::<MessageState>()?,
+        };
+        let options = &message_state.options;
+
+        let emotion = data.emotions.get(index);
+        let missing_name = || RunnerError::MissingActorName {
+            location: location.clone(),
+            actor: index,
+            language: language.clone(),
+        };
+        let view_actor = actor
+            .view_actor(language, &config.language, emotion)
+            .ok_or_else(missing_name)?;
+        let actor_state = |index: u8| {
+            let emotion = data.emotions.get(index);
+            let actor = actors.get(index as usize)?;
+            Some(ActorState::new(index, emotion, actor.portrait(emotion)))
+        };
+        let speaker = actor_state(index).ok_or_else(no_actor)?;
+        let listeners = (options.listeners.iter().flat_map(|listeners| listeners.iter()))
+            .filter_map(|listener| actor_state(actors.index_of(listener)?))
+            .collect();
+
+        let text = Self::text(&message_state.texts, language, location)?;
+
+        let line_speed_factor = options.speed.unwrap_or_default();
+        let speed_factor = TypingSpeedFactor::from(*line_speed_factor * *actor.typing_speed());
+
+        let timeline = message_state.timeline(|| {
+            TypingTimeline::new(text, language, &config.typing, speed_factor)
+        });
+        let visible_graphemes = Self::visible_graphemes(
+            timeline,
             state_machine.fast_forward.as_ref(),
             message_state,
             &config.typing,
-            text,
-            &config.language,
+            state_machine.view_time(engine.clock().now()),
         );
 
-        let lifecycle = Self::lifecycle(text, visible_chars_count, message_state);
+        let lifecycle = Self::lifecycle(timeline, visible_graphemes, message_state);
+
+        let view = MessageView::new(actor, view_actor, text, &message_state.texts, lifecycle)
+            .with_options(options)
+            .with_states(speaker, listeners)
+            .with_paused(state_machine.is_paused());
+        let secondary = Self::secondary_text(&message_state.texts, language, config);
+        Ok(match secondary {
+            Some(secondary) => {
+                let visible = Self::secondary_visible(
+                    secondary,
+                    view.lifecycle(),
+                    timeline,
+                    config.secondary_reveal,
+                );
+                view.with_secondary(secondary, visible)
+            }
+            None => view,
+        })
+    }
+
+    /// The text in the secondary language, only when the texts are multilingual and that
+    /// language is not the shown one, so a text does not show twice.
+    fn secondary_text<'a>(
+        texts: &'a Texts,
+        language: &LanguageTag,
+        config: &Config,
+    ) -> Option<&'a Text> {
+        let secondary = config.secondary_language.as_ref()?;
+        match texts {
+            Texts::Multilingual(lang_texts) if secondary != language => lang_texts.get(secondary),
+            _ => None,
+        }
+    }
 
-        MessageView::new(actor, view_actor, text, &message_state.texts, lifecycle)
+    /// Byte length of the secondary text shown while the primary one is at `lifecycle`.
+    fn secondary_visible(
+        secondary: &Text,
+        lifecycle: &MessageLifecycle,
+        timeline: &TypingTimeline,
+        reveal: SecondaryReveal,
+    ) -> usize {
+        let MessageLifecycle::Typing(typed) = lifecycle else {
+            return secondary.len();
+        };
+        match reveal {
+            SecondaryReveal::Instant => secondary.len(),
+            SecondaryReveal::Completed => 0,
+            SecondaryReveal::Synced => {
+                let graphemes = secondary.graphemes(true).count();
+                let shown = graphemes * typed / timeline.len().max(1);
+                (secondary.grapheme_indices(true).nth(shown))
+                    .map_or(secondary.len(), |(index, _)| index)
+            }
+        }
+    }
+
+    fn text<'a>(
+        texts: &'a Texts,
+        language: &LanguageTag,
+        location: &Location,
+    ) -> Result<&'a Text, RunnerError> {
+        texts.get(language).ok_or_else(|| RunnerError::MissingText {
+            location: location.clone(),
+            language: language.clone(),
+        })
     }
 
     fn confirm_view<'a>(
         engine: &'a Engine,
         data: &'a Data,
         confirm: &'a Confirm,
-    ) -> ConfirmView<'a> {
-        let config = engine.config();
-        let cs = data.visiting_state_or_panic::<ConfirmState>();
-
-        let responses = cs.response_texts.as_ref().map(|response_texts| {
-            let yes = response_texts
-                .yes
-                .get(&config.language)
-                .expect("yes response text for the specified language should exist");
-            let no = response_texts
-                .no
-                .get(&config.language)
-                .expect("no response text for the specified language should exist");
-            (yes, no)
+    ) -> Result<ConfirmView<'a>, RunnerError> {
+        let language = data.language(engine.config());
+        let location = data.state_machine.location();
+        let cs = data.current_state::<ConfirmState>()?;
+
+        let responses = cs
+            .response_texts
+            .as_ref()
+            .map(|response_texts| {
+                let yes = Self::text(&response_texts.yes, language, location)?;
+                let no = Self::text(&response_texts.no, language, location)?;
+                Ok::<_, RunnerError>((yes, no))
+            })
+            .transpose()?;
+
+        let secondary_responses = cs.response_texts.as_ref().and_then(|response_texts| {
+            let yes = Self::secondary_text(&response_texts.yes, language, engine.config())?;
+            let no = Self::secondary_text(&response_texts.no, language, engine.config())?;
+            Some((yes, no))
         });
 
         let message_view =
-            Self::message_view(engine, data, &confirm.message, Some(&cs.message_state));
+            Self::message_view(engine, data, &confirm.message, Some(&cs.message_state))?;
 
-        ConfirmView::new(
+        Ok(ConfirmView::new(
             cs.confirmed.is_some(),
             responses,
             cs.response_texts.as_ref(),
             message_view,
         )
+        .with_secondary_responses(secondary_responses))
     }
 
-    fn choice_view<'a>(engine: &'a Engine, data: &'a Data, choice: &'a Choice) -> ChoiceView<'a> {
-        let cs = data.visiting_state_or_panic::<ChoiceState>();
+    fn choice_view<'a>(
+        engine: &'a Engine,
+        data: &'a Data,
+        choice: &'a Choice,
+    ) -> Result<ChoiceView<'a>, RunnerError> {
+        let language = data.language(engine.config());
+        let location = data.state_machine.location();
+        let cs = data.current_state::<ChoiceState>()?;
 
         let choices = cs
             .texts
             .iter()
             .map(|(key, texts)| {
-                let text = texts
-                    .get(&engine.config().language)
-                    .expect("text for the specified language should exist");
-                (Cow::Borrowed(key), Cow::Borrowed(text))
+                let text = Self::text(texts, language, location)?;
+                Ok((Cow::Borrowed(key), Cow::Borrowed(text)))
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, RunnerError>>()?;
+        let secondary_choices = cs
+            .texts
+            .iter()
+            .filter_map(|(key, texts)| {
+                let text = Self::secondary_text(texts, language, engine.config())?;
+                Some((Cow::Borrowed(key), Cow::Borrowed(text)))
+            })
+            .collect();
         let default = choice
             .options
             .as_ref()
@@ -172,62 +324,75 @@ impl View<'_> {
         let selected = cs.selected.as_ref().map(|s| Cow::Borrowed(&s.choice_key));
         let started_at = Cow::Borrowed(&cs.visited_at);
 
-        let message = choice.options.as_ref().and_then(|options| {
-            options
-                .message
-                .as_ref()
-                .map(|message| Self::message_view(engine, data, message, cs.message_state.as_ref()))
-        });
+        let message = choice
+            .options
+            .as_ref()
+            .and_then(|options| options.message.as_ref())
+            .map(|message| Self::message_view(engine, data, message, cs.message_state.as_ref()))
+            .transpose()?;
+
+        let clock = ClockHandle::new(engine.clock().clone());
 
-        ChoiceView::new(choices, default, timeout, started_at, selected, message)
+        Ok(
+            ChoiceView::new(choices, default, timeout, started_at, selected, message, clock)
+                .with_secondary_choices(secondary_choices)
+                .with_pause(cs.total_paused, data.state_machine.paused),
+        )
     }
 
-    fn lifecycle(text: &Text, visible_chars_count: usize, message_state: &MessageState) -> MessageLifecycle {
-        match text.char_indices().nth(visible_chars_count) {
-            Some(_) => MessageLifecycle::Typing(visible_chars_count),
-            None if !message_state.is_completed() => MessageLifecycle::Finished,
-            None if message_state.is_skipped() => MessageLifecycle::Completed(message_state.skipped_at.unwrap()),
-            None => MessageLifecycle::Completed(message_state.completed_at.unwrap()),
+    fn lifecycle(
+        timeline: &TypingTimeline,
+        visible_graphemes: usize,
+        message_state: &MessageState,
+    ) -> MessageLifecycle {
+        if visible_graphemes < timeline.len() {
+            return MessageLifecycle::Typing(visible_graphemes);
+        }
+        match message_state.skipped_at.or(message_state.completed_at) {
+            None => MessageLifecycle::Finished,
+            Some(completed_at) => MessageLifecycle::Completed(completed_at),
         }
     }
 
-    fn visible_chars_count(
-        line_speed_factor: TypingSpeedFactor,
+    fn visible_graphemes(
+        timeline: &TypingTimeline,
         fast_forward: Option<&std::time::Instant>,
         message_state: &MessageState,
         typing: &super::engine::config::TypingConfig,
-        text: &Text,
-        language_tag: &language_tags::LanguageTag,
+        now: std::time::Instant,
     ) -> usize {
-        use std::ops::{Add, AddAssign, Mul};
-
         if message_state.is_skipped() || message_state.is_completed() {
             return usize::MAX;
         }
 
+        let elapsed = Self::typing_elapsed(fast_forward, message_state, typing, now);
+        timeline.visible_graphemes(elapsed)
+    }
+
+    /// How long the message has been typing, counting fast forward and the start delay but
+    /// not the time spent paused.
+    pub(crate) fn typing_elapsed(
+        fast_forward: Option<&std::time::Instant>,
+        message_state: &MessageState,
+        typing: &super::engine::config::TypingConfig,
+        now: std::time::Instant,
+    ) -> std::time::Duration {
+        use std::ops::{Add, AddAssign};
+
         let mut total_fast_forward = message_state.total_fast_forward;
 
         if let Some(start) = fast_forward {
-            total_fast_forward.add_assign(start.elapsed());
+            total_fast_forward.add_assign(now.saturating_duration_since(*start));
         }
 
-        let effective_elapsed = message_state
-            .visited_at
-            .elapsed()
+        now.saturating_duration_since(message_state.visited_at)
             .add(total_fast_forward.mul_f32(*typing.fast_forward_factor))
             .saturating_sub(
                 (!message_state.initial_fast_forward)
                     .then(|| typing.start_delay)
                     .unwrap_or_default(),
-            );
-
-        effective_elapsed
-            .mul_f32(
-                typing
-                    .effective_speed(text, language_tag)
-                    .mul(*line_speed_factor),
             )
-            .as_secs() as usize
+            .saturating_sub(message_state.total_paused)
     }
 }
 
@@ -336,6 +501,16 @@ impl View<'static> {
         matches!(self, View::Choice(_))
     }
 
+    /// Whether the runner is paused, with typing and choice timeouts frozen.
+    pub fn is_paused(&self) -> bool {
+        match self {
+            View::Message(message_view) => message_view.is_paused(),
+            View::Confirm(confirm_view) => confirm_view.message_view.is_paused(),
+            View::Choice(choice_view) => choice_view.is_paused(),
+            _ => false,
+        }
+    }
+
     pub fn is_terminated(&self) -> bool {
         matches!(self, View::Terminated(_))
     }
@@ -356,3 +531,205 @@ impl View<'static> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_set_language() {
+        use crate::{Action, Config, Data, Dialogue, Engine, ManualClock, Runner, Store};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Dialogue = r#"
+nodes:
+  main:
+  - message:
+      en: abcdefghij
+      ja: あいうえ
+      ko: 가나다라
+  - message: ${engine.language}
+"#
+        .parse()
+        .unwrap();
+
+        let mut config = Config::default();
+        config.typing.auto_adjust = false;
+        config.typing.start_delay = Duration::ZERO;
+        config
+            .typing
+            .language_speeds
+            .insert("en".parse().unwrap(), 10.0.into());
+        config
+            .typing
+            .language_speeds
+            .insert("ja".parse().unwrap(), 10.0.into());
+        let clock = Arc::new(ManualClock::with_unix_time(Duration::from_secs(1000)));
+        let engine = Engine::builder()
+            .config(config)
+            .clock(clock.clone())
+            .build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        clock.advance(Duration::from_millis(500));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("abcde"));
+
+        assert!(
+            runner
+                .dispatch(Action::SetLanguage("ja".parse().unwrap()))
+                .unwrap()
+        );
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("あい"));
+        clock.advance(Duration::from_millis(100));
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("あいう"));
+
+        assert!(
+            !runner
+                .dispatch(Action::SetLanguage("fr".parse().unwrap()))
+                .unwrap()
+        );
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("あいう"));
+        assert!(
+            runner
+                .dispatch(Action::SetLanguage("ko".parse().unwrap()))
+                .unwrap()
+        );
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("가나다"));
+        assert_eq!(
+            runner.view().actor().unwrap().name(),
+            "System",
+            "the system actor has no Korean name, so it is named in the config language"
+        );
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        assert_eq!(runner.view().message(), Some("ko"));
+    }
+
+    #[test]
+    fn test_bilingual_subtitles() {
+        use crate::SecondaryReveal;
+        use crate::{Action, Config, Data, Dialogue, Engine, ManualClock, Runner, Store, View};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dialogue: Arc<Dialogue> = r#"
+nodes:
+  main:
+  - message:
+      ja: あいうえ
+      en: abcdefgh
+  - message: Mono
+  - confirm:
+      ja: いい？
+      en: OK?
+    options:
+      response:
+        yes:
+          ja: はい
+          en: "Yes"
+        no:
+          ja: いいえ
+          en: "No"
+  - choice:
+      a:
+        ja: あ
+        en: A
+      b: B
+"#
+        .parse()
+        .map(Arc::new)
+        .unwrap();
+
+        let run = |reveal: SecondaryReveal| {
+            let mut config = Config {
+                language: "ja".parse().unwrap(),
+                secondary_language: Some("en".parse().unwrap()),
+                secondary_reveal: reveal,
+                ..Config::default()
+            };
+            config.typing.auto_adjust = false;
+            config.typing.start_delay = Duration::ZERO;
+            config
+                .typing
+                .language_speeds
+                .insert("ja".parse().unwrap(), 10.0.into());
+            let clock = Arc::new(ManualClock::with_unix_time(Duration::from_secs(1000)));
+            let engine = Engine::builder()
+                .config(config)
+                .clock(clock.clone())
+                .build();
+            let store = Store::new(&engine, Data::default());
+            let mut runner = Runner::instantiate(&store, dialogue.clone()).unwrap();
+
+            clock.advance(Duration::from_millis(200));
+            runner.update_view().unwrap();
+            let message = runner.view().as_message().unwrap();
+            assert_eq!(message.text().as_str(), "あいうえ");
+            assert_eq!(
+                message.secondary_text().map(|t| t.as_str()),
+                Some("abcdefgh")
+            );
+            let typing = message.secondary_str().map(str::to_owned);
+
+            runner.dispatch(Action::Skip).unwrap();
+            runner.update_view().unwrap();
+            let message = runner.view().as_message().unwrap();
+            assert_eq!(message.secondary_str(), Some("abcdefgh"));
+            typing
+        };
+        assert_eq!(run(SecondaryReveal::Instant).as_deref(), Some("abcdefgh"));
+        assert_eq!(run(SecondaryReveal::Synced).as_deref(), Some("abcd"));
+        assert_eq!(run(SecondaryReveal::Completed).as_deref(), Some(""));
+
+        let config = Config {
+            language: "ja".parse().unwrap(),
+            secondary_language: Some("en".parse().unwrap()),
+            ..Config::default()
+        };
+        let engine = Engine::builder().config(config).build();
+        let store = &Store::new(&engine, Data::default());
+        let mut runner = Runner::instantiate(store, dialogue.clone()).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.update_view().unwrap();
+        let message = runner.view().as_message().unwrap();
+        assert_eq!(message.secondary_str(), None);
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.dispatch(Action::Skip).unwrap();
+        runner.update_view().unwrap();
+        let View::Confirm(confirm) = runner.view() else {
+            panic!("expected a confirm view");
+        };
+        assert_eq!(confirm.message_view.secondary_str(), Some("OK?"));
+        assert_eq!(confirm.secondary_responses(), Some(("Yes", "No")));
+
+        runner.dispatch(Action::Confirm(true)).unwrap();
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        let secondary = choice
+            .secondary_choices()
+            .iter()
+            .map(|(key, text)| (key.as_str(), text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(secondary, vec![("a", "A")]);
+
+        let english = "en".parse().unwrap();
+        assert!(runner.dispatch(Action::SetLanguage(english)).unwrap());
+        runner.update_view().unwrap();
+        let choice = runner.view().as_choice().unwrap();
+        assert!(
+            choice.secondary_choices().is_empty(),
+            "the secondary language is now the shown one"
+        );
+    }
+}
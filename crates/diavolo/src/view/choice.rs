@@ -1,5 +1,6 @@
 use dialogue::{ChoiceKey, Text, Timeout};
 
+use super::super::clock::ClockHandle;
 use super::message::MessageView;
 
 use std::borrow::Cow;
@@ -13,16 +14,21 @@ pub struct ChoiceView<'a> {
     started_at: Cow<'a, Instant>,
     selected: Option<Cow<'a, ChoiceKey>>,
     message_view: Option<MessageView<'a>>,
+    secondary_choices: Vec<(Cow<'a, ChoiceKey>, Cow<'a, Text>)>,
+    total_paused: Duration,
+    paused_at: Option<Instant>,
+    clock: ClockHandle,
 }
 
 impl<'a> ChoiceView<'a> {
-    pub fn new(
+    pub(crate) fn new(
         choices: Vec<(Cow<'a, ChoiceKey>, Cow<'a, Text>)>,
         default: Option<Cow<'a, ChoiceKey>>,
         timeout: Option<Cow<'a, Timeout>>,
         started_at: Cow<'a, Instant>,
         selected: Option<Cow<'a, ChoiceKey>>,
         message_view: Option<MessageView<'a>>,
+        clock: ClockHandle,
     ) -> Self {
         Self {
             choices,
@@ -31,6 +37,29 @@ impl<'a> ChoiceView<'a> {
             started_at,
             selected,
             message_view,
+            secondary_choices: Vec::new(),
+            total_paused: Duration::ZERO,
+            paused_at: None,
+            clock,
+        }
+    }
+
+    /// Stops the timeout for the time spent paused, and at `paused_at` while paused.
+    pub(crate) fn with_pause(self, total_paused: Duration, paused_at: Option<Instant>) -> Self {
+        Self {
+            total_paused,
+            paused_at,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_secondary_choices(
+        self,
+        secondary_choices: Vec<(Cow<'a, ChoiceKey>, Cow<'a, Text>)>,
+    ) -> Self {
+        Self {
+            secondary_choices,
+            ..self
         }
     }
 
@@ -45,6 +74,11 @@ impl<'a> ChoiceView<'a> {
         let selected = self.selected.map(|k| Cow::Owned(k.into_owned()));
         let started_at = Cow::Owned(self.started_at.into_owned());
         let message_view = self.message_view.map(|mv| mv.into_owned());
+        let secondary_choices = self
+            .secondary_choices
+            .into_iter()
+            .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned())))
+            .collect();
 
         ChoiceView::new(
             choices,
@@ -53,7 +87,10 @@ impl<'a> ChoiceView<'a> {
             started_at,
             selected,
             message_view,
+            self.clock,
         )
+        .with_secondary_choices(secondary_choices)
+        .with_pause(self.total_paused, self.paused_at)
     }
 }
 
@@ -62,6 +99,11 @@ impl ChoiceView<'_> {
         &self.choices
     }
 
+    /// The choices which have a text in the secondary language, in that language.
+    pub fn secondary_choices(&self) -> &[(Cow<'_, ChoiceKey>, Cow<'_, Text>)] {
+        &self.secondary_choices
+    }
+
     pub fn choices_available(&self) -> Option<&Vec<(Cow<'_, ChoiceKey>, Cow<'_, Text>)>> {
         match &self.message_view {
             None => Some(&self.choices),
@@ -103,6 +145,10 @@ impl ChoiceView<'_> {
         &self.message_view
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
     pub fn has_timeout(&self) -> bool {
         self.timeout.is_some()
     }
@@ -115,13 +161,14 @@ impl ChoiceView<'_> {
         self.timeout
             .as_ref()
             .and_then(|timeout| {
-                let elapsed = self
+                let since = self
                     .message_view
                     .as_ref()
                     .and_then(|m| m.completed_at())
-                    .unwrap_or(*self.started_at)
-                    .elapsed();
-                timeout.checked_sub(elapsed)
+                    .unwrap_or(*self.started_at);
+                let now = self.paused_at.unwrap_or_else(|| self.clock.now());
+                let elapsed = now.saturating_duration_since(since);
+                timeout.checked_sub(elapsed.saturating_sub(self.total_paused))
             })
             .unwrap_or(Duration::MAX)
     }
@@ -9,6 +9,7 @@ pub struct ConfirmView<'a> {
     pub(crate) confirmed: bool,
     pub(crate) responses: Option<(Cow<'a, Text>, Cow<'a, Text>)>,
     pub(crate) response_texts: Option<Cow<'a, ConfirmResponse>>,
+    pub(crate) secondary_responses: Option<(Cow<'a, Text>, Cow<'a, Text>)>,
     pub(crate) message_view: MessageView<'a>,
 }
 
@@ -31,10 +32,18 @@ impl<'a> ConfirmView<'a> {
             confirmed,
             responses: responses.map(|(yes, no)| (Cow::Borrowed(yes), Cow::Borrowed(no))),
             response_texts: response_texts.map(Cow::Borrowed),
+            secondary_responses: None,
             message_view,
         }
     }
 
+    pub(crate) fn with_secondary_responses(self, responses: Option<(&'a Text, &'a Text)>) -> Self {
+        Self {
+            secondary_responses: responses.map(|(yes, no)| (Cow::Borrowed(yes), Cow::Borrowed(no))),
+            ..self
+        }
+    }
+
     pub fn into_owned(self) -> ConfirmView<'static> {
         ConfirmView {
             confirmed: self.confirmed,
@@ -42,6 +51,9 @@ impl<'a> ConfirmView<'a> {
                 .responses
                 .map(|(yes, no)| (Cow::Owned(yes.into_owned()), Cow::Owned(no.into_owned()))),
             response_texts: self.response_texts.map(|rt| Cow::Owned(rt.into_owned())),
+            secondary_responses: self
+                .secondary_responses
+                .map(|(yes, no)| (Cow::Owned(yes.into_owned()), Cow::Owned(no.into_owned()))),
             message_view: self.message_view.into_owned(),
         }
     }
@@ -52,6 +64,13 @@ impl<'a> ConfirmView<'a> {
             .map(|(yes, no)| (yes.as_str(), no.as_str()))
     }
 
+    /// The responses in the secondary language, if both have a text in it.
+    pub fn secondary_responses(&'a self) -> Option<(&'a str, &'a str)> {
+        self.secondary_responses
+            .as_ref()
+            .map(|(yes, no)| (yes.as_str(), no.as_str()))
+    }
+
     pub fn is_available(&self) -> bool {
         !self.confirmed
             && matches!(
@@ -1,8 +1,10 @@
 use crate::dialogue_ctx::{Actor, ViewActor};
-use dialogue::{Text, Texts};
+use crate::emotions::ActorState;
+use dialogue::{FontProperties, MessageOptions, Text, Texts, TypingSpeedFactor};
 
 use std::borrow::Cow;
 use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct MessageView<'a> {
@@ -11,6 +13,13 @@ pub struct MessageView<'a> {
     text: Cow<'a, Text>,
     texts: Cow<'a, Texts>,
     lifecycle: MessageLifecycle,
+    options: Cow<'a, MessageOptions>,
+    secondary: Option<Cow<'a, Text>>,
+    /// Byte length of the secondary text shown so far.
+    secondary_visible: usize,
+    speaker: Option<ActorState>,
+    listeners: Vec<ActorState>,
+    paused: bool,
 }
 
 impl<'a> MessageView<'a> {
@@ -27,6 +36,39 @@ impl<'a> MessageView<'a> {
             text: Cow::Borrowed(text),
             texts: Cow::Borrowed(texts),
             lifecycle,
+            options: Cow::Owned(MessageOptions::default()),
+            secondary: None,
+            secondary_visible: 0,
+            speaker: None,
+            listeners: Vec::new(),
+            paused: false,
+        }
+    }
+
+    pub(crate) fn with_options(self, options: &'a MessageOptions) -> Self {
+        Self {
+            options: Cow::Borrowed(options),
+            ..self
+        }
+    }
+
+    pub(crate) fn with_secondary(self, text: &'a Text, visible: usize) -> Self {
+        Self {
+            secondary: Some(Cow::Borrowed(text)),
+            secondary_visible: visible,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_paused(self, paused: bool) -> Self {
+        Self { paused, ..self }
+    }
+
+    pub(crate) fn with_states(self, speaker: ActorState, listeners: Vec<ActorState>) -> Self {
+        Self {
+            speaker: Some(speaker),
+            listeners,
+            ..self
         }
     }
 
@@ -37,6 +79,12 @@ impl<'a> MessageView<'a> {
             text: Cow::Owned(self.text.into_owned()),
             texts: Cow::Owned(self.texts.into_owned()),
             lifecycle: self.lifecycle,
+            options: Cow::Owned(self.options.into_owned()),
+            secondary: self.secondary.map(|text| Cow::Owned(text.into_owned())),
+            secondary_visible: self.secondary_visible,
+            speaker: self.speaker,
+            listeners: self.listeners,
+            paused: self.paused,
         }
     }
 
@@ -48,18 +96,49 @@ impl<'a> MessageView<'a> {
         &self.view_actor
     }
 
+    /// The options of the message, with their templates evaluated.
+    pub fn options(&self) -> &MessageOptions {
+        &self.options
+    }
+
+    /// The emotion the message sets on its actor, if any.
+    pub fn emotion(&self) -> Option<&str> {
+        self.options.emotion.as_deref()
+    }
+
+    /// The speed factor of the message, not including the one of its actor.
+    pub fn speed(&self) -> TypingSpeedFactor {
+        self.options.speed.unwrap_or_default()
+    }
+
+    pub fn font(&self) -> Option<&FontProperties> {
+        self.options.font.as_ref()
+    }
+
+    /// Indexes of the actors the message is addressed to.
+    pub fn listeners(&self) -> impl Iterator<Item = u8> + '_ {
+        self.listeners.iter().map(ActorState::index)
+    }
+
+    /// Emotion and portrait of the actor speaking.
+    pub fn speaker_state(&self) -> Option<&ActorState> {
+        self.speaker.as_ref()
+    }
+
+    /// Emotions and portraits of the listeners of the message.
+    pub fn listener_states(&self) -> &[ActorState] {
+        &self.listeners
+    }
+
     pub fn visible_str(&self) -> &str {
         match self.lifecycle {
-            MessageLifecycle::Typing(visible_chars_count) => {
-                let text = &self.text;
-                text.char_indices()
-                    .nth(visible_chars_count)
-                    .map(|(idx, _)| &text[..idx])
-                    .expect(
-                        "visible chars count should be less than or equal to the total chars count",
-                    )
+            MessageLifecycle::Typing(visible_graphemes) => {
+                let text = self.text.as_str();
+                (text.grapheme_indices(true))
+                    .nth(visible_graphemes)
+                    .map_or(text, |(idx, _)| &text[..idx])
             }
-            MessageLifecycle::Finished | MessageLifecycle::Completed(_) => &self.text.as_str(),
+            MessageLifecycle::Finished | MessageLifecycle::Completed(_) => self.text.as_str(),
         }
     }
 
@@ -67,6 +146,23 @@ impl<'a> MessageView<'a> {
         &self.text
     }
 
+    /// The whole text in the secondary language, if the message has one.
+    pub fn secondary_text(&self) -> Option<&Text> {
+        self.secondary.as_deref()
+    }
+
+    /// The part of the secondary text its reveal policy shows so far, which is empty while
+    /// hidden.
+    pub fn secondary_str(&self) -> Option<&str> {
+        let text = self.secondary.as_deref()?;
+        Some(&text[..self.secondary_visible])
+    }
+
+    /// Whether the runner is paused, with typing frozen where it was.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     pub fn lifecycle(&self) -> &MessageLifecycle {
         &self.lifecycle
     }
@@ -89,7 +185,90 @@ impl<'a> MessageView<'a> {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum MessageLifecycle {
+    /// The number of grapheme clusters revealed so far.
     Typing(usize),
     Finished,
     Completed(Instant),
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Data, Dialogue, DialogueCtx, Engine, Runner, Store, View};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_message_options() {
+        let dialogue: Dialogue = r#"
+actor:
+  roles: [hero, merchant]
+nodes:
+  main:
+  - eval: mood = "angry"
+  - owner: merchant
+    message: Welcome!
+    options:
+      emotion: ${mood}
+      speed: 2.0
+      font:
+        weight: 700
+        color: "${mood == 'angry' ? 'red' : 'black'}"
+      listeners: [hero]
+  - confirm: Deal?
+    owner: merchant
+    options:
+      message:
+        font:
+          size: 12.5
+  - choice:
+      yes: Yes
+    options:
+      message:
+        texts: Sure?
+        owner: hero
+        options:
+          listeners: [1]
+"#
+        .parse()
+        .unwrap();
+
+        let actors =
+            serde_json::json!({ "hero": { "name": "Alice" }, "merchant": { "name": "Bob" } });
+        let dialogue_ctx = DialogueCtx::builder().actors(actors).unwrap().build();
+        let engine = Engine::default();
+        let store = &Store::new(&engine, Data::with_ctx(dialogue_ctx));
+        let mut runner = Runner::instantiate(store, Arc::new(dialogue)).unwrap();
+
+        runner.update_view().unwrap();
+        let View::Message(message) = runner.view() else {
+            panic!("expected a message view");
+        };
+        assert_eq!(message.emotion(), Some("angry"));
+        assert_eq!(*message.speed(), 2.0);
+        let font = message.font().unwrap();
+        assert_eq!(
+            (font.weight, font.color.as_deref()),
+            (Some(700), Some("red"))
+        );
+        assert_eq!(message.listeners().collect::<Vec<_>>(), [0]);
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Advance).unwrap();
+        runner.update_view().unwrap();
+        let View::Confirm(confirm) = runner.view() else {
+            panic!("expected a confirm view");
+        };
+        assert_eq!(confirm.font().and_then(|font| font.size), Some(12.5));
+        assert_eq!((confirm.emotion(), *confirm.speed()), (None, 1.0));
+
+        runner.dispatch(Action::Skip).unwrap();
+        runner.dispatch(Action::Confirm(true)).unwrap();
+        runner.update_view().unwrap();
+        let View::Choice(choice) = runner.view() else {
+            panic!("expected a choice view");
+        };
+        let prompt = choice.message_view().as_ref().unwrap();
+        assert_eq!(prompt.listeners().collect::<Vec<_>>(), [1]);
+        assert!(prompt.font().is_none());
+    }
+}
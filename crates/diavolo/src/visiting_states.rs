@@ -1,14 +1,16 @@
+use super::error::RunnerError;
 use super::line_state::choice::Selected;
 use super::line_state::*;
 use super::runner::EvaluatedLine;
+use super::script_value::ScriptValue;
 use dialogue::{LineId, LineType, Location, NodeKey};
 
-use boa_engine::JsValue;
 use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::time::Instant;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct VisitingStates(HashMap<NodeKey, Node>);
 
 impl VisitingStates {
@@ -32,55 +34,64 @@ impl VisitingStates {
         location: &Location,
         initial_fast_forward: bool,
         evaluated_line: EvaluatedLine,
-    ) {
+        now: Instant,
+    ) -> Result<(), RunnerError> {
         let visiting_counting = self
             .get_mut(&location.node_key)
-            .expect("Node must be initialized before visiting lines")
-            .get_index_mut(*location.line_position)
+            .and_then(|node| node.get_index_mut(*location.line_position))
             .map(|(_, vc)| vc)
-            .expect("Line must be initialized before visiting");
+            .ok_or_else(|| RunnerError::internal(location, "Line visited before initialized"))?;
 
-        match visiting_counting {
-            VisitingCounting::Message(states) => {
-                states.push(MessageState::new(
-                    initial_fast_forward,
-                    evaluated_line.into_message_or_panic(),
-                ));
+        match (visiting_counting, evaluated_line) {
+            (VisitingCounting::Message(states), EvaluatedLine::Message(texts, options)) => {
+                states.push(MessageState::new(now, initial_fast_forward, texts, options));
             }
-            VisitingCounting::Confirm(states) => {
-                let (texts, response_texts) = evaluated_line.into_confirm_or_panic();
+            (
+                VisitingCounting::Confirm(states),
+                EvaluatedLine::Confirm(texts, options, response_texts),
+            ) => {
                 states.push(ConfirmState::new(
+                    now,
                     initial_fast_forward,
                     texts,
+                    options,
                     response_texts,
                 ));
             }
-            VisitingCounting::Choice(states) => {
-                let (choice_texts, texts) = evaluated_line.into_choice_or_panic();
-                let state = match texts {
-                    Some(texts) => {
-                        ChoiceState::new_with_message(choice_texts, initial_fast_forward, texts)
-                    }
-                    None => ChoiceState::new(choice_texts),
+            (VisitingCounting::Choice(states), EvaluatedLine::Choice(choice_texts, message)) => {
+                let state = match message {
+                    Some((texts, options)) => ChoiceState::new_with_message(
+                        now,
+                        choice_texts,
+                        initial_fast_forward,
+                        texts,
+                        options,
+                    ),
+                    None => ChoiceState::new(now, choice_texts),
                 };
                 states.push(state);
             }
-            VisitingCounting::Eval(states) => {
-                states.push(EvalState::new(evaluated_line.into_eval_or_panic()));
+            (VisitingCounting::Eval(states), EvaluatedLine::Eval) => {
+                states.push(EvalState::new(now));
             }
-            VisitingCounting::Goto(states) => {
-                states.push(GotoState::new(evaluated_line.into_goto_or_panic()));
+            (VisitingCounting::Goto(states), EvaluatedLine::Goto(line_id_or_index)) => {
+                states.push(GotoState::new(now, line_id_or_index));
             }
-            VisitingCounting::Call(states) => {
-                states.push(CallState::new(evaluated_line.into_call_or_panic()));
+            (VisitingCounting::Call(states), EvaluatedLine::Call(node_key)) => {
+                states.push(CallState::new(now, node_key));
             }
-            VisitingCounting::Return(states) => {
-                states.push(ReturnState::new(evaluated_line.into_return_or_panic()))
+            (VisitingCounting::Return(states), EvaluatedLine::Return(value)) => {
+                states.push(ReturnState::new(now, value))
             }
-            VisitingCounting::Exit => {
+            (VisitingCounting::Exit, _) => {
                 tracing::warn!("Visiting an Exit line does not require visiting state");
             }
+            _ => {
+                let message = "Evaluated line does not match the line type";
+                return Err(RunnerError::internal(location, message));
+            }
         };
+        Ok(())
     }
 
     pub fn lines(&self, node_key: &NodeKey) -> Option<&Lines> {
@@ -144,7 +155,7 @@ impl DerefMut for VisitingStates {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Node(Lines);
 
 impl Deref for Node {
@@ -161,7 +172,7 @@ impl DerefMut for Node {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Lines(IndexMap<LineId, VisitingCounting>);
 
 impl Deref for Lines {
@@ -178,7 +189,7 @@ impl DerefMut for Lines {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum VisitingCounting {
     Message(LineStates<MessageState>),
     Choice(LineStates<ChoiceState>),
@@ -233,7 +244,7 @@ impl From<&LineType> for VisitingCounting {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LineStates<T: LineState>(Vec<T>);
 
 impl<T: LineState> Default for LineStates<T> {
@@ -277,7 +288,7 @@ impl ChoiceStates {
 }
 
 impl CallStates {
-    pub fn returned_value(&self) -> Option<&JsValue> {
+    pub fn returned_value(&self) -> Option<&ScriptValue> {
         self.last().and_then(|s| s.returned_value.as_ref())
     }
 }
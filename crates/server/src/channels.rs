@@ -8,4 +8,4 @@ pub use runner_operation::*;
 pub use runner_state_info::*;
 pub use view_ch::*;
 
-const CHANNEL_BUFFER_SIZE: usize = 100;
\ No newline at end of file
+pub(crate) const CHANNEL_BUFFER_SIZE: usize = 100;
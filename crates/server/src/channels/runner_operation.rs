@@ -1,18 +1,25 @@
+use super::super::runtimes::RuntimePath;
 use super::CHANNEL_BUFFER_SIZE;
 use std::sync::OnceLock;
 use tokio::sync::mpsc::{self, Receiver, Sender, error::SendError};
 
-static RUNNER_OP_TX: OnceLock<Sender<RunnerOperation>> = OnceLock::new();
+/// An operation on the dialogue running for a runtime.
+pub type RoutedOperation = (RuntimePath, RunnerOperation);
 
-pub fn runner_op_rx() -> Receiver<RunnerOperation> {
-    let (tx, rx) = mpsc::channel::<RunnerOperation>(CHANNEL_BUFFER_SIZE);
+static RUNNER_OP_TX: OnceLock<Sender<RoutedOperation>> = OnceLock::new();
+
+pub fn runner_op_rx() -> Receiver<RoutedOperation> {
+    let (tx, rx) = mpsc::channel::<RoutedOperation>(CHANNEL_BUFFER_SIZE);
     RUNNER_OP_TX.set(tx).unwrap();
     rx
 }
 
-pub async fn send_op(op: RunnerOperation) -> Result<(), SendError<RunnerOperation>> {
+pub async fn send_op(
+    runtime_path: RuntimePath,
+    op: RunnerOperation,
+) -> Result<(), SendError<RoutedOperation>> {
     let tx = RUNNER_OP_TX.get().expect("RUNNER_OP_TX is not initialized");
-    tx.send(op).await
+    tx.send((runtime_path, op)).await
 }
 
 #[derive(Debug)]
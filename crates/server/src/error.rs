@@ -17,6 +17,8 @@ pub enum Error {
     SerdeJsonError(#[from] serde_json::Error),
     #[error("Dialogue error: {0}")]
     DialogueError(#[from] diavolo::dialogue::Error),
+    #[error("Runner error: {0}")]
+    RunnerError(#[from] diavolo::RunnerError),
 
     #[error("Server error: {0}")]
     ServerError(#[from] ServerError),
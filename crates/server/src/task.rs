@@ -1,23 +1,66 @@
 use super::channels::{self, DialogueQueueParams, RunnerOperation};
 use super::error::{self, ServerError};
-use super::runtimes::Runtimes;
+use super::runtimes::{RuntimePath, Runtimes};
 
-use diavolo::{Dialogue, DialogueCtx, Runner, Store};
+use diavolo::{Data, DialogueCtx, Runner};
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::mpsc;
-use tokio::task::LocalSet;
 
+/// The dialogues and runner operations of one runtime, processed by a task of its own.
+struct Queue {
+    dialogues: mpsc::Sender<DialogueQueueParams>,
+    operations: mpsc::Sender<RunnerOperation>,
+}
+
+/// Runs the dialogues of each runtime one after another, and those of different runtimes
+/// concurrently.
 pub async fn run_task(runtimes: Runtimes) -> error::Result<()> {
-    let local = LocalSet::new();
+    tracing::debug!("Task started");
+
+    let runtimes = Arc::new(runtimes);
+    let mut dq_rx = channels::dialogue_queue_rx();
+    let mut ro_rx = channels::runner_op_rx();
+    let mut queues = HashMap::<RuntimePath, Queue>::new();
+
+    loop {
+        tokio::select! {
+            params = dq_rx.recv() => {
+                let Some(params) = params else { break };
+                tracing::debug!("Received a dialogue queue params");
+                let runtime_path = params.runtime_path.clone();
+                let queue = queues
+                    .entry(runtime_path.clone())
+                    .or_insert_with(|| spawn_queue(runtimes.clone(), runtime_path));
+                if queue.dialogues.send(params).await.is_err() {
+                    tracing::error!("Dialogue queue task has stopped");
+                }
+            }
+            op = ro_rx.recv() => {
+                let Some((runtime_path, op)) = op else { break };
+                match queues.get(&runtime_path) {
+                    Some(queue) => {
+                        if queue.operations.send(op).await.is_err() {
+                            tracing::error!("Dialogue queue task has stopped");
+                        }
+                    }
+                    None => tracing::warn!("No dialogue queue for {:?}", runtime_path),
+                }
+            }
+        }
+    }
 
-    local.spawn_local(async move {
-        tracing::debug!("Task started");
+    Ok(())
+}
 
-        let mut dq_rx = channels::dialogue_queue_rx();
-        let mut ro_rx = channels::runner_op_rx();
+fn spawn_queue(runtimes: Arc<Runtimes>, runtime_path: RuntimePath) -> Queue {
+    let (dialogues, mut dialogues_rx) = mpsc::channel(channels::CHANNEL_BUFFER_SIZE);
+    let (operations, mut ro_rx) = mpsc::channel(channels::CHANNEL_BUFFER_SIZE);
 
-        while let Some(params) = dq_rx.recv().await {
-            tracing::debug!("Received a dialogue queue params");
+    tokio::spawn(async move {
+        tracing::debug!("Dialogue queue started for {:?}", runtime_path);
+        while let Some(params) = dialogues_rx.recv().await {
             if let Err(e) = process_queue(&mut ro_rx, &runtimes, params).await {
                 tracing::error!("Error processing dialogue queue: {}", e);
             } else {
@@ -26,15 +69,10 @@ pub async fn run_task(runtimes: Runtimes) -> error::Result<()> {
         }
     });
 
-    local
-        .run_until(async {
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            }
-        })
-        .await;
-
-    Ok(())
+    Queue {
+        dialogues,
+        operations,
+    }
 }
 
 async fn process_queue(
@@ -52,14 +90,13 @@ async fn process_queue(
     let runtime = runtimes
         .get(&runtime_path)
         .ok_or(ServerError::RuntimeNotFound)?;
-    let engine = &runtime.engine;
     let dialogue_ctx = DialogueCtx::builder()
         .system_actor(true)
         .actors(actors)?
         .args(args)
         .build();
-    let mut store = Store::new(engine, diavolo::Data::with_ctx(dialogue_ctx));
-    let mut runner = Runner::instantiate(&mut store, &dialogue)?;
+    let data = Data::with_ctx(dialogue_ctx);
+    let mut runner = Runner::new(runtime.engine.clone(), Arc::new(dialogue), data)?;
 
     loop {
         if runner.is_terminated() {
@@ -67,7 +104,18 @@ async fn process_queue(
             break Ok(());
         }
 
-        let Some(op) = rx.recv().await else {
+        let pending = runner.is_pending();
+        let op = tokio::select! {
+            op = rx.recv() => op,
+            result = runner.settle(), if pending => {
+                result?;
+                if let Some(view) = runner.update_view()? {
+                    channels::send_view(view.clone()).await?;
+                }
+                continue;
+            }
+        };
+        let Some(op) = op else {
             tracing::warn!("Runner operation channel closed");
             break Ok(());
         };
@@ -76,13 +124,13 @@ async fn process_queue(
 
         match op {
             RunnerOperation::UpdateView => {
-                if let Some(view) = runner.update_view() {
+                if let Some(view) = runner.update_view()? {
                     tracing::debug!("Updated view: {:?}", view);
                     channels::send_view(view.clone()).await?;
                 };
             }
             RunnerOperation::Dispatch(action) => {
-                runner.dispatch(action);
+                runner.dispatch(action)?;
             }
         }
     }
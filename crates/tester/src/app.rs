@@ -9,8 +9,8 @@ use color_eyre::eyre::Result;
 use crossterm::event::KeyCode;
 use ratatui::Frame;
 
-pub struct App<'engine, 'dialogue> {
-    runner: diavolo::Runner<'engine, 'dialogue>,
+pub struct App {
+    runner: diavolo::Runner,
     log_collector: LogCollector,
     prev_log_count: usize,
     log_auto_scroll: bool,
@@ -19,7 +19,7 @@ pub struct App<'engine, 'dialogue> {
     should_quit: bool,
 }
 
-impl App<'_, '_> {
+impl App {
     fn update_log_scroll(&mut self) {
         let log_count = self.log_collector.len();
         if log_count > 0 {
@@ -28,11 +28,8 @@ impl App<'_, '_> {
     }
 }
 
-impl<'engine, 'dialogue> App<'engine, 'dialogue>
-where
-    'engine: 'dialogue,
-{
-    pub fn new(runner: diavolo::Runner<'engine, 'dialogue>) -> Self {
+impl App {
+    pub fn new(runner: diavolo::Runner) -> Self {
         Self {
             runner,
 
@@ -59,9 +56,16 @@ where
                 None
             }
             Action::CheckView => {
-                if let Some(view) = self.runner.update_view() {
-                    tracing::trace!("View state changed: {:?}", view);
-                    self.selectable_list = view.selectable().map(SelectableList::from)
+                match self.runner.update_view() {
+                    Ok(Some(view)) => {
+                        tracing::trace!("View state changed: {:?}", view);
+                        self.selectable_list = view.selectable().map(SelectableList::from)
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        tracing::error!("Runner error: {}", e);
+                        self.should_quit = true;
+                    }
                 }
                 None
             }
@@ -108,9 +112,14 @@ where
                         .expect("selectable_list must be present when confirming selectable"),
                     _ => unreachable!(),
                 };
-                self.runner
-                    .dispatch(runner_action)
-                    .then_some(Action::CheckView)
+                match self.runner.dispatch(runner_action) {
+                    Ok(handled) => handled.then_some(Action::CheckView),
+                    Err(e) => {
+                        tracing::error!("Runner error: {}", e);
+                        self.should_quit = true;
+                        None
+                    }
+                }
             }
         }
     }
@@ -140,7 +149,7 @@ where
         }
     }
 
-    pub async fn run(&'engine mut self) -> Result<()> {
+    pub async fn run(&mut self) -> Result<()> {
         let mut tui = tui::Tui::new()?.tick_rate(4.0).frame_rate(60.0);
 
         tui.enter()?;
@@ -17,10 +17,7 @@ impl ChoiceList {
 
 impl Into<Vec<ListItem<'_>>> for &ChoiceList {
     fn into(self) -> Vec<ListItem<'static>> {
-        self.items
-            .iter()
-            .map(ListItem::from)
-            .collect::<Vec<_>>()
+        self.items.iter().map(ListItem::from).collect::<Vec<_>>()
     }
 }
 
@@ -4,7 +4,7 @@ use ratatui::layout::{Alignment, Constraint, Layout, Rect};
 use ratatui::style::Stylize;
 use ratatui::widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap};
 
-impl Widget for &mut App<'_, '_> {
+impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let [name_area, dialogue_area, log_area, help_area] = Layout::vertical(
             [
@@ -47,7 +47,7 @@ impl Widget for &mut App<'_, '_> {
     }
 }
 
-impl App<'_, '_> {
+impl App {
     fn render_name(&self, area: Rect, name: &str, buf: &mut Buffer) {
         let block = Block::new()
             .borders(Borders::ALL)
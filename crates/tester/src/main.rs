@@ -5,6 +5,8 @@ use diavolo_tester::{App, CollectorLayer, LOG_COLLECTOR};
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
+use std::sync::Arc;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::registry()
@@ -30,9 +32,9 @@ async fn main() -> Result<()> {
         // .args(args)
         .build();
     // let dialogue_ctx: DialogueCtx = DialogueCtx::builder().build();
-    let mut store: Store<'_> = Store::new(&engine, diavolo::Data::with_ctx(dialogue_ctx));
+    let store: Store = Store::new(&engine, diavolo::Data::with_ctx(dialogue_ctx));
     let dialogue: Dialogue = RAW_DIALOGUE.parse()?;
-    let runner: Runner<'_, '_> = Runner::instantiate(&mut store, &dialogue).unwrap();
+    let runner: Runner = Runner::instantiate(&store, Arc::new(dialogue)).unwrap();
 
     App::new(runner).run().await
 }